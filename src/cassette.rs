@@ -0,0 +1,176 @@
+// Record/replay layer for LLM calls.
+//
+// A cassette is a JSON file of (provider, model, prompt, reply) interactions. `CassetteProvider`
+// wraps the routed provider: in `record` mode every call goes to the provider and is saved; in
+// `replay` mode a call with a recorded model + prompt is answered from the cassette and anything
+// new is passed through and recorded; `strict` mode never calls the provider and fails on
+// unrecorded calls, so CI notices when a prompt change invalidates the recordings.
+
+use crate::llm::{Capabilities, CostModel, LlmProvider, LlmRequest};
+use crate::mock_llm::prompt_hash;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Environment variable naming the cassette file.
+pub const CASSETTE_ENV: &str = "GRAPHFLOW_LLM_CASSETTE";
+/// Environment variable selecting the cassette mode (default: replay).
+pub const CASSETTE_MODE_ENV: &str = "GRAPHFLOW_LLM_CASSETTE_MODE";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CassetteMode {
+    Record,
+    Replay,
+    Strict,
+}
+
+impl CassetteMode {
+    pub fn parse(s: &str) -> Result<CassetteMode, String> {
+        match s.to_ascii_lowercase().as_str() {
+            "record" => Ok(CassetteMode::Record),
+            "replay" | "" => Ok(CassetteMode::Replay),
+            "strict" => Ok(CassetteMode::Strict),
+            other => Err(format!("Unknown cassette mode `{}` (expected record, replay or strict)", other)),
+        }
+    }
+}
+
+/// Where the cassette lives and how it is used.
+#[derive(Debug, Clone)]
+pub struct CassetteConfig {
+    pub path: PathBuf,
+    pub mode: CassetteMode,
+}
+
+impl CassetteConfig {
+    /// The cassette from `GRAPHFLOW_LLM_CASSETTE` / `GRAPHFLOW_LLM_CASSETTE_MODE`, if set.
+    pub fn from_env() -> Result<Option<CassetteConfig>, String> {
+        match env::var(CASSETTE_ENV) {
+            Ok(path) if !path.is_empty() => {
+                let mode = CassetteMode::parse(&env::var(CASSETTE_MODE_ENV).unwrap_or_default())?;
+                Ok(Some(CassetteConfig { path: PathBuf::from(path), mode }))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+/// One recorded call. The prompt is stored in full so cassette diffs show what changed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub provider: String,
+    pub model: String,
+    pub prompt: String,
+    pub reply: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    /// The cassette at `path`; a missing file is an empty cassette.
+    pub fn load(path: &Path) -> Result<Cassette, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| format!("Invalid cassette {}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Cassette::default()),
+            Err(e) => Err(format!("Cannot read cassette {}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn find(&self, model: &str, prompt: &str) -> Option<&Interaction> {
+        self.interactions.iter().find(|i| i.model == model && i.prompt == prompt)
+    }
+
+    // Add or replace the interaction for its model + prompt, re-reading the file under a lock so
+    // concurrent requests recording into one cassette do not drop each other's entries
+    fn save(path: &Path, interaction: Interaction) -> Result<(), String> {
+        static WRITE: Mutex<()> = Mutex::new(());
+        let _guard = WRITE.lock().map_err(|_| "Cassette lock poisoned".to_string())?;
+        let mut cassette = Cassette::load(path)?;
+        match cassette.interactions.iter_mut().find(|i| i.model == interaction.model && i.prompt == interaction.prompt) {
+            Some(existing) => *existing = interaction,
+            None => cassette.interactions.push(interaction),
+        }
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+        }
+        let text = serde_json::to_string_pretty(&cassette).map_err(|e| e.to_string())?;
+        fs::write(path, text + "\n").map_err(|e| format!("Cannot write cassette {}: {}", path.display(), e))
+    }
+}
+
+/// A provider answering from (and recording into) a cassette. `inner` is the real provider, or
+/// why it could not be created; it is only needed for calls the cassette cannot answer.
+pub struct CassetteProvider {
+    pub config: CassetteConfig,
+    pub provider: String,
+    pub inner: Result<Arc<dyn LlmProvider>, String>,
+}
+
+impl CassetteProvider {
+    fn inner(&self) -> Result<&Arc<dyn LlmProvider>, String> {
+        self.inner.as_ref().map_err(|e| e.clone())
+    }
+
+    // Recorded reply for `req`, if the mode allows replaying it
+    fn replay(&self, req: &LlmRequest) -> Result<Option<String>, String> {
+        if self.config.mode == CassetteMode::Record {
+            return Ok(None);
+        }
+        let cassette = Cassette::load(&self.config.path)?;
+        match cassette.find(&req.model, &req.prompt) {
+            Some(hit) => Ok(Some(hit.reply.clone())),
+            None if self.config.mode == CassetteMode::Strict => Err(format!(
+                "Unrecorded LLM call in strict replay: model `{}`, prompt {} (cassette {})",
+                req.model,
+                prompt_hash(&req.prompt),
+                self.config.path.display()
+            )),
+            None => Ok(None),
+        }
+    }
+
+    fn record(&self, req: &LlmRequest, reply: &str) -> Result<(), String> {
+        let interaction = Interaction { provider: self.provider.clone(), model: req.model.clone(), prompt: req.prompt.clone(), reply: reply.to_string() };
+        Cassette::save(&self.config.path, interaction)
+    }
+}
+
+#[async_trait]
+impl LlmProvider for CassetteProvider {
+    fn capabilities(&self) -> Capabilities {
+        match &self.inner {
+            Ok(inner) => inner.capabilities(),
+            Err(_) => Capabilities { streaming: false, max_output_tokens: u32::MAX, structured_output: false },
+        }
+    }
+
+    fn cost_model(&self) -> CostModel {
+        self.inner.as_ref().map(|p| p.cost_model()).unwrap_or_default()
+    }
+
+    async fn complete(&self, req: &LlmRequest) -> Result<String, String> {
+        if let Some(reply) = self.replay(req)? {
+            return Ok(reply);
+        }
+        let reply = self.inner()?.complete(req).await?;
+        self.record(req, &reply)?;
+        Ok(reply)
+    }
+
+    async fn stream(&self, req: &LlmRequest, on_delta: &mut (dyn for<'s> FnMut(&'s str) + Send)) -> Result<String, String> {
+        if let Some(reply) = self.replay(req)? {
+            on_delta(&reply);
+            return Ok(reply);
+        }
+        let reply = self.inner()?.stream(req, on_delta).await?;
+        self.record(req, &reply)?;
+        Ok(reply)
+    }
+}
@@ -0,0 +1,549 @@
+use crate::state::{Container, EdgeData, EdgeStyle, GlobalStyle, GraphData, LayoutHints, NodeData, NodeStyle};
+use std::collections::{HashMap, HashSet};
+
+// Deterministic Graphviz DOT import/export (no LLM involved).
+// Supported: [strict] graph/digraph, node/edge statements with attribute lists, node/edge/graph
+// default attributes, `rankdir`, edge chains and `{a b} -> c` groups, `subgraph cluster_*` as containers.
+
+/// Pull the DOT source out of a ```dot / ```graphviz fence, or take the text as-is.
+fn dot_source(text: &str) -> &str {
+    for fence in ["```dot", "```graphviz", "```gv"] {
+        if let Some(start) = text.find(fence) {
+            let body = &text[start + fence.len()..];
+            let end = body.find("```").unwrap_or(body.len());
+            return &body[..end];
+        }
+    }
+    text
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Id(String),
+    Punct(char),
+    EdgeOp,
+}
+
+// Strip comments and split into tokens; quoted/HTML strings become a single Id
+fn tokenize(src: &str) -> Vec<Tok> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut toks = Vec::new();
+    let mut i = 0;
+    let mut line_start = true;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\n' { line_start = true; i += 1; continue; }
+        if c.is_whitespace() { i += 1; continue; }
+        // `#` lines are C preprocessor output and ignored by Graphviz
+        if line_start && c == '#' {
+            while i < chars.len() && chars[i] != '\n' { i += 1; }
+            continue;
+        }
+        line_start = false;
+        if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' { i += 1; }
+            continue;
+        }
+        if c == '/' && chars.get(i + 1) == Some(&'*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) { i += 1; }
+            i += 2;
+            continue;
+        }
+        if c == '-' && matches!(chars.get(i + 1), Some('>') | Some('-')) {
+            toks.push(Tok::EdgeOp);
+            i += 2;
+            continue;
+        }
+        if c == '"' {
+            let mut s = String::new();
+            i += 1;
+            while i < chars.len() && chars[i] != '"' {
+                if chars[i] == '\\' && i + 1 < chars.len() {
+                    match chars[i + 1] {
+                        '"' => s.push('"'),
+                        '\n' => {}
+                        'n' | 'l' | 'r' => s.push('\n'),
+                        other => { s.push('\\'); s.push(other); }
+                    }
+                    i += 2;
+                    continue;
+                }
+                s.push(chars[i]);
+                i += 1;
+            }
+            i += 1;
+            toks.push(Tok::Id(s));
+            continue;
+        }
+        if c == '<' {
+            // HTML-like label: keep the inner markup as text
+            let start = i;
+            let mut depth = 0;
+            let mut end = None;
+            for (j, &ch) in chars.iter().enumerate().skip(start) {
+                if ch == '<' { depth += 1; }
+                if ch == '>' { depth -= 1; }
+                if depth == 0 { end = Some(j); break; }
+            }
+            // An unclosed `<` is not a label; skip it like other stray characters
+            let Some(end) = end else { i += 1; continue };
+            let inner: String = chars[start + 1..end].iter().collect();
+            toks.push(Tok::Id(strip_html(&inner)));
+            i = end + 1;
+            continue;
+        }
+        if matches!(c, '{' | '}' | '[' | ']' | ';' | ',' | '=' | ':' | '+') {
+            toks.push(Tok::Punct(c));
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < chars.len() && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '.')) {
+            i += 1;
+        }
+        // Numerals may start with '-'
+        if i == start && c == '-' {
+            i += 1;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') { i += 1; }
+        }
+        if i == start { i += 1; continue; }
+        toks.push(Tok::Id(chars[start..i].iter().collect()));
+    }
+    // Merge "a" + "b" into one string
+    let mut out: Vec<Tok> = Vec::new();
+    let mut k = 0;
+    while k < toks.len() {
+        if toks[k] == Tok::Punct('+') {
+            if let (Some(Tok::Id(prev)), Some(Tok::Id(next))) = (out.last_mut(), toks.get(k + 1)) {
+                prev.push_str(next);
+                k += 2;
+                continue;
+            }
+        }
+        out.push(toks[k].clone());
+        k += 1;
+    }
+    out
+}
+
+fn strip_html(s: &str) -> String {
+    let mut out = String::new();
+    let mut in_tag = false;
+    for c in s.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out.replace("&amp;", "&").replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").trim().to_string()
+}
+
+fn is_keyword(s: &str) -> bool {
+    matches!(s.to_lowercase().as_str(), "graph" | "digraph" | "subgraph" | "node" | "edge" | "strict")
+}
+
+// Header: [strict] (graph|digraph) [ID] {  ->  Some(directed)
+fn header(toks: &[Tok]) -> Option<(bool, usize)> {
+    let mut i = 0;
+    let kw = |t: Option<&Tok>, w: &str| matches!(t, Some(Tok::Id(s)) if s.eq_ignore_ascii_case(w));
+    if kw(toks.get(i), "strict") { i += 1; }
+    let directed = if kw(toks.get(i), "digraph") { true } else if kw(toks.get(i), "graph") { false } else { return None };
+    i += 1;
+    if let Some(Tok::Id(_)) = toks.get(i) { i += 1; }
+    if toks.get(i) != Some(&Tok::Punct('{')) { return None; }
+    Some((directed, i + 1))
+}
+
+/// True when the text is (or contains a fenced) Graphviz DOT graph.
+pub fn is_dot_graph(text: &str) -> bool {
+    header(&tokenize(dot_source(text))).is_some()
+}
+
+type Attrs = HashMap<String, String>;
+
+#[derive(Clone, Default)]
+struct Scope {
+    node: Attrs,
+    edge: Attrs,
+    container: Option<usize>,
+}
+
+#[derive(Default)]
+struct Builder {
+    directed: bool,
+    toks: Vec<Tok>,
+    pos: usize,
+    nodes: Vec<NodeData>,
+    index: HashMap<String, usize>,
+    edges: Vec<EdgeData>,
+    containers: Vec<Container>,
+    placed: HashSet<String>,
+    graph_attrs: Attrs,
+}
+
+impl Builder {
+    fn peek(&self) -> Option<&Tok> { self.toks.get(self.pos) }
+    fn eat(&mut self, t: &Tok) -> bool {
+        if self.peek() == Some(t) { self.pos += 1; true } else { false }
+    }
+    fn next_id(&mut self) -> Option<String> {
+        match self.peek() {
+            Some(Tok::Id(s)) => { let s = s.clone(); self.pos += 1; Some(s) }
+            _ => None,
+        }
+    }
+
+    // [a=b, c=d][e=f]
+    fn attr_list(&mut self) -> Attrs {
+        let mut attrs = Attrs::new();
+        while self.eat(&Tok::Punct('[')) {
+            while !self.eat(&Tok::Punct(']')) {
+                if self.peek().is_none() { return attrs; }
+                let Some(k) = self.next_id() else { self.pos += 1; continue };
+                if self.eat(&Tok::Punct('=')) {
+                    if let Some(v) = self.next_id() { attrs.insert(k.to_lowercase(), v); }
+                }
+                let _ = self.eat(&Tok::Punct(',')) || self.eat(&Tok::Punct(';'));
+            }
+        }
+        attrs
+    }
+
+    fn touch(&mut self, id: &str, scope: &Scope, attrs: Option<&Attrs>) {
+        let idx = match self.index.get(id) {
+            Some(i) => *i,
+            None => {
+                self.nodes.push(NodeData {
+                    id: id.to_string(),
+                    label: id.to_string(),
+                    x: 0.0,
+                    y: 0.0,
+                    style: NodeStyle { shape: "rectangle".to_string(), color: String::new() },
+                });
+                let idx = self.nodes.len() - 1;
+                self.index.insert(id.to_string(), idx);
+                // Scope defaults apply when the node is created
+                apply_node_attrs(&mut self.nodes[idx], &scope.node);
+                idx
+            }
+        };
+        if let Some(attrs) = attrs { apply_node_attrs(&mut self.nodes[idx], attrs); }
+        // First cluster that mentions a node owns it
+        if let Some(ci) = scope.container {
+            if self.placed.insert(id.to_string()) {
+                self.containers[ci].children.push(id.to_string());
+            }
+        }
+    }
+
+    fn add_edge(&mut self, source: &str, target: &str, attrs: &Attrs) {
+        let base = format!("{}_{}", source, target);
+        let mut id = base.clone();
+        let mut k = 2;
+        while self.edges.iter().any(|e| e.id == id) {
+            id = format!("{}_{}", base, k);
+            k += 1;
+        }
+        let line = match attrs.get("style").map(|s| s.to_lowercase()) {
+            Some(s) if s.contains("dashed") => "dashed",
+            Some(s) if s.contains("dotted") => "dotted",
+            Some(s) if s.contains("bold") || s.contains("penwidth") => "thick",
+            Some(s) if s.contains("invis") => return,
+            _ => "solid",
+        };
+        let dir = attrs.get("dir").map(|d| d.to_lowercase()).unwrap_or_else(|| if self.directed { "forward" } else { "none" }.to_string());
+        let head = attrs.get("arrowhead").map(|a| a.to_lowercase());
+        let arrow = match (dir.as_str(), head.as_deref()) {
+            ("none", _) => "none",
+            ("both", _) => "both",
+            ("back", _) => "start",
+            (_, Some("none")) => "none",
+            (_, Some(h)) if h.contains("dot") => "dot",
+            (_, Some(h)) if h.contains("tee") => "bar",
+            _ => "end",
+        };
+        self.edges.push(EdgeData {
+            id,
+            source: source.to_string(),
+            target: target.to_string(),
+            label: attrs.get("label").or_else(|| attrs.get("xlabel")).cloned().unwrap_or_default(),
+            style: EdgeStyle { line: line.to_string(), arrow: arrow.to_string() },
+        });
+    }
+
+    // node_id [: port [: compass]]
+    fn node_id(&mut self) -> Option<String> {
+        let id = self.next_id()?;
+        while self.eat(&Tok::Punct(':')) { let _ = self.next_id(); }
+        Some(id)
+    }
+
+    // Returns the node ids mentioned by an edge operand (a node or a subgraph)
+    fn operand(&mut self, scope: &Scope) -> Option<Vec<String>> {
+        match self.peek() {
+            Some(Tok::Punct('{')) => Some(self.subgraph(scope, None)),
+            Some(Tok::Id(s)) if s.eq_ignore_ascii_case("subgraph") => {
+                self.pos += 1;
+                let name = match self.peek() { Some(Tok::Id(_)) => self.next_id(), _ => None };
+                Some(self.subgraph(scope, name))
+            }
+            Some(Tok::Id(_)) => {
+                let id = self.node_id()?;
+                self.touch(&id, scope, None);
+                Some(vec![id])
+            }
+            _ => None,
+        }
+    }
+
+    // { stmt_list }; returns all node ids mentioned inside
+    fn subgraph(&mut self, parent: &Scope, name: Option<String>) -> Vec<String> {
+        let mut scope = parent.clone();
+        if let Some(name) = name.as_deref() {
+            if let Some(rest) = name.strip_prefix("cluster") {
+                let id = rest.trim_start_matches('_');
+                let id = if id.is_empty() { format!("cluster_{}", self.containers.len()) } else { id.to_string() };
+                self.containers.push(Container { id: id.clone(), label: id, children: Vec::new(), style: None });
+                scope.container = Some(self.containers.len() - 1);
+            }
+        }
+        if !self.eat(&Tok::Punct('{')) { return Vec::new(); }
+        let mut mentioned = Vec::new();
+        while self.peek().is_some() && !self.eat(&Tok::Punct('}')) {
+            self.statement(&mut scope, &mut mentioned);
+        }
+        mentioned
+    }
+
+    fn statement(&mut self, scope: &mut Scope, mentioned: &mut Vec<String>) {
+        if self.eat(&Tok::Punct(';')) || self.eat(&Tok::Punct(',')) { return; }
+        // attr_stmt: graph|node|edge [..]
+        if let Some(Tok::Id(kw)) = self.peek() {
+            let kw = kw.to_lowercase();
+            if matches!(kw.as_str(), "graph" | "node" | "edge") && self.toks.get(self.pos + 1) == Some(&Tok::Punct('[')) {
+                self.pos += 1;
+                let attrs = self.attr_list();
+                match kw.as_str() {
+                    "node" => scope.node.extend(attrs),
+                    "edge" => scope.edge.extend(attrs),
+                    _ => self.graph_attr(scope, attrs),
+                }
+                return;
+            }
+            // ID = ID
+            if self.toks.get(self.pos + 1) == Some(&Tok::Punct('=')) {
+                let k = self.next_id().unwrap_or_default();
+                self.pos += 1;
+                let v = self.next_id().unwrap_or_default();
+                self.graph_attr(scope, Attrs::from([(k.to_lowercase(), v)]));
+                return;
+            }
+        }
+
+        let Some(first) = self.operand(scope) else { self.pos += 1; return };
+        mentioned.extend(first.iter().cloned());
+        if self.peek() != Some(&Tok::EdgeOp) {
+            // node_stmt (a lone subgraph has no attribute list)
+            if first.len() == 1 && self.peek() == Some(&Tok::Punct('[')) {
+                let attrs = self.attr_list();
+                self.touch(&first[0], scope, Some(&attrs));
+            }
+            return;
+        }
+        let mut chain = vec![first];
+        while self.eat(&Tok::EdgeOp) {
+            match self.operand(scope) {
+                Some(ids) => { mentioned.extend(ids.iter().cloned()); chain.push(ids); }
+                None => break,
+            }
+        }
+        let mut attrs = scope.edge.clone();
+        attrs.extend(self.attr_list());
+        for pair in chain.windows(2) {
+            for s in &pair[0] {
+                for t in &pair[1] { self.add_edge(s, t, &attrs); }
+            }
+        }
+    }
+
+    fn graph_attr(&mut self, scope: &Scope, attrs: Attrs) {
+        match scope.container {
+            Some(ci) => {
+                if let Some(label) = attrs.get("label") { self.containers[ci].label = label.clone(); }
+                let fill = attrs.get("fillcolor").or_else(|| attrs.get("bgcolor"));
+                if fill.is_some() || attrs.contains_key("color") || attrs.contains_key("pencolor") {
+                    let style = self.containers[ci].style.get_or_insert_with(Default::default);
+                    if let Some(fill) = fill { style.bg = Some(fill.clone()); }
+                    if let Some(border) = attrs.get("pencolor").or_else(|| attrs.get("color")) { style.border = Some(border.clone()); }
+                }
+            }
+            None => self.graph_attrs.extend(attrs),
+        }
+    }
+
+    fn finish(self) -> GraphData {
+        let direction = match self.graph_attrs.get("rankdir").map(|d| d.to_uppercase()).as_deref() {
+            Some("LR") => "LR",
+            Some("RL") => "RL",
+            Some("BT") => "BT",
+            _ => "TB",
+        };
+        let background = self.graph_attrs.get("bgcolor").cloned().unwrap_or_else(|| "#ffffff".to_string());
+        GraphData {
+            nodes: self.nodes,
+            edges: self.edges,
+            layout_hints: Some(LayoutHints { direction: direction.to_string(), algorithm: "longest_path".to_string(), ..Default::default() }),
+            global_style: Some(GlobalStyle { font: "Inter".to_string(), background, theme: Some("minimal".to_string()) }),
+            decorations: None,
+            containers: if self.containers.is_empty() { None } else { Some(self.containers) },
+            sequence: None,
+        }
+    }
+}
+
+fn apply_node_attrs(n: &mut NodeData, attrs: &Attrs) {
+    if let Some(label) = attrs.get("label") {
+        // `\N` is Graphviz's "node name" placeholder
+        n.label = label.replace("\\N", &n.id);
+    }
+    let rounded = attrs.get("style").map(|s| s.to_lowercase().contains("rounded")).unwrap_or(false);
+    if let Some(shape) = attrs.get("shape") {
+        n.style.shape = match shape.to_lowercase().as_str() {
+            "box" | "rect" | "rectangle" | "square" | "record" | "mrecord" | "plain" | "plaintext" | "none" => "rectangle",
+            "ellipse" | "oval" => "ellipse",
+            "circle" | "doublecircle" | "point" => "circle",
+            "diamond" | "mdiamond" => "diamond",
+            "cylinder" => "cylinder",
+            "hexagon" => "hexagon",
+            "parallelogram" => "parallelogram",
+            _ => "rectangle",
+        }
+        .to_string();
+    }
+    if rounded && n.style.shape == "rectangle" { n.style.shape = "rounded".to_string(); }
+    let filled = attrs.get("style").map(|s| s.to_lowercase().contains("filled")).unwrap_or(false);
+    if let Some(fill) = attrs.get("fillcolor") {
+        n.style.color = fill.clone();
+    } else if let (true, Some(color)) = (filled, attrs.get("color")) {
+        n.style.color = color.clone();
+    }
+}
+
+/// Parse a Graphviz DOT graph (optionally inside a ```dot fence) into GraphData.
+/// Returns None when the text is not a DOT graph.
+pub fn parse_dot(text: &str) -> Option<GraphData> {
+    let toks = tokenize(dot_source(text));
+    let (directed, start) = header(&toks)?;
+    let mut b = Builder { directed, toks, pos: start, ..Default::default() };
+    let mut scope = Scope::default();
+    let mut mentioned = Vec::new();
+    while b.peek().is_some() && !b.eat(&Tok::Punct('}')) {
+        b.statement(&mut scope, &mut mentioned);
+    }
+    if b.nodes.is_empty() { return None; }
+    Some(b.finish())
+}
+
+// Bare ids are [A-Za-z_][A-Za-z0-9_]* (non-keyword); everything else is quoted
+fn dot_id(s: &str) -> String {
+    let bare = s.chars().next().map(|c| c.is_ascii_alphabetic() || c == '_').unwrap_or(false)
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !is_keyword(s);
+    if bare { s.to_string() } else { dot_text(s) }
+}
+
+fn dot_text(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+
+fn dot_node(n: &NodeData) -> String {
+    let (shape, rounded) = match n.style.shape.to_lowercase().as_str() {
+        "rounded" | "stadium" => ("box", true),
+        "circle" => ("circle", false),
+        "ellipse" | "oval" => ("ellipse", false),
+        "diamond" | "decision" | "rhombus" => ("diamond", false),
+        "cylinder" | "database" | "db" => ("cylinder", false),
+        "hexagon" => ("hexagon", false),
+        "parallelogram" => ("parallelogram", false),
+        _ => ("box", false),
+    };
+    let mut attrs = vec![format!("label={}", dot_text(&n.label)), format!("shape={}", shape)];
+    let color = n.style.color.trim();
+    let style = match (rounded, color.is_empty()) {
+        (true, true) => Some("rounded"),
+        (true, false) => Some("\"rounded,filled\""),
+        (false, false) => Some("filled"),
+        _ => None,
+    };
+    if let Some(style) = style { attrs.push(format!("style={}", style)); }
+    if !color.is_empty() { attrs.push(format!("fillcolor={}", dot_text(color))); }
+    format!("{} [{}];", dot_id(&n.id), attrs.join(", "))
+}
+
+fn dot_edge_attrs(e: &EdgeData) -> Vec<String> {
+    let mut attrs = Vec::new();
+    if !e.label.is_empty() { attrs.push(format!("label={}", dot_text(&e.label))); }
+    match e.style.line.to_lowercase().as_str() {
+        "dashed" => attrs.push("style=dashed".to_string()),
+        "dotted" => attrs.push("style=dotted".to_string()),
+        "thick" | "bold" => attrs.push("style=bold".to_string()),
+        _ => {}
+    }
+    match e.style.arrow.to_lowercase().as_str() {
+        "none" => attrs.push("dir=none".to_string()),
+        "both" => attrs.push("dir=both".to_string()),
+        "start" => attrs.push("dir=back".to_string()),
+        "dot" | "circle" => attrs.push("arrowhead=dot".to_string()),
+        "bar" | "cross" => attrs.push("arrowhead=tee".to_string()),
+        _ => {}
+    }
+    attrs
+}
+
+/// Export GraphData as a Graphviz `digraph` that `parse_dot` reads back unchanged.
+pub fn graphdata_to_dot(g: &GraphData) -> String {
+    let dir = match g.layout_hints.as_ref().map(|h| h.direction.to_uppercase()).as_deref() {
+        Some("LR") => "LR",
+        Some("RL") => "RL",
+        Some("BT") => "BT",
+        _ => "TB",
+    };
+    let mut out = format!("digraph G {{\n    rankdir={};\n", dir);
+
+    let mut in_container: HashSet<&str> = HashSet::new();
+    for c in g.containers.iter().flatten() {
+        out.push_str(&format!("    subgraph {} {{\n", dot_id(&format!("cluster_{}", c.id))));
+        out.push_str(&format!("        label={};\n", dot_text(&c.label)));
+        if let Some(style) = &c.style {
+            if let Some(bg) = &style.bg { out.push_str(&format!("        style=filled;\n        fillcolor={};\n", dot_text(bg))); }
+            if let Some(border) = &style.border { out.push_str(&format!("        pencolor={};\n", dot_text(border))); }
+        }
+        for child in &c.children {
+            if in_container.contains(child.as_str()) { continue; }
+            if let Some(n) = g.nodes.iter().find(|n| &n.id == child) {
+                in_container.insert(child.as_str());
+                out.push_str(&format!("        {}\n", dot_node(n)));
+            }
+        }
+        out.push_str("    }\n");
+    }
+    for n in &g.nodes {
+        if in_container.contains(n.id.as_str()) { continue; }
+        out.push_str(&format!("    {}\n", dot_node(n)));
+    }
+
+    for e in &g.edges {
+        if !g.nodes.iter().any(|n| n.id == e.source) || !g.nodes.iter().any(|n| n.id == e.target) { continue; }
+        let attrs = dot_edge_attrs(e);
+        if attrs.is_empty() {
+            out.push_str(&format!("    {} -> {};\n", dot_id(&e.source), dot_id(&e.target)));
+        } else {
+            out.push_str(&format!("    {} -> {} [{}];\n", dot_id(&e.source), dot_id(&e.target), attrs.join(", ")));
+        }
+    }
+    out.push_str("}\n");
+    out
+}
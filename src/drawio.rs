@@ -0,0 +1,236 @@
+use crate::edge_style::{EdgeLook, Routing, Stroke};
+use crate::excalidraw::{builtin_emoji, resolve_decoration_asset};
+use crate::shapes::{node_box, Shape};
+use crate::state::{EdgeData, GraphData, NodeData};
+use base64::{engine::general_purpose as b64, Engine as _};
+use std::collections::HashMap;
+use std::fs;
+
+// draw.io / diagrams.net export: an uncompressed mxGraphModel inside an <mxfile>.
+// Node positions are centers (as produced by apply_auto_layout); draw.io geometry is top-left,
+// and children of a container are positioned relative to the container.
+
+const CONTAINER_PADDING: f64 = 40.0;
+const MARGIN: f64 = 40.0;
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\n', "&#xa;")
+}
+
+fn color_or(default_hex: &str, c: &str) -> String {
+    if c.trim().is_empty() { default_hex.to_string() } else { c.trim().to_string() }
+}
+
+fn node_style(n: &NodeData, font: &str) -> String {
+    let shape = match Shape::parse(&n.style.shape) {
+        Shape::Rounded => "rounded=1;",
+        Shape::Ellipse => "ellipse;",
+        Shape::Circle => "ellipse;aspect=fixed;",
+        Shape::Diamond => "rhombus;",
+        Shape::Cylinder => "shape=cylinder3;boundedLbl=1;size=10;",
+        Shape::Hexagon => "shape=hexagon;perimeter=hexagonPerimeter2;",
+        Shape::Parallelogram => "shape=parallelogram;perimeter=parallelogramPerimeter;",
+        Shape::Document => "shape=document;boundedLbl=1;",
+        Shape::Actor => "shape=umlActor;verticalLabelPosition=bottom;verticalAlign=top;",
+        Shape::Rectangle => "rounded=0;",
+    };
+    format!(
+        "{}whiteSpace=wrap;fillColor={};strokeColor=#111827;fontColor=#111827;fontFamily={};",
+        shape,
+        color_or("#F3F4F6", &n.style.color),
+        font
+    )
+}
+
+fn edge_style(e: &EdgeData) -> String {
+    let look = EdgeLook::of(&e.style);
+    let mut style = String::from(match look.routing {
+        Routing::Orthogonal => "edgeStyle=orthogonalEdgeStyle;rounded=1;",
+        Routing::Curved => "curved=1;",
+        Routing::Straight => "",
+    });
+    match look.stroke {
+        Stroke::Dashed => style.push_str("dashed=1;"),
+        Stroke::Dotted => style.push_str("dashed=1;dashPattern=1 4;"),
+        Stroke::Solid => {}
+    }
+    if look.width > 2.0 { style.push_str("strokeWidth=3;"); }
+    let head = |h: Option<&str>| match h {
+        None => "none",
+        Some("triangle") => "block",
+        Some("bar") => "dash",
+        Some("dot") => "oval",
+        Some(_) => "classic",
+    };
+    style.push_str(&format!("startArrow={};endArrow={};html=1;strokeColor=#111827;", head(look.start_head), head(look.end_head)));
+    style
+}
+
+struct Rect {
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+}
+
+enum DecorContent {
+    Image(String),
+    Text(String),
+}
+
+/// Export GraphData as an uncompressed `.drawio` file. With `allow_images`, decorations whose
+/// asset resolves under `assets_dir` are embedded as image cells; others become text cells.
+pub fn graphdata_to_drawio_with_opts(g: &GraphData, allow_images: bool, assets_dir: &str) -> String {
+    let font = g.global_style.as_ref().map(|s| s.font.clone()).filter(|f| !f.is_empty()).unwrap_or_else(|| "Helvetica".to_string());
+    let background = g.global_style.as_ref().map(|s| s.background.clone()).filter(|b| !b.is_empty()).unwrap_or_else(|| "#FFFFFF".to_string());
+
+    // Absolute node boxes
+    let mut boxes: HashMap<&str, Rect> = HashMap::new();
+    for n in &g.nodes {
+        let (w, h) = node_box(n);
+        boxes.insert(n.id.as_str(), Rect { x: n.x as f64 - w / 2.0, y: n.y as f64 - h / 2.0, w, h });
+    }
+
+    // Containers: bbox of children + padding; a node belongs to the first container listing it
+    let mut parent_of: HashMap<&str, usize> = HashMap::new();
+    let mut container_boxes: Vec<Option<Rect>> = Vec::new();
+    for (ci, c) in g.containers.iter().flatten().enumerate() {
+        let (mut minx, mut miny, mut maxx, mut maxy) = (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for id in &c.children {
+            if let Some(b) = boxes.get(id.as_str()) {
+                parent_of.entry(id.as_str()).or_insert(ci);
+                minx = minx.min(b.x);
+                miny = miny.min(b.y);
+                maxx = maxx.max(b.x + b.w);
+                maxy = maxy.max(b.y + b.h);
+            }
+        }
+        container_boxes.push(minx.is_finite().then_some(Rect {
+            x: minx - CONTAINER_PADDING,
+            y: miny - CONTAINER_PADDING,
+            w: maxx - minx + 2.0 * CONTAINER_PADDING,
+            h: maxy - miny + 2.0 * CONTAINER_PADDING,
+        }));
+    }
+
+    // Decorations: embedded image when available, otherwise emoji/text
+    let mut decorations: Vec<(Rect, DecorContent)> = Vec::new();
+    for d in g.decorations.iter().flatten() {
+        let mut cx = d.at_x.unwrap_or(0.0) as f64;
+        let mut cy = d.at_y.unwrap_or(0.0) as f64;
+        if let Some(n) = d.target.as_ref().and_then(|t| g.nodes.iter().find(|n| &n.id == t)) {
+            cx = n.x as f64;
+            cy = n.y as f64;
+        }
+        if let Some(off) = &d.offset {
+            cx += off.dx as f64;
+            cy += off.dy as f64;
+        }
+        let kind = d.r#type.to_lowercase();
+        let image = if allow_images && (kind == "image" || kind == "icon") {
+            resolve_decoration_asset(&d.builtin, &d.url, assets_dir)
+                .and_then(|(path, mime)| fs::read(path).ok().map(|bytes| (bytes, mime)))
+                // draw.io styles use ';' as separator, so data URIs drop the ";base64" marker
+                .map(|(bytes, mime)| format!("data:{},{}", mime, b64::STANDARD.encode(bytes)))
+        } else {
+            None
+        };
+        match image {
+            Some(data) => {
+                let (w, h) = d.size.as_ref().map(|s| (s.w as f64, s.h as f64)).unwrap_or((24.0, 24.0));
+                decorations.push((Rect { x: cx - w / 2.0, y: cy - h / 2.0, w, h }, DecorContent::Image(data)));
+            }
+            None => {
+                let label = if let Some(b) = &d.builtin { builtin_emoji(b).to_string() } else { d.text.clone().unwrap_or_default() };
+                if label.is_empty() { continue; }
+                let (w, h) = d.size.as_ref().map(|s| (s.w as f64, s.h as f64)).unwrap_or((20.0, 20.0));
+                decorations.push((Rect { x: cx - w / 2.0, y: cy - h / 2.0, w, h }, DecorContent::Text(label)));
+            }
+        }
+    }
+
+    // Shift everything so the drawing starts at (MARGIN, MARGIN)
+    let mut minx = f64::INFINITY;
+    let mut miny = f64::INFINITY;
+    for r in boxes.values().chain(container_boxes.iter().flatten()).chain(decorations.iter().map(|(r, _)| r)) {
+        minx = minx.min(r.x);
+        miny = miny.min(r.y);
+    }
+    let (dx, dy) = if minx.is_finite() { (MARGIN - minx, MARGIN - miny) } else { (0.0, 0.0) };
+
+    let geometry = |x: f64, y: f64, w: f64, h: f64| {
+        format!("<mxGeometry x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" as=\"geometry\"/>", x, y, w, h)
+    };
+
+    let mut cells = String::new();
+    for (c, r) in g.containers.iter().flatten().zip(&container_boxes) {
+        let Some(r) = r else { continue };
+        let style = c.style.as_ref();
+        let fill = style.and_then(|s| s.bg.clone()).unwrap_or_else(|| "#FFFFFF".to_string());
+        let border = style.and_then(|s| s.border.clone()).unwrap_or_else(|| "#D1D5DB".to_string());
+        let label = style.and_then(|s| s.label_tag.clone()).unwrap_or_else(|| c.label.clone());
+        cells.push_str(&format!(
+            "        <mxCell id=\"{}\" value=\"{}\" style=\"rounded=1;arcSize=6;container=1;collapsible=0;whiteSpace=wrap;verticalAlign=top;align=left;spacingLeft=8;fillColor={};strokeColor={};fontColor=#374151;fontFamily={};\" vertex=\"1\" parent=\"1\">\n            {}\n        </mxCell>\n",
+            xml_escape(&format!("container-{}", c.id)),
+            xml_escape(&label),
+            xml_escape(&fill),
+            xml_escape(&border),
+            xml_escape(&font),
+            geometry(r.x + dx, r.y + dy, r.w, r.h)
+        ));
+    }
+
+    let containers: Vec<_> = g.containers.iter().flatten().collect();
+    for n in &g.nodes {
+        let b = &boxes[n.id.as_str()];
+        // Children are positioned relative to their container
+        let (parent, x, y) = match parent_of.get(n.id.as_str()).and_then(|ci| container_boxes[*ci].as_ref().map(|r| (*ci, r))) {
+            Some((ci, r)) => (format!("container-{}", containers[ci].id), b.x - r.x, b.y - r.y),
+            None => ("1".to_string(), b.x + dx, b.y + dy),
+        };
+        cells.push_str(&format!(
+            "        <mxCell id=\"{}\" value=\"{}\" style=\"{}\" vertex=\"1\" parent=\"{}\">\n            {}\n        </mxCell>\n",
+            xml_escape(&format!("node-{}", n.id)),
+            xml_escape(&n.label),
+            xml_escape(&node_style(n, &font)),
+            xml_escape(&parent),
+            geometry(x, y, b.w, b.h)
+        ));
+    }
+
+    for e in &g.edges {
+        if !boxes.contains_key(e.source.as_str()) || !boxes.contains_key(e.target.as_str()) { continue; }
+        cells.push_str(&format!(
+            "        <mxCell id=\"{}\" value=\"{}\" style=\"{}\" edge=\"1\" parent=\"1\" source=\"{}\" target=\"{}\">\n            <mxGeometry relative=\"1\" as=\"geometry\"/>\n        </mxCell>\n",
+            xml_escape(&format!("edge-{}", e.id)),
+            xml_escape(&e.label),
+            xml_escape(&edge_style(e)),
+            xml_escape(&format!("node-{}", e.source)),
+            xml_escape(&format!("node-{}", e.target))
+        ));
+    }
+
+    for (i, (r, content)) in decorations.iter().enumerate() {
+        let (value, style) = match content {
+            DecorContent::Image(data) => (String::new(), format!("shape=image;imageAspect=1;aspect=fixed;image={};", data)),
+            DecorContent::Text(text) => (text.clone(), "text;align=center;verticalAlign=middle;".to_string()),
+        };
+        cells.push_str(&format!(
+            "        <mxCell id=\"decor-{}\" value=\"{}\" style=\"{}\" vertex=\"1\" parent=\"1\">\n            {}\n        </mxCell>\n",
+            i,
+            xml_escape(&value),
+            xml_escape(&style),
+            geometry(r.x + dx, r.y + dy, r.w, r.h)
+        ));
+    }
+
+    format!(
+        "<mxfile host=\"GraphFlow\" type=\"device\">\n  <diagram id=\"graphflow\" name=\"Page-1\">\n    <mxGraphModel grid=\"1\" gridSize=\"10\" guides=\"1\" tooltips=\"1\" connect=\"1\" arrows=\"1\" fold=\"1\" page=\"0\" pageScale=\"1\" math=\"0\" shadow=\"0\" background=\"{}\">\n      <root>\n        <mxCell id=\"0\"/>\n        <mxCell id=\"1\" parent=\"0\"/>\n{}      </root>\n    </mxGraphModel>\n  </diagram>\n</mxfile>\n",
+        xml_escape(&background),
+        cells
+    )
+}
@@ -0,0 +1,93 @@
+use crate::state::EdgeStyle;
+
+// Edge styles: `EdgeStyle.line` and `EdgeStyle.arrow` are free-form words from the model or an
+// importer ("dashed", "smooth", "both triangle", "start-dot", ...). Tokens are combined, so
+// "curved dashed" is a dashed curve; anything unrecognized keeps the defaults
+// (orthogonal, solid, open arrowhead at the target).
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Routing {
+    Straight,
+    Orthogonal,
+    Curved,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stroke {
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EdgeLook {
+    pub routing: Routing,
+    pub stroke: Stroke,
+    pub width: f64,
+    /// Excalidraw arrowhead names ("arrow", "triangle", "bar", "dot"); `None` draws no head.
+    pub start_head: Option<&'static str>,
+    pub end_head: Option<&'static str>,
+}
+
+fn tokens(s: &str) -> impl Iterator<Item = String> + '_ {
+    s.split(|c: char| !c.is_alphanumeric()).filter(|t| !t.is_empty()).map(|t| t.to_lowercase())
+}
+
+fn routing_word(t: &str) -> Option<Routing> {
+    match t {
+        "straight" | "direct" | "line" => Some(Routing::Straight),
+        "orthogonal" | "ortho" | "elbow" | "step" => Some(Routing::Orthogonal),
+        "curved" | "curve" | "smooth" | "spline" | "bezier" => Some(Routing::Curved),
+        _ => None,
+    }
+}
+
+/// `line` with its routing word replaced by `routing` ("orthogonal dashed" -> "curved dashed"),
+/// keeping stroke and width words.
+pub fn with_routing(line: &str, routing: &str) -> String {
+    let rest: Vec<String> = tokens(line).filter(|t| routing_word(t).is_none()).collect();
+    std::iter::once(routing.to_string()).chain(rest).collect::<Vec<_>>().join(" ")
+}
+
+impl EdgeLook {
+    pub fn of(style: &EdgeStyle) -> EdgeLook {
+        let mut look = EdgeLook { routing: Routing::Orthogonal, stroke: Stroke::Solid, width: 2.0, start_head: None, end_head: Some("arrow") };
+        for t in tokens(&style.line) {
+            if let Some(routing) = routing_word(&t) {
+                look.routing = routing;
+                continue;
+            }
+            match t.as_str() {
+                "dashed" | "dash" => look.stroke = Stroke::Dashed,
+                "dotted" | "dot" => look.stroke = Stroke::Dotted,
+                "thick" | "bold" => look.width = 4.0,
+                _ => {}
+            }
+        }
+        let (mut start, mut end, mut head) = (false, true, "arrow");
+        for t in tokens(&style.arrow) {
+            match t.as_str() {
+                "none" | "no" => (start, end) = (false, false),
+                "start" | "back" | "reverse" => (start, end) = (true, false),
+                "end" | "forward" => (start, end) = (false, true),
+                "both" | "double" | "bidirectional" => (start, end) = (true, true),
+                "arrow" | "open" => head = "arrow",
+                "triangle" | "filled" | "block" => head = "triangle",
+                "bar" | "tee" | "cross" => head = "bar",
+                "dot" | "circle" => head = "dot",
+                _ => {}
+            }
+        }
+        look.start_head = start.then_some(head);
+        look.end_head = end.then_some(head);
+        look
+    }
+
+    pub fn stroke_style(&self) -> &'static str {
+        match self.stroke {
+            Stroke::Solid => "solid",
+            Stroke::Dashed => "dashed",
+            Stroke::Dotted => "dotted",
+        }
+    }
+}
@@ -1,18 +1,26 @@
 use crate::state::{GraphData};
+use crate::edge_style::{EdgeLook, Routing};
+use crate::labels::{place_labels, LabelRequest};
+use crate::routing::{container_boxes, route_edges};
+use crate::sequence::sequence_to_excalidraw_elements;
+use crate::shapes::{border_point, node_box, outline, shape_size, Shape, ACTOR_FIGURE_H};
+use crate::text::{edge_label, node_label, text_width, TextBlock, LABEL_FONT};
 use serde_json::{json, Value};
 use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
 use std::fs;
 use std::path::Path;
+use std::f64::consts::PI;
 use base64::{engine::general_purpose as b64, Engine as _};
 
-fn seed_from<T: Hash>(t: &T) -> u32 {
+pub(crate) fn seed_from<T: Hash>(t: &T) -> u32 {
     let mut s = DefaultHasher::new();
     t.hash(&mut s);
     (s.finish() & 0xFFFF_FFFF) as u32
 }
 
-fn builtin_emoji(name: &str) -> &str {
+pub(crate) fn builtin_emoji(name: &str) -> &str {
     match name.to_lowercase().as_str() {
         "salesperson" | "sales" => "🧑‍💼",
         "email" => "📨",
@@ -23,49 +31,17 @@ fn builtin_emoji(name: &str) -> &str {
     }
 }
 
-pub fn graphdata_to_excalidraw_scene_with_opts(g: &GraphData, allow_images: bool, assets_dir: &str) -> Value {
-    let mut scene = graphdata_to_excalidraw_scene(g);
-    if !allow_images { return scene; }
-
-    let decorations_opt = g.decorations.as_ref();
-    if decorations_opt.is_none() { return scene; }
-
-    // Helper: resolve an asset path from decoration
-    let resolve_asset = |builtin: &Option<String>, url: &Option<String>| -> Option<(String, String)> {
-        // Returns (abs_path, mime)
-        // Priority: url starting with "builtin:" -> assets_dir/name.ext; else builtin field; else url as relative path
-        if let Some(u) = url {
-            if let Some(rest) = u.strip_prefix("builtin:") {
-                let candidates = [
-                    format!("{}/{}.svg", assets_dir, rest),
-                    format!("{}/{}.png", assets_dir, rest),
-                    format!("{}/{}.jpg", assets_dir, rest),
-                    format!("{}/{}.jpeg", assets_dir, rest),
-                ];
-                for p in candidates.iter() {
-                    let path = Path::new(p);
-                    if path.exists() {
-                        let mime = if p.ends_with(".svg") { "image/svg+xml" } else if p.ends_with(".png") { "image/png" } else if p.ends_with(".jpg") || p.ends_with(".jpeg") { "image/jpeg" } else { "application/octet-stream" };
-                        return Some((path.to_string_lossy().to_string(), mime.to_string()));
-                    }
-                }
-            } else {
-                // Treat url as relative path under assets_dir if not absolute
-                let path = if Path::new(u).is_absolute() { Path::new(u).to_path_buf() } else { Path::new(assets_dir).join(u) };
-                if path.exists() {
-                    let p = path.to_string_lossy().to_string();
-                    let mime = if p.ends_with(".svg") { "image/svg+xml" } else if p.ends_with(".png") { "image/png" } else if p.ends_with(".jpg") || p.ends_with(".jpeg") { "image/jpeg" } else { "application/octet-stream" };
-                    return Some((p, mime.to_string()));
-                }
-            }
-        }
-        if let Some(b) = builtin {
-            let key = b.to_lowercase();
+/// Resolve a decoration's asset file under `assets_dir`.
+pub(crate) fn resolve_decoration_asset(builtin: &Option<String>, url: &Option<String>, assets_dir: &str) -> Option<(String, String)> {
+    // Returns (abs_path, mime)
+    // Priority: url starting with "builtin:" -> assets_dir/name.ext; else builtin field; else url as relative path
+    if let Some(u) = url {
+        if let Some(rest) = u.strip_prefix("builtin:") {
             let candidates = [
-                format!("{}/{}.svg", assets_dir, key),
-                format!("{}/{}.png", assets_dir, key),
-                format!("{}/{}.jpg", assets_dir, key),
-                format!("{}/{}.jpeg", assets_dir, key),
+                format!("{}/{}.svg", assets_dir, rest),
+                format!("{}/{}.png", assets_dir, rest),
+                format!("{}/{}.jpg", assets_dir, rest),
+                format!("{}/{}.jpeg", assets_dir, rest),
             ];
             for p in candidates.iter() {
                 let path = Path::new(p);
@@ -74,9 +50,42 @@ pub fn graphdata_to_excalidraw_scene_with_opts(g: &GraphData, allow_images: bool
                     return Some((path.to_string_lossy().to_string(), mime.to_string()));
                 }
             }
+        } else {
+            // Treat url as relative path under assets_dir if not absolute
+            let path = if Path::new(u).is_absolute() { Path::new(u).to_path_buf() } else { Path::new(assets_dir).join(u) };
+            if path.exists() {
+                let p = path.to_string_lossy().to_string();
+                let mime = if p.ends_with(".svg") { "image/svg+xml" } else if p.ends_with(".png") { "image/png" } else if p.ends_with(".jpg") || p.ends_with(".jpeg") { "image/jpeg" } else { "application/octet-stream" };
+                return Some((p, mime.to_string()));
+            }
         }
-        None
-    };
+    }
+    if let Some(b) = builtin {
+        let key = b.to_lowercase();
+        let candidates = [
+            format!("{}/{}.svg", assets_dir, key),
+            format!("{}/{}.png", assets_dir, key),
+            format!("{}/{}.jpg", assets_dir, key),
+            format!("{}/{}.jpeg", assets_dir, key),
+        ];
+        for p in candidates.iter() {
+            let path = Path::new(p);
+            if path.exists() {
+                let mime = if p.ends_with(".svg") { "image/svg+xml" } else if p.ends_with(".png") { "image/png" } else if p.ends_with(".jpg") || p.ends_with(".jpeg") { "image/jpeg" } else { "application/octet-stream" };
+                return Some((path.to_string_lossy().to_string(), mime.to_string()));
+            }
+        }
+    }
+    None
+}
+
+pub fn graphdata_to_excalidraw_scene_with_opts(g: &GraphData, allow_images: bool, assets_dir: &str) -> Value {
+    let mut scene = graphdata_to_excalidraw_scene(g);
+    if !allow_images { return scene; }
+
+    let decorations_opt = g.decorations.as_ref();
+    if decorations_opt.is_none() { return scene; }
+
 
     // Collect extra elements and files
     let mut extra_elements: Vec<Value> = Vec::new();
@@ -95,7 +104,7 @@ pub fn graphdata_to_excalidraw_scene_with_opts(g: &GraphData, allow_images: bool
         // If decoration type is image/icon and asset exists, embed image; else fallback to emoji/text
         let is_visual = d.r#type.to_lowercase() == "image" || d.r#type.to_lowercase() == "icon";
         if is_visual {
-            if let Some((path, mime)) = resolve_asset(&d.builtin, &d.url) {
+            if let Some((path, mime)) = resolve_decoration_asset(&d.builtin, &d.url, assets_dir) {
                 if let Ok(bytes) = fs::read(&path) {
                     let data_b64 = b64::STANDARD.encode(bytes);
                     let data_url = format!("data:{};base64,{}", mime, data_b64);
@@ -184,65 +193,190 @@ pub fn graphdata_to_excalidraw_scene_with_opts(g: &GraphData, allow_images: bool
     scene
 }
 
-fn node_size(label: &str) -> (f64, f64) {
-    // Wider boxes for longer labels to reduce overlap
-    let w = (label.len() as f64 * 10.0 + 30.0).max(100.0);
-    let h = 48.0;
-    (w, h)
-}
-
 fn color_or(default_hex: &str, c: &str) -> String {
     if c.trim().is_empty() { default_hex.to_string() } else { c.to_string() }
 }
 
+// Distance Excalidraw keeps between a bound arrow's end and the shape
+const BINDING_GAP: f64 = 4.0;
+
+// Height of a cylinder's elliptical top cap
+const CYLINDER_CAP: f64 = 16.0;
+
+fn shape_element(kind: &str, id: String, seed: u32, (x, y, w, h): (f64, f64, f64, f64), bg: &str) -> Value {
+    json!({
+        "type": kind,
+        "version": 1,
+        "versionNonce": (seed as i64),
+        "isDeleted": false,
+        "id": id,
+        "seed": seed,
+        "fillStyle": "solid",
+        "strokeWidth": 2,
+        "strokeStyle": "solid",
+        "roughness": 1,
+        "opacity": 100,
+        "angle": 0,
+        "x": x,
+        "y": y,
+        "strokeColor": "#111827",
+        "backgroundColor": bg,
+        "width": w,
+        "height": h,
+        "boundElements": [],
+        "updated": 0,
+        "roundness": Value::Null
+    })
+}
+
+// Line element through `points` (relative to its top-left), closed when first == last
+fn line_element(id: String, seed: u32, x: f64, y: f64, points: &[(f64, f64)], bg: &str) -> Value {
+    let w = points.iter().map(|p| p.0).fold(0.0, f64::max);
+    let h = points.iter().map(|p| p.1).fold(0.0, f64::max);
+    let mut el = shape_element("line", id, seed, (x, y, w, h), bg);
+    el["points"] = json!(points.iter().map(|p| [p.0, p.1]).collect::<Vec<_>>());
+    el
+}
+
+/// Excalidraw elements for one node outline. The first element is always `node-{id}`, a
+/// bindable rectangle/ellipse/diamond spanning the whole node box. Shapes Excalidraw has no
+/// primitive for get an invisible frame tagged with `customData.shape`, and their drawing is made of
+/// grouped `shapePart` pieces that the importer skips.
+fn node_shape_elements(id: &str, shape: Shape, seed: u32, (x, y, w, h): (f64, f64, f64, f64), bg: &str) -> Vec<Value> {
+    let main_id = format!("node-{}", id);
+    let group = json!([format!("node-group-{}", id)]);
+    let part = |name: &str, kind: &str, px: f64, py: f64, pw: f64, ph: f64, fill: &str| {
+        let mut el = shape_element(kind, format!("node-{}-{}", name, id), seed_from(&(id.to_string(), name.to_string())), (px, py, pw, ph), fill);
+        el["groupIds"] = group.clone();
+        el["customData"] = json!({"shapePart": true});
+        el
+    };
+    let tagged = |mut el: Value| {
+        el["groupIds"] = group.clone();
+        el["customData"] = json!({"shape": shape.name()});
+        el
+    };
+    // Invisible box that carries the node id, bindings and label for composite shapes
+    let frame = || {
+        let mut el = tagged(shape_element("rectangle", main_id.clone(), seed, (x, y, w, h), "transparent"));
+        el["strokeColor"] = json!("transparent");
+        el
+    };
+    let outline_part = |pts: &[(f64, f64)]| {
+        let mut el = line_element(format!("node-outline-{}", id), seed_from(&(id.to_string(), "outline")), x, y, pts, bg);
+        el["groupIds"] = group.clone();
+        el["customData"] = json!({"shapePart": true});
+        el
+    };
+    match shape {
+        Shape::Rectangle => vec![shape_element("rectangle", main_id, seed, (x, y, w, h), bg)],
+        Shape::Rounded => {
+            let mut el = shape_element("rectangle", main_id, seed, (x, y, w, h), bg);
+            el["roundness"] = json!({"type": 3});
+            vec![el]
+        }
+        Shape::Ellipse => vec![shape_element("ellipse", main_id, seed, (x, y, w, h), bg)],
+        Shape::Circle => vec![tagged(shape_element("ellipse", main_id, seed, (x, y, w, h), bg))],
+        Shape::Diamond => vec![shape_element("diamond", main_id, seed, (x, y, w, h), bg)],
+        Shape::Parallelogram | Shape::Hexagon => {
+            let mut pts: Vec<(f64, f64)> = outline(shape, w, h).unwrap_or_default().into_iter().map(|(px, py)| (px + w / 2.0, py + h / 2.0)).collect();
+            pts.push(pts[0]);
+            vec![frame(), outline_part(&pts)]
+        }
+        Shape::Document => {
+            // Flat top, wavy bottom edge
+            let wave = h * 0.12;
+            let mut pts = vec![(0.0, 0.0), (w, 0.0)];
+            for k in 0..=16 {
+                let t = k as f64 / 16.0;
+                pts.push((w * (1.0 - t), h - wave + wave * (2.0 * PI * t).sin()));
+            }
+            pts.push((0.0, 0.0));
+            vec![frame(), outline_part(&pts)]
+        }
+        Shape::Cylinder => {
+            // Body with a rounded bottom, then the top cap as a separate ellipse
+            let ry = CYLINDER_CAP / 2.0;
+            let mut pts = vec![(0.0, ry), (0.0, h - ry)];
+            for k in 1..16 {
+                let a = PI * k as f64 / 16.0;
+                pts.push((w / 2.0 - w / 2.0 * a.cos(), h - ry + ry * a.sin()));
+            }
+            pts.extend([(w, h - ry), (w, ry)]);
+            for k in 1..16 {
+                let a = PI * k as f64 / 16.0;
+                pts.push((w / 2.0 + w / 2.0 * a.cos(), ry - ry * a.sin()));
+            }
+            pts.push((0.0, ry));
+            vec![frame(), outline_part(&pts), part("cap", "ellipse", x, y, w, CYLINDER_CAP, bg)]
+        }
+        Shape::Actor => {
+            // The stick figure is drawn on top of the frame, the label sits underneath
+            let cx = x + w / 2.0;
+            let head = 18.0;
+            let limbs = |name: &str, pts: &[(f64, f64)], ox: f64, oy: f64| {
+                let mut el = part(name, "line", ox, oy, 0.0, 0.0, "transparent");
+                el["width"] = json!(pts.iter().map(|p| p.0).fold(0.0, f64::max));
+                el["height"] = json!(pts.iter().map(|p| p.1).fold(0.0, f64::max));
+                el["points"] = json!(pts.iter().map(|p| [p.0, p.1]).collect::<Vec<_>>());
+                el
+            };
+            let (neck, hip, foot) = (y + head, y + 40.0, y + ACTOR_FIGURE_H - 4.0);
+            vec![
+                frame(),
+                part("head", "ellipse", cx - head / 2.0, y, head, head, bg),
+                limbs("body", &[(0.0, 0.0), (0.0, hip - neck)], cx, neck),
+                limbs("arms", &[(0.0, 0.0), (36.0, 0.0)], cx - 18.0, neck + 8.0),
+                limbs("legs", &[(0.0, foot - hip), (16.0, 0.0), (32.0, foot - hip)], cx - 16.0, hip),
+            ]
+        }
+    }
+}
+
 pub fn graphdata_to_excalidraw_scene(g: &GraphData) -> Value {
+    // Sequence diagrams have their own emitter (lifelines, ordered messages, frames)
+    if let Some(seq) = g.sequence.as_ref().filter(|s| !s.participants.is_empty() || !s.messages.is_empty()) {
+        return scene_json(sequence_to_excalidraw_elements(seq), g);
+    }
+
     // Build in layers: arrows (bottom), rectangles (middle), labels (top)
     let mut arrows: Vec<Value> = Vec::new();
     let mut rects: Vec<Value> = Vec::new();
     let mut texts: Vec<Value> = Vec::new();
+    // Shape id -> elements bound to it (arrows and its label), filled in as they are emitted
+    let mut bound: HashMap<String, Vec<Value>> = HashMap::new();
 
     for n in &g.nodes {
         let seed = seed_from(&n.id);
-        let (w, h) = node_size(&n.label);
+        let shape = Shape::parse(&n.style.shape);
+        let (w, h) = shape_size(shape, &n.label);
         let stroke = "#111827";
         let bg = color_or("#F3F4F6", &n.style.color);
-        let rect = json!({
-            "type": "rectangle",
-            "version": 1,
-            "versionNonce": (seed as i64),
-            "isDeleted": false,
-            "id": format!("node-{}", n.id),
-            "seed": seed,
-            "fillStyle": "solid",
-            "strokeWidth": 2,
-            "strokeStyle": "solid",
-            "roughness": 1,
-            "opacity": 100,
-            "angle": 0,
-            "x": n.x as f64 - w/2.0,
-            "y": n.y as f64 - h/2.0,
-            "strokeColor": stroke,
-            "backgroundColor": bg,
-            "width": w,
-            "height": h,
-            "boundElements": [],
-            "updated": 0,
-            "roundness": {"type": 3}
-        });
-        rects.push(rect);
+        let (x, y) = (n.x as f64 - w / 2.0, n.y as f64 - h / 2.0);
+        rects.extend(node_shape_elements(&n.id, shape, seed, (x, y, w, h), &bg));
 
         let text_seed = seed_from(&(n.id.clone(), "text"));
         // Center text: position text box so its center aligns with node center
-        let text_w = (n.label.len() as f64 * 9.0).min(w - 16.0).max(24.0);
-        let text_h = 24.0;
+        // (actors carry their label under the figure, cylinders below the top cap)
+        let wrapped = node_label(&n.label);
+        let text_w = wrapped.width.max(24.0);
+        let text_h = wrapped.height.max(24.0);
+        let text_cy = match shape {
+            Shape::Actor => y + ACTOR_FIGURE_H + text_h / 2.0 + 2.0,
+            Shape::Cylinder => n.y as f64 + CYLINDER_CAP / 2.0,
+            Shape::Document => n.y as f64 - h * 0.05,
+            _ => n.y as f64,
+        };
         let text_x = n.x as f64 - text_w/2.0;
-        let text_y = n.y as f64 - text_h/2.0;
+        let text_y = text_cy - text_h/2.0;
+        let label_id = format!("node-label-{}", n.id);
+        bound.entry(format!("node-{}", n.id)).or_default().push(json!({"id": label_id, "type": "text"}));
         let text = json!({
             "type": "text",
             "version": 1,
             "versionNonce": (text_seed as i64),
             "isDeleted": false,
-            "id": format!("node-label-{}", n.id),
+            "id": label_id,
             "seed": text_seed,
             "fillStyle": "solid",
             "strokeWidth": 1,
@@ -258,37 +392,74 @@ pub fn graphdata_to_excalidraw_scene(g: &GraphData) -> Value {
             "height": text_h,
             "boundElements": [],
             "updated": 0,
-            "text": n.label,
+            "text": wrapped.text(),
+            "originalText": n.label,
+            "containerId": format!("node-{}", n.id),
             "fontSize": 16,
-            "fontFamily": 1,
+            "fontFamily": LABEL_FONT.family(),
             "textAlign": "center",
-            "verticalAlign": "middle",
+            "verticalAlign": if shape == Shape::Actor { "bottom" } else { "middle" },
             "baseline": 18
         });
         texts.push(text);
     }
 
-    for e in &g.edges {
+    // Orthogonal edges follow obstacle-avoiding routes; the L-shape is the fallback
+    let routes = route_edges(g);
+    // Absolute arrow paths (parallel to `arrows`) and the labels waiting for them: (edge, arrow)
+    let mut paths: Vec<Vec<(f64, f64)>> = Vec::new();
+    let mut pending_labels: Vec<(usize, usize)> = Vec::new();
+    for (ei, e) in g.edges.iter().enumerate() {
         let seed = seed_from(&e.id);
         let src = g.nodes.iter().find(|n| n.id == e.source);
         let tgt = g.nodes.iter().find(|n| n.id == e.target);
         if let (Some(s), Some(t)) = (src, tgt) {
-            let (sw, sh) = node_size(&s.label);
-            let (tw, th) = node_size(&t.label);
+            let (s_shape, t_shape) = (Shape::parse(&s.style.shape), Shape::parse(&t.style.shape));
+            let (sw, sh) = shape_size(s_shape, &s.label);
+            let (tw, th) = shape_size(t_shape, &t.label);
             let sx = s.x as f64;
             let sy = s.y as f64;
             let tx = t.x as f64;
             let ty = t.y as f64;
 
-            // Compute intersection points with source and target rectangles to avoid overlapping nodes
-            let (start_x, start_y) = line_rect_border_intersection(sx, sy, sw, sh, tx, ty);
-            let (end_x, end_y) = line_rect_border_intersection(tx, ty, tw, th, sx, sy);
+            // Compute intersection points with the source and target outlines to avoid overlapping nodes
+            let route = routes[ei].as_ref().filter(|r| r.len() >= 2);
+            let (start_x, start_y) = match route {
+                Some(r) => r[0],
+                None => border_intersection(s_shape, sx, sy, sw, sh, tx, ty),
+            };
+            let (end_x, end_y) = match route {
+                Some(r) => r[r.len() - 1],
+                None => border_intersection(t_shape, tx, ty, tw, th, sx, sy),
+            };
 
             let dx = end_x - start_x;
             let dy = end_y - start_y;
-            // Orthogonal routing: L-shape via (dx, 0) then (dx, dy)
             let arrow_seed = seed_from(&(e.id.clone(), "arrow"));
-            let points = vec![vec![0.0, 0.0], vec![dx, 0.0], vec![dx, dy]];
+            // Routing from EdgeStyle.line: straight segment, orthogonal route (L-shape via (dx, 0)
+            // then (dx, dy) when unrouted), or a curve through an offset midpoint (Excalidraw
+            // smooths rounded arrows)
+            let look = EdgeLook::of(&e.style);
+            let curved = look.routing == Routing::Curved;
+            let bend = if curved {
+                let len = (dx * dx + dy * dy).sqrt().max(1.0);
+                let k = len * 0.12;
+                Some((dx / 2.0 - dy / len * k, dy / 2.0 + dx / len * k))
+            } else {
+                None
+            };
+            let points = match (bend, look.routing) {
+                (Some((bx, by)), _) => vec![vec![0.0, 0.0], vec![bx, by], vec![dx, dy]],
+                (None, Routing::Straight) => vec![vec![0.0, 0.0], vec![dx, dy]],
+                (None, _) => match route {
+                    Some(r) => r.iter().map(|p| vec![p.0 - start_x, p.1 - start_y]).collect(),
+                    None => vec![vec![0.0, 0.0], vec![dx, 0.0], vec![dx, dy]],
+                },
+            };
+            let xs = points.iter().map(|p| p[0]);
+            let ys = points.iter().map(|p| p[1]);
+            let width = xs.clone().fold(f64::NEG_INFINITY, f64::max) - xs.fold(f64::INFINITY, f64::min);
+            let height = ys.clone().fold(f64::NEG_INFINITY, f64::max) - ys.fold(f64::INFINITY, f64::min);
             let arrow = json!({
                 "type": "arrow",
                 "version": 1,
@@ -297,8 +468,8 @@ pub fn graphdata_to_excalidraw_scene(g: &GraphData) -> Value {
                 "id": format!("edge-{}", e.id),
                 "seed": arrow_seed,
                 "fillStyle": "solid",
-                "strokeWidth": 2,
-                "strokeStyle": "solid",
+                "strokeWidth": look.width,
+                "strokeStyle": look.stroke_style(),
                 "roughness": 1,
                 "opacity": 100,
                 "angle": 0,
@@ -306,62 +477,97 @@ pub fn graphdata_to_excalidraw_scene(g: &GraphData) -> Value {
                 "y": start_y,
                 "strokeColor": "#111827",
                 "backgroundColor": "transparent",
-                "width": dx.abs(),
-                "height": dy.abs(),
+                "width": width,
+                "height": height,
                 "boundElements": [],
                 "updated": 0,
                 "lastCommittedPoint": Value::Null,
                 "points": points,
-                "startArrowhead": Value::Null,
-                "endArrowhead": "arrow"
+                "roundness": if curved { json!({"type": 2}) } else { Value::Null },
+                "startArrowhead": look.start_head,
+                "endArrowhead": look.end_head,
+                // Bound to both nodes (aimed at their centers) so the arrow follows them when dragged
+                "startBinding": {"elementId": format!("node-{}", s.id), "focus": 0.0, "gap": BINDING_GAP},
+                "endBinding": {"elementId": format!("node-{}", t.id), "focus": 0.0, "gap": BINDING_GAP}
             });
+            let arrow_id = format!("edge-{}", e.id);
+            for end in [&s.id, &t.id] {
+                bound.entry(format!("node-{}", end)).or_default().push(json!({"id": arrow_id, "type": "arrow"}));
+            }
+            paths.push(points.iter().map(|p| (start_x + p[0], start_y + p[1])).collect());
             arrows.push(arrow);
 
-            if !e.label.is_empty() {
-                // Segment-aware label: choose longest orthogonal segment
-                let (seg_mid_x, seg_mid_y, nx, ny) = if dx.abs() >= dy.abs() {
-                    // horizontal segment midpoint at (start_x + dx/2, start_y)
-                    (start_x + dx/2.0, start_y, 0.0, 1.0)
-                } else {
-                    // vertical segment midpoint at (end_x, start_y + dy/2)
-                    (end_x, start_y + dy/2.0, -1.0, 0.0)
-                };
-                let off = 12.0;
-                let lx = seg_mid_x + nx * off;
-                let ly = seg_mid_y + ny * off;
-                let lw = (e.label.len() as f64 * 9.0 + 8.0).max(24.0);
-                let lh = 20.0;
-                let lseed = seed_from(&(e.id.clone(), "label"));
-                let label = json!({
-                    "type": "text",
-                    "version": 1,
-                    "versionNonce": (lseed as i64),
-                    "isDeleted": false,
-                    "id": format!("edge-label-{}", e.id),
-                    "seed": lseed,
-                    "fillStyle": "solid",
-                    "strokeWidth": 1,
-                    "strokeStyle": "solid",
-                    "roughness": 0,
-                    "opacity": 100,
-                    "angle": 0,
-                    "x": lx - lw/2.0,
-                    "y": ly - lh/2.0,
-                    "strokeColor": "#111827",
-                    "backgroundColor": "transparent",
-                    "width": lw,
-                    "height": lh,
-                    "boundElements": [],
-                    "updated": 0,
-                    "text": e.label,
-                    "fontSize": 14,
-                    "fontFamily": 1,
-                    "textAlign": "center",
-                    "verticalAlign": "middle",
-                    "baseline": 16
-                });
-                texts.push(label);
-            }
+            if !e.label.is_empty() { pending_labels.push((ei, arrows.len() - 1)); }
+        }
+    }
+
+    // Edge labels go where they collide least with nodes, arrows and each other
+    let node_rects: Vec<(f64, f64, f64, f64)> = g
+        .nodes
+        .iter()
+        .map(|n| {
+            let (w, h) = node_box(n);
+            (n.x as f64 - w / 2.0, n.y as f64 - h / 2.0, n.x as f64 + w / 2.0, n.y as f64 + h / 2.0)
+        })
+        .collect();
+    let wrapped: Vec<TextBlock> = pending_labels.iter().map(|&(ei, _)| edge_label(&g.edges[ei].label)).collect();
+    let requests: Vec<LabelRequest> = pending_labels
+        .iter()
+        .zip(&wrapped)
+        .map(|(&(_, k), t)| LabelRequest { path: k, w: (t.width + 8.0).max(24.0), h: t.height.max(20.0) })
+        .collect();
+    let canvas = g.global_style.as_ref().map(|gs| gs.background.clone()).filter(|b| !b.is_empty()).unwrap_or("#FFFFFF".to_string());
+    let spots = place_labels(&node_rects, &paths, &requests);
+    for (((&(ei, k), spot), req), text) in pending_labels.iter().zip(spots).zip(&requests).zip(&wrapped) {
+        let e = &g.edges[ei];
+        let (lw, lh) = (req.w, req.h);
+        let (lx, ly) = (spot.x, spot.y);
+        if spot.boxed {
+            // No free spot: the label sits on its arrow over a background-colored box
+            let bseed = seed_from(&(e.id.clone(), "label-bg"));
+            let mut bg = shape_element("rectangle", format!("edge-label-bg-{}", e.id), bseed, (lx - lw / 2.0 - 2.0, ly - lh / 2.0 - 1.0, lw + 4.0, lh + 2.0), &canvas);
+            bg["strokeColor"] = json!("transparent");
+            bg["roughness"] = json!(0);
+            bg["customData"] = json!({"labelBackground": true});
+            texts.push(bg);
+        }
+        let lseed = seed_from(&(e.id.clone(), "label"));
+        let label = json!({
+            "type": "text",
+            "version": 1,
+            "versionNonce": (lseed as i64),
+            "isDeleted": false,
+            "id": format!("edge-label-{}", e.id),
+            "seed": lseed,
+            "fillStyle": "solid",
+            "strokeWidth": 1,
+            "strokeStyle": "solid",
+            "roughness": 0,
+            "opacity": 100,
+            "angle": 0,
+            "x": lx - lw/2.0,
+            "y": ly - lh/2.0,
+            "strokeColor": "#111827",
+            "backgroundColor": "transparent",
+            "width": lw,
+            "height": lh,
+            "boundElements": [],
+            "updated": 0,
+            "text": text.text(),
+            "originalText": e.label,
+            "containerId": format!("edge-{}", e.id),
+            "fontSize": 14,
+            "fontFamily": LABEL_FONT.family(),
+            "textAlign": "center",
+            "verticalAlign": "middle",
+            "baseline": 16
+        });
+        arrows[k]["boundElements"] = json!([{"id": format!("edge-label-{}", e.id), "type": "text"}]);
+        texts.push(label);
+    }
+    for el in rects.iter_mut() {
+        if let Some(list) = el["id"].as_str().and_then(|id| bound.remove(id)) {
+            el["boundElements"] = json!(list);
         }
     }
 
@@ -369,21 +575,10 @@ pub fn graphdata_to_excalidraw_scene(g: &GraphData) -> Value {
     let mut elements: Vec<Value> = Vec::new();
     // Containers behind everything
     if let Some(conts) = g.containers.as_ref() {
-        let padding = 40.0;
-        for c in conts {
-            // compute bbox from children
-            let mut minx = f64::INFINITY; let mut miny = f64::INFINITY; let mut maxx = f64::NEG_INFINITY; let mut maxy = f64::NEG_INFINITY;
-            for id in &c.children {
-                if let Some(n) = g.nodes.iter().find(|n| &n.id == id) {
-                    let (w,h) = node_size(&n.label);
-                    minx = minx.min(n.x as f64 - w/2.0);
-                    miny = miny.min(n.y as f64 - h/2.0);
-                    maxx = maxx.max(n.x as f64 + w/2.0);
-                    maxy = maxy.max(n.y as f64 + h/2.0);
-                }
-            }
-            if !minx.is_finite() { continue; }
-            let x = minx - padding; let y = miny - padding; let w = (maxx-minx) + 2.0*padding; let h = (maxy-miny) + 2.0*padding;
+        // Same boxes the router treats as obstacles
+        for (c, bbox) in conts.iter().zip(container_boxes(g)) {
+            let Some((x0, y0, x1, y1)) = bbox else { continue };
+            let (x, y, w, h) = (x0, y0, x1 - x0, y1 - y0);
             let seed = seed_from(&(c.id.clone(), "container"));
             let bg = c.style.as_ref().and_then(|s| s.bg.clone()).unwrap_or("#FFFFFF".to_string());
             let rect = json!({
@@ -412,7 +607,7 @@ pub fn graphdata_to_excalidraw_scene(g: &GraphData) -> Value {
             elements.push(rect);
             // header chip/tag
             let tag = c.style.as_ref().and_then(|s| s.label_tag.clone()).unwrap_or(c.label.clone());
-            let tag_w = (tag.len() as f64 * 7.5 + 24.0).max(48.0);
+            let tag_w = (text_width(&tag, LABEL_FONT, 14.0) + 24.0).max(48.0);
             let tag_h = 20.0;
             let tseed = seed_from(&(c.id.clone(), "container-tag"));
             let label = json!({
@@ -452,6 +647,10 @@ pub fn graphdata_to_excalidraw_scene(g: &GraphData) -> Value {
     elements.extend(rects);
     elements.extend(texts);
 
+    scene_json(elements, g)
+}
+
+fn scene_json(elements: Vec<Value>, g: &GraphData) -> Value {
     // app background color from global_style or default
     let app_bg = g
         .global_style
@@ -472,22 +671,14 @@ pub fn graphdata_to_excalidraw_scene(g: &GraphData) -> Value {
     })
 }
 
-// Compute intersection point between a rectangle border and a ray from (cx,cy) toward (tx,ty)
-fn line_rect_border_intersection(cx: f64, cy: f64, w: f64, h: f64, tx: f64, ty: f64) -> (f64, f64) {
+// Compute intersection point between a node outline and a ray from (cx,cy) toward (tx,ty)
+fn border_intersection(shape: Shape, cx: f64, cy: f64, w: f64, h: f64, tx: f64, ty: f64) -> (f64, f64) {
     let dx = tx - cx;
     let dy = ty - cy;
-    // Avoid division by zero
-    let mut t_candidates: Vec<f64> = Vec::new();
-    if dx != 0.0 { t_candidates.push((w / 2.0) / dx.abs()); }
-    if dy != 0.0 { t_candidates.push((h / 2.0) / dy.abs()); }
-    let t = if let Some(min_t) = t_candidates.into_iter().fold(None, |acc: Option<f64>, v| {
-        Some(match acc { Some(a) => if v < a { v } else { a }, None => v })
-    }) { min_t } else { 0.0 };
+    let (sx, sy) = border_point(shape, cx, cy, w, h, tx, ty);
     // Slight inset to keep arrow off the stroke
     let inset = 2.0;
     let norm = (dx.hypot(dy)).max(1.0);
-    let sx = cx + dx * t;
-    let sy = cy + dy * t;
     // Pull back by inset along the direction
     let ux = dx / norm;
     let uy = dy / norm;
@@ -0,0 +1,394 @@
+use crate::state::{Container, Decoration, DecorationSize, EdgeData, EdgeStyle, GlobalStyle, GraphData, LayoutHints, NodeData, NodeStyle};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+// Excalidraw scene -> GraphData (inverse of graphdata_to_excalidraw_scene).
+// Shapes with a label become nodes, arrows/lines become edges (bindings first, else the nearest
+// shape within SNAP_DISTANCE), and rectangles that enclose other shapes become containers.
+// Ids written by our emitter ("node-", "edge-", "container-") are stripped so scenes round-trip.
+// Node shapes without an Excalidraw primitive are closed lines tagged with `customData.shape`;
+// their extra pieces (`customData.shapePart`) and edge label backgrounds are skipped.
+
+const SNAP_DISTANCE: f64 = 40.0;
+const LABEL_DISTANCE: f64 = 32.0;
+
+#[derive(Clone)]
+struct Bounds {
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+}
+
+impl Bounds {
+    fn of(el: &Value) -> Self {
+        let f = |k: &str| el.get(k).and_then(|v| v.as_f64()).unwrap_or(0.0);
+        // Negative sizes are legal in Excalidraw (dragged up/left)
+        let (x, w) = if f("width") < 0.0 { (f("x") + f("width"), -f("width")) } else { (f("x"), f("width")) };
+        let (y, h) = if f("height") < 0.0 { (f("y") + f("height"), -f("height")) } else { (f("y"), f("height")) };
+        Bounds { x, y, w, h }
+    }
+    fn center(&self) -> (f64, f64) { (self.x + self.w / 2.0, self.y + self.h / 2.0) }
+    fn area(&self) -> f64 { self.w * self.h }
+    fn contains_point(&self, (px, py): (f64, f64)) -> bool {
+        px >= self.x && px <= self.x + self.w && py >= self.y && py <= self.y + self.h
+    }
+    fn contains(&self, o: &Bounds) -> bool {
+        o.x >= self.x && o.y >= self.y && o.x + o.w <= self.x + self.w && o.y + o.h <= self.y + self.h && self.area() > o.area()
+    }
+    // Distance from a point to the box (0 when inside)
+    fn distance(&self, (px, py): (f64, f64)) -> f64 {
+        let dx = (self.x - px).max(0.0).max(px - (self.x + self.w));
+        let dy = (self.y - py).max(0.0).max(py - (self.y + self.h));
+        (dx * dx + dy * dy).sqrt()
+    }
+}
+
+fn str_of<'a>(el: &'a Value, key: &str) -> Option<&'a str> {
+    el.get(key).and_then(|v| v.as_str())
+}
+
+fn strip_id(id: &str, prefix: &str) -> String {
+    id.strip_prefix(prefix).filter(|s| !s.is_empty()).unwrap_or(id).to_string()
+}
+
+fn fill_of(el: &Value) -> String {
+    match str_of(el, "backgroundColor") {
+        Some(c) if !c.is_empty() && c != "transparent" => c.to_string(),
+        _ => String::new(),
+    }
+}
+
+// Absolute polyline of an arrow/line element
+fn tagged_shape(el: &Value) -> Option<&str> {
+    el.pointer("/customData/shape").and_then(|v| v.as_str())
+}
+
+// NodeStyle.shape for a shape element: the emitter's tag, else rounded rectangles, else the type
+fn shape_name(el: &Value) -> String {
+    if let Some(tag) = tagged_shape(el) { return tag.to_string(); }
+    match str_of(el, "type") {
+        Some("rectangle") if el.get("roundness").map(|r| !r.is_null()).unwrap_or(false) => "rounded".to_string(),
+        Some(kind) => kind.to_string(),
+        None => "rectangle".to_string(),
+    }
+}
+
+fn arrow_points(el: &Value) -> Vec<(f64, f64)> {
+    let x = el.get("x").and_then(|v| v.as_f64()).unwrap_or(0.0);
+    let y = el.get("y").and_then(|v| v.as_f64()).unwrap_or(0.0);
+    el.get("points")
+        .and_then(|v| v.as_array())
+        .map(|pts| {
+            pts.iter()
+                .filter_map(|p| Some((x + p.get(0)?.as_f64()?, y + p.get(1)?.as_f64()?)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn segment_distance((px, py): (f64, f64), (ax, ay): (f64, f64), (bx, by): (f64, f64)) -> f64 {
+    let (dx, dy) = (bx - ax, by - ay);
+    let len2 = dx * dx + dy * dy;
+    let t = if len2 == 0.0 { 0.0 } else { (((px - ax) * dx + (py - ay) * dy) / len2).clamp(0.0, 1.0) };
+    let (cx, cy) = (ax + t * dx, ay + t * dy);
+    ((px - cx).powi(2) + (py - cy).powi(2)).sqrt()
+}
+
+fn arrowhead(v: Option<&str>) -> Option<&'static str> {
+    match v {
+        None => None,
+        Some("dot") | Some("circle") | Some("circle_outline") => Some("dot"),
+        Some("bar") => Some("bar"),
+        Some("triangle") | Some("triangle_outline") => Some("triangle"),
+        Some(_) => Some("arrow"),
+    }
+}
+
+// EdgeStyle.line: routing (omitted when the emitter's orthogonal default redraws the same path)
+// plus the stroke, e.g. "curved dashed"
+fn edge_line(el: &Value, points: &[(f64, f64)]) -> String {
+    let aligned = |a: (f64, f64), b: (f64, f64)| (a.0 - b.0).abs() < 0.5 || (a.1 - b.1).abs() < 0.5;
+    let routing = if points.len() > 2 {
+        if el.get("roundness").map(|r| !r.is_null()).unwrap_or(false) {
+            Some("curved")
+        } else if points.windows(2).all(|w| aligned(w[0], w[1])) {
+            Some("orthogonal")
+        } else {
+            None
+        }
+    } else if points.len() == 2 && !aligned(points[0], points[1]) {
+        Some("straight")
+    } else {
+        None
+    };
+    let stroke = match str_of(el, "strokeStyle") {
+        Some("dashed") => Some("dashed"),
+        Some("dotted") => Some("dotted"),
+        _ => None,
+    };
+    match (routing, stroke) {
+        (Some(r), Some(s)) => format!("{} {}", r, s),
+        (Some(r), None) => r.to_string(),
+        (None, Some(s)) => s.to_string(),
+        (None, None) => "solid".to_string(),
+    }
+}
+
+/// Rebuild GraphData from an Excalidraw scene (`{"elements": [...]}` or a bare element array).
+pub fn excalidraw_scene_to_graphdata(scene: &Value) -> Result<GraphData, String> {
+    let elements = scene
+        .get("elements")
+        .or(Some(scene))
+        .and_then(|v| v.as_array())
+        .ok_or("Scene has no elements array")?;
+    let live: Vec<&Value> = elements
+        .iter()
+        .filter(|e| !e.get("isDeleted").and_then(|v| v.as_bool()).unwrap_or(false))
+        .filter(|e| !["/customData/shapePart", "/customData/labelBackground"].iter().any(|k| e.pointer(k).and_then(|v| v.as_bool()).unwrap_or(false)))
+        .collect();
+
+    let shapes: Vec<&Value> = live
+        .iter()
+        .copied()
+        .filter(|e| matches!(str_of(e, "type"), Some("rectangle" | "ellipse" | "diamond")) || tagged_shape(e).is_some())
+        .collect();
+    let texts: Vec<&Value> = live.iter().copied().filter(|e| str_of(e, "type") == Some("text")).collect();
+    let shape_bounds: Vec<Bounds> = shapes.iter().map(|e| Bounds::of(e)).collect();
+
+    // Rectangles enclosing other shapes are containers (emitter containers are always tagged)
+    let is_container: Vec<bool> = shapes
+        .iter()
+        .enumerate()
+        .map(|(i, e)| {
+            str_of(e, "id").map(|id| id.starts_with("container-")).unwrap_or(false)
+                || (str_of(e, "type") == Some("rectangle") && shape_bounds.iter().enumerate().any(|(j, b)| j != i && shape_bounds[i].contains(b)))
+        })
+        .collect();
+
+    // Text -> owner: bound text (containerId) first, else the smallest shape containing its center
+    let shape_index: HashMap<&str, usize> = shapes.iter().enumerate().filter_map(|(i, e)| str_of(e, "id").map(|id| (id, i))).collect();
+    let mut shape_text: HashMap<usize, Vec<&Value>> = HashMap::new();
+    let mut free_texts: Vec<&Value> = Vec::new();
+    for t in &texts {
+        // Text bound to something that is not a shape (an arrow label) never belongs to a node
+        let owner = match str_of(t, "containerId").filter(|cid| live.iter().any(|e| str_of(e, "id") == Some(cid))) {
+            Some(cid) => shape_index.get(cid).copied(),
+            None => {
+                let c = Bounds::of(t).center();
+                (0..shapes.len())
+                    .filter(|&i| !is_container[i] && shape_bounds[i].contains_point(c))
+                    .min_by(|&a, &b| shape_bounds[a].area().total_cmp(&shape_bounds[b].area()))
+            }
+        };
+        match owner {
+            Some(i) => shape_text.entry(i).or_default().push(t),
+            None => free_texts.push(t),
+        }
+    }
+    let text_of = |v: &[&Value]| v.iter().filter_map(|t| str_of(t, "text")).collect::<Vec<_>>().join("\n");
+
+    // Nodes
+    let mut nodes: Vec<NodeData> = Vec::new();
+    let mut node_of_shape: HashMap<usize, usize> = HashMap::new();
+    let mut node_shapes: Vec<usize> = Vec::new();
+    let mut used_ids: HashSet<String> = HashSet::new();
+    for (i, e) in shapes.iter().enumerate() {
+        if is_container[i] { continue; }
+        let raw = str_of(e, "id").unwrap_or("");
+        let mut id = if raw.is_empty() { format!("n{}", i) } else { strip_id(raw, "node-") };
+        while !used_ids.insert(id.clone()) { id = format!("{}_{}", id, i); }
+        let label = shape_text.get(&i).map(|v| text_of(v)).unwrap_or_default();
+        let (cx, cy) = shape_bounds[i].center();
+        node_of_shape.insert(i, nodes.len());
+        node_shapes.push(i);
+        nodes.push(NodeData {
+            id: id.clone(),
+            label: if label.is_empty() { id } else { label },
+            x: cx as f32,
+            y: cy as f32,
+            style: NodeStyle { shape: shape_name(e), color: fill_of(e) },
+        });
+    }
+
+    // Containers: children are nodes whose center falls inside; the smallest enclosing container wins
+    let container_shapes: Vec<usize> = (0..shapes.len()).filter(|&i| is_container[i]).collect();
+    let mut containers: Vec<Container> = container_shapes
+        .iter()
+        .map(|&i| {
+            let raw = str_of(shapes[i], "id").unwrap_or("");
+            Container {
+                id: if raw.is_empty() { format!("container_{}", i) } else { strip_id(raw, "container-") },
+                label: shape_text.get(&i).map(|v| text_of(v)).unwrap_or_default(),
+                children: Vec::new(),
+                style: None,
+            }
+        })
+        .collect();
+    for (ni, &si) in node_shapes.iter().enumerate() {
+        let c = shape_bounds[si].center();
+        let owner = container_shapes
+            .iter()
+            .enumerate()
+            .filter(|(_, &ci)| shape_bounds[ci].contains_point(c))
+            .min_by(|(_, &a), (_, &b)| shape_bounds[a].area().total_cmp(&shape_bounds[b].area()));
+        if let Some((k, _)) = owner { containers[k].children.push(nodes[ni].id.clone()); }
+    }
+    for (k, &ci) in container_shapes.iter().enumerate() {
+        let bg = fill_of(shapes[ci]);
+        let border = str_of(shapes[ci], "strokeColor").map(|s| s.to_string());
+        containers[k].style = Some(crate::state::ContainerStyle { bg: (!bg.is_empty()).then_some(bg), border, radius: None, label_tag: None });
+    }
+
+    // Edges
+    let resolve = |binding: Option<&str>, p: Option<(f64, f64)>| -> Option<usize> {
+        if let Some(&si) = binding.and_then(|b| shape_index.get(b)) {
+            if let Some(&ni) = node_of_shape.get(&si) { return Some(ni); }
+        }
+        let p = p?;
+        node_shapes
+            .iter()
+            .map(|&si| (si, shape_bounds[si].distance(p)))
+            .filter(|(_, d)| *d <= SNAP_DISTANCE)
+            .min_by(|a, b| a.1.total_cmp(&b.1).then(shape_bounds[a.0].area().total_cmp(&shape_bounds[b.0].area())))
+            .map(|(si, _)| node_of_shape[&si])
+    };
+    let mut edges: Vec<EdgeData> = Vec::new();
+    let mut edge_paths: Vec<Vec<(f64, f64)>> = Vec::new();
+    for e in live.iter().filter(|e| matches!(str_of(e, "type"), Some("arrow" | "line")) && tagged_shape(e).is_none()) {
+        let points = arrow_points(e);
+        let binding = |k: &str| e.get(k).and_then(|b| b.get("elementId")).and_then(|v| v.as_str());
+        let (Some(s), Some(t)) = (resolve(binding("startBinding"), points.first().copied()), resolve(binding("endBinding"), points.last().copied())) else { continue };
+        let is_arrow = str_of(e, "type") == Some("arrow");
+        let head = |k: &str| if is_arrow { arrowhead(str_of(e, k)) } else { None };
+        let arrow = match (head("startArrowhead"), head("endArrowhead")) {
+            (None, None) => "none".to_string(),
+            (None, Some("arrow")) => "end".to_string(),
+            (None, Some(kind)) => kind.to_string(),
+            (Some("arrow"), None) => "start".to_string(),
+            (Some(kind), None) => format!("start {}", kind),
+            (Some("arrow"), Some(_)) => "both".to_string(),
+            (Some(kind), Some(_)) => format!("both {}", kind),
+        };
+        let (source, target) = (nodes[s].id.clone(), nodes[t].id.clone());
+        let raw = str_of(e, "id").unwrap_or("");
+        let base = if raw.starts_with("edge-") { strip_id(raw, "edge-") } else { format!("{}_{}", source, target) };
+        let mut id = base.clone();
+        let mut k = 2;
+        while edges.iter().any(|x| x.id == id) {
+            id = format!("{}_{}", base, k);
+            k += 1;
+        }
+        let label = texts
+            .iter()
+            .filter(|t| !raw.is_empty() && str_of(t, "containerId") == Some(raw))
+            .filter_map(|t| str_of(t, "text"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        edges.push(EdgeData { id, source, target, label, style: EdgeStyle { line: edge_line(e, &points), arrow } });
+        edge_paths.push(points);
+    }
+
+    // Free texts: arrow-bound ones are done, the nearest arrow claims unbound labels,
+    // texts inside a container are its tag, and anything else is a note decoration.
+    let mut decorations: Vec<Decoration> = Vec::new();
+    for t in free_texts {
+        if str_of(t, "containerId").map(|cid| live.iter().any(|e| str_of(e, "id") == Some(cid))).unwrap_or(false) { continue; }
+        let text = str_of(t, "text").unwrap_or("").to_string();
+        // Labels written by our emitter are matched by id
+        let tid = str_of(t, "id").unwrap_or("");
+        if let Some(e) = tid.strip_prefix("edge-label-").and_then(|id| edges.iter_mut().find(|e| e.id == id)) {
+            e.label = text;
+            continue;
+        }
+        if let Some(c) = tid.strip_prefix("container-tag-").and_then(|id| containers.iter_mut().find(|c| c.id == id)) {
+            c.label = text;
+            continue;
+        }
+        let b = Bounds::of(t);
+        let c = b.center();
+        let nearest = edge_paths
+            .iter()
+            .enumerate()
+            .filter_map(|(i, pts)| pts.windows(2).map(|w| segment_distance(c, w[0], w[1])).min_by(f64::total_cmp).map(|d| (i, d)))
+            .filter(|(_, d)| *d <= LABEL_DISTANCE)
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((i, _)) = nearest {
+            if edges[i].label.is_empty() {
+                edges[i].label = text;
+                continue;
+            }
+        }
+        let holder = container_shapes
+            .iter()
+            .enumerate()
+            .filter(|(_, &ci)| shape_bounds[ci].contains_point(c))
+            .min_by(|(_, &a), (_, &b)| shape_bounds[a].area().total_cmp(&shape_bounds[b].area()));
+        if let Some((k, _)) = holder {
+            if containers[k].label.is_empty() {
+                containers[k].label = text;
+                continue;
+            }
+        }
+        if text.trim().is_empty() { continue; }
+        decorations.push(Decoration {
+            r#type: "note".to_string(),
+            at_x: Some(c.0 as f32),
+            at_y: Some(c.1 as f32),
+            size: Some(DecorationSize { w: b.w as f32, h: b.h as f32 }),
+            text: Some(text),
+            ..Default::default()
+        });
+    }
+    for e in live.iter().filter(|e| str_of(e, "type") == Some("image")) {
+        let b = Bounds::of(e);
+        let (cx, cy) = b.center();
+        let url = str_of(e, "fileId")
+            .and_then(|fid| scene.get("files").and_then(|f| f.get(fid)))
+            .and_then(|f| str_of(f, "dataURL"))
+            .map(|s| s.to_string());
+        decorations.push(Decoration {
+            r#type: "image".to_string(),
+            at_x: Some(cx as f32),
+            at_y: Some(cy as f32),
+            size: Some(DecorationSize { w: b.w as f32, h: b.h as f32 }),
+            url,
+            ..Default::default()
+        });
+    }
+    for c in &mut containers {
+        if c.label.is_empty() { c.label = c.id.clone(); }
+    }
+
+    if nodes.is_empty() { return Err("Scene contains no shapes".to_string()); }
+
+    // Direction: dominant axis of edge vectors
+    let (mut horiz, mut vert) = (0.0f64, 0.0f64);
+    for e in &edges {
+        let (Some(s), Some(t)) = (nodes.iter().find(|n| n.id == e.source), nodes.iter().find(|n| n.id == e.target)) else { continue };
+        horiz += (t.x - s.x) as f64;
+        vert += (t.y - s.y) as f64;
+    }
+    let direction = if horiz.abs() > vert.abs() {
+        if horiz < 0.0 { "RL" } else { "LR" }
+    } else if vert < 0.0 {
+        "BT"
+    } else {
+        "TB"
+    };
+    let background = scene
+        .get("appState")
+        .and_then(|a| str_of(a, "viewBackgroundColor"))
+        .unwrap_or("#FFFFFF")
+        .to_string();
+
+    Ok(GraphData {
+        nodes,
+        edges,
+        layout_hints: Some(LayoutHints { direction: direction.to_string(), algorithm: "longest_path".to_string(), ..Default::default() }),
+        global_style: Some(GlobalStyle { font: "Inter".to_string(), background, theme: Some("minimal".to_string()) }),
+        decorations: if decorations.is_empty() { None } else { Some(decorations) },
+        containers: if containers.is_empty() { None } else { Some(containers) },
+        sequence: None,
+    })
+}
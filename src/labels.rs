@@ -0,0 +1,143 @@
+// Edge label placement. Every label gets candidate spots along its own path: beside each
+// segment (both sides, several positions along it) and, as a last resort, on the line itself.
+// Candidates are scored against node boxes, labels placed so far, and all arrow paths; the
+// cheapest free spot wins, and a label with no free spot sits on its path in a small box filled
+// with the background color so the arrow underneath does not strike through it.
+
+/// Space between a label and the segment it annotates.
+const GAP: f64 = 4.0;
+/// Cost per pixel of arrow running through a label (areas count one per square pixel).
+const ARROW_WEIGHT: f64 = 20.0;
+/// Cost per pixel of distance from the middle of the path, to prefer central spots.
+const CENTER_WEIGHT: f64 = 0.05;
+/// Positions along a segment, most preferred first.
+const ALONG: [f64; 5] = [0.5, 0.35, 0.65, 0.2, 0.8];
+
+/// One label to place: the index of its path in `paths`, and its size.
+#[derive(Debug, Clone, Copy)]
+pub struct LabelRequest {
+    pub path: usize,
+    pub w: f64,
+    pub h: f64,
+}
+
+/// Center of a placed label; `boxed` labels sit on their path and need a filled background.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    pub x: f64,
+    pub y: f64,
+    pub boxed: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Rect {
+    x0: f64,
+    y0: f64,
+    x1: f64,
+    y1: f64,
+}
+
+impl Rect {
+    fn centered(x: f64, y: f64, w: f64, h: f64) -> Rect {
+        Rect { x0: x - w / 2.0, y0: y - h / 2.0, x1: x + w / 2.0, y1: y + h / 2.0 }
+    }
+
+    fn overlap(&self, o: &Rect) -> f64 {
+        let w = self.x1.min(o.x1) - self.x0.max(o.x0);
+        let h = self.y1.min(o.y1) - self.y0.max(o.y0);
+        if w > 0.0 && h > 0.0 { w * h } else { 0.0 }
+    }
+
+    // Length of segment a-b inside the rectangle (Liang-Barsky clipping)
+    fn clip_length(&self, a: (f64, f64), b: (f64, f64)) -> f64 {
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let (mut t0, mut t1) = (0.0f64, 1.0f64);
+        for (p, q) in [(-dx, a.0 - self.x0), (dx, self.x1 - a.0), (-dy, a.1 - self.y0), (dy, self.y1 - a.1)] {
+            if p == 0.0 {
+                if q < 0.0 { return 0.0; }
+            } else {
+                let r = q / p;
+                if p < 0.0 { t0 = t0.max(r) } else { t1 = t1.min(r) }
+            }
+        }
+        if t1 > t0 { (t1 - t0) * dx.hypot(dy) } else { 0.0 }
+    }
+}
+
+// Point halfway along the path by length
+fn path_middle(path: &[(f64, f64)]) -> (f64, f64) {
+    let total: f64 = path.windows(2).map(|w| (w[1].0 - w[0].0).hypot(w[1].1 - w[0].1)).sum();
+    let mut left = total / 2.0;
+    for w in path.windows(2) {
+        let len = (w[1].0 - w[0].0).hypot(w[1].1 - w[0].1);
+        if len >= left && len > 0.0 {
+            let t = left / len;
+            return (w[0].0 + (w[1].0 - w[0].0) * t, w[0].1 + (w[1].1 - w[0].1) * t);
+        }
+        left -= len;
+    }
+    path.first().copied().unwrap_or((0.0, 0.0))
+}
+
+// Candidate centers along a path: (x, y, on_line)
+fn candidates(path: &[(f64, f64)], w: f64, h: f64) -> Vec<(f64, f64, bool)> {
+    let mut out = Vec::new();
+    for seg in path.windows(2) {
+        let (a, b) = (seg[0], seg[1]);
+        let len = (b.0 - a.0).hypot(b.1 - a.1);
+        if len < 1.0 { continue; }
+        // unit normal; below a horizontal run and left of a vertical one come first
+        let (nx, ny) = (-(b.1 - a.1) / len, (b.0 - a.0) / len);
+        let (nx, ny) = if ny < -1e-9 || (ny.abs() <= 1e-9 && nx > 0.0) { (-nx, -ny) } else { (nx, ny) };
+        let off = nx.abs() * w / 2.0 + ny.abs() * h / 2.0 + GAP;
+        for t in ALONG {
+            let (px, py) = (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t);
+            out.push((px + nx * off, py + ny * off, false));
+            out.push((px - nx * off, py - ny * off, false));
+            out.push((px, py, true));
+        }
+    }
+    out
+}
+
+/// Place labels one by one (in order), avoiding `nodes` (boxes as `(x0, y0, x1, y1)`), every
+/// path in `paths`, and each other. Returns one placement per request.
+pub fn place_labels(nodes: &[(f64, f64, f64, f64)], paths: &[Vec<(f64, f64)>], requests: &[LabelRequest]) -> Vec<Placement> {
+    let nodes: Vec<Rect> = nodes.iter().map(|&(x0, y0, x1, y1)| Rect { x0, y0, x1, y1 }).collect();
+    let mut placed: Vec<Rect> = Vec::new();
+    let mut out = Vec::with_capacity(requests.len());
+    for req in requests {
+        let own = &paths[req.path];
+        let middle = path_middle(own);
+        let collision = |r: &Rect, on_line: bool| {
+            let mut cost: f64 = nodes.iter().map(|n| r.overlap(n)).sum::<f64>() + placed.iter().map(|p| r.overlap(p)).sum::<f64>();
+            for (i, path) in paths.iter().enumerate() {
+                // a boxed label covers its own arrow on purpose
+                if on_line && i == req.path { continue; }
+                cost += path.windows(2).map(|s| r.clip_length(s[0], s[1])).sum::<f64>() * ARROW_WEIGHT;
+            }
+            cost
+        };
+        let mut best_free: Option<(f64, (f64, f64))> = None;
+        let mut best_boxed: Option<(f64, (f64, f64))> = None;
+        for (x, y, on_line) in candidates(own, req.w, req.h) {
+            let r = Rect::centered(x, y, req.w, req.h);
+            let hit = collision(&r, on_line);
+            let score = (x - middle.0).hypot(y - middle.1) * CENTER_WEIGHT;
+            if on_line {
+                let total = hit + score;
+                if best_boxed.map(|(b, _)| total < b).unwrap_or(true) { best_boxed = Some((total, (x, y))); }
+            } else if hit == 0.0 && best_free.map(|(b, _)| score < b).unwrap_or(true) {
+                best_free = Some((score, (x, y)));
+            }
+        }
+        let placement = match (best_free, best_boxed) {
+            (Some((_, (x, y))), _) => Placement { x, y, boxed: false },
+            (None, Some((_, (x, y)))) => Placement { x, y, boxed: true },
+            (None, None) => Placement { x: middle.0, y: middle.1, boxed: true },
+        };
+        placed.push(Rect::centered(placement.x, placement.y, req.w, req.h));
+        out.push(placement);
+    }
+    out
+}
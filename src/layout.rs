@@ -0,0 +1,851 @@
+use crate::state::{GraphData, LayoutHints};
+use crate::shapes::node_box;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::f64::consts::PI;
+
+// Layout engines, selected by `LayoutHints.algorithm` through a small registry:
+// longest_path (default), layered (Sugiyama), force, radial, grid, tree and preserve.
+// All engines write node centers into `NodeData.x/y`.
+
+/// Minimum free space between two boxes in the same rank or in consecutive ranks.
+const MIN_SEP: f64 = 24.0;
+/// Secondary-axis extent reserved for the dummy nodes that carry long edges.
+const DUMMY_EXTENT: f64 = 16.0;
+const ORDER_SWEEPS: usize = 24;
+const COORD_SWEEPS: usize = 8;
+
+/// Rendered box size for a node label, wrapped and measured as the Excalidraw emitter draws it.
+pub fn approx_node_size(label: &str) -> (f64, f64) {
+    let text = crate::text::node_label(label);
+    let w = (text.width + 30.0).max(100.0);
+    let h = (text.height + 28.0).max(48.0);
+    (w, h)
+}
+
+fn is_vertical(dir: &str) -> bool {
+    matches!(dir, "TB" | "TD" | "BT")
+}
+
+// Reverse DFS back edges so the graph becomes acyclic; returns the oriented edge list
+fn break_cycles(n: usize, edges: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut out_adj: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for (i, &(s, _)) in edges.iter().enumerate() { out_adj[s].push(i); }
+    // 0 = unvisited, 1 = on stack, 2 = done
+    let mut state = vec![0u8; n];
+    let mut reversed = vec![false; edges.len()];
+    for root in 0..n {
+        if state[root] != 0 { continue; }
+        let mut stack: Vec<(usize, usize)> = vec![(root, 0)];
+        state[root] = 1;
+        while let Some((u, k)) = stack.pop() {
+            if k < out_adj[u].len() {
+                stack.push((u, k + 1));
+                let ei = out_adj[u][k];
+                let v = edges[ei].1;
+                match state[v] {
+                    0 => { state[v] = 1; stack.push((v, 0)); }
+                    1 => reversed[ei] = true,
+                    _ => {}
+                }
+            } else {
+                state[u] = 2;
+            }
+        }
+    }
+    edges.iter().zip(reversed).map(|(&(s, t), r)| if r { (t, s) } else { (s, t) }).collect()
+}
+
+// Longest-path ranks, then sources are pulled down next to their first successor
+fn assign_ranks(n: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+    let mut indeg = vec![0usize; n];
+    let mut succ: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut pred: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for &(s, t) in edges {
+        succ[s].push(t);
+        pred[t].push(s);
+        indeg[t] += 1;
+    }
+    let mut order: Vec<usize> = (0..n).filter(|&i| indeg[i] == 0).collect();
+    let mut head = 0;
+    while head < order.len() {
+        let u = order[head];
+        head += 1;
+        for &v in &succ[u] {
+            indeg[v] -= 1;
+            if indeg[v] == 0 { order.push(v); }
+        }
+    }
+    let mut rank = vec![0usize; n];
+    for &u in &order {
+        for &v in &succ[u] { rank[v] = rank[v].max(rank[u] + 1); }
+    }
+    for &u in order.iter().rev() {
+        if pred[u].is_empty() && !succ[u].is_empty() {
+            rank[u] = succ[u].iter().map(|&v| rank[v]).min().unwrap_or(1).saturating_sub(1);
+        }
+    }
+    rank
+}
+
+fn count_crossings(upper: &[usize], lower: &[usize], pos: &[usize], down: &[Vec<usize>]) -> usize {
+    let lower_set: Vec<bool> = {
+        let mut v = vec![false; pos.len()];
+        for &u in lower { v[u] = true; }
+        v
+    };
+    let mut pairs: Vec<(usize, usize)> = Vec::new();
+    for &u in upper {
+        for &v in &down[u] {
+            if lower_set[v] { pairs.push((pos[u], pos[v])); }
+        }
+    }
+    pairs.sort_unstable();
+    // Inversions of the lower ends, counted with a Fenwick tree over lower positions
+    // (Barth, Juenger and Mutzel): O(E log V) instead of comparing every pair of edges
+    let mut tree = vec![0usize; lower.len() + 1];
+    let mut crossings = 0;
+    for (i, &(_, p)) in pairs.iter().enumerate() {
+        let mut k = p + 1;
+        let mut not_above = 0;
+        while k > 0 {
+            not_above += tree[k];
+            k &= k - 1;
+        }
+        crossings += i - not_above;
+        let mut k = p + 1;
+        while k < tree.len() {
+            tree[k] += 1;
+            k += k & k.wrapping_neg();
+        }
+    }
+    crossings
+}
+
+// Crossings between the edges of `u` and those of `v` (same layer, `u` left of `v`) toward
+// `neighbors`; swapping the two changes only these
+fn pair_crossings(u: usize, v: usize, pos: &[usize], neighbors: &[Vec<usize>]) -> usize {
+    neighbors[u].iter().map(|&a| neighbors[v].iter().filter(|&&b| pos[a] > pos[b]).count()).sum()
+}
+
+fn total_crossings(layers: &[Vec<usize>], pos: &[usize], down: &[Vec<usize>]) -> usize {
+    layers.windows(2).map(|w| count_crossings(&w[0], &w[1], pos, down)).sum()
+}
+
+fn median(mut vals: Vec<f64>) -> Option<f64> {
+    if vals.is_empty() { return None; }
+    vals.sort_by(f64::total_cmp);
+    let m = vals.len() / 2;
+    Some(if vals.len() % 2 == 1 {
+        vals[m]
+    } else if vals.len() == 2 {
+        (vals[0] + vals[1]) / 2.0
+    } else {
+        // Weighted median (Gansner et al.): lean toward the side whose neighbors are packed tighter
+        let left = vals[m - 1] - vals[0];
+        let right = vals[vals.len() - 1] - vals[m];
+        if left + right == 0.0 { (vals[m - 1] + vals[m]) / 2.0 } else { (vals[m - 1] * right + vals[m] * left) / (left + right) }
+    })
+}
+
+fn reindex(layer: &[usize], pos: &mut [usize]) {
+    for (i, &v) in layer.iter().enumerate() { pos[v] = i; }
+}
+
+// Reorder `layer` by the median position of its neighbors in the fixed adjacent layer
+fn median_sweep(layer: &mut [usize], pos: &mut [usize], neighbors: &[Vec<usize>]) {
+    let keys: HashMap<usize, f64> = layer
+        .iter()
+        .map(|&v| {
+            let m = median(neighbors[v].iter().map(|&u| pos[u] as f64).collect());
+            // Nodes without neighbors keep their current slot
+            (v, m.unwrap_or(pos[v] as f64))
+        })
+        .collect();
+    layer.sort_by(|a, b| keys[a].total_cmp(&keys[b]).then(pos[*a].cmp(&pos[*b])));
+    reindex(layer, pos);
+}
+
+// Swap adjacent nodes while that lowers crossings with both neighboring layers
+fn transpose(layers: &mut [Vec<usize>], pos: &mut [usize], up: &[Vec<usize>], down: &[Vec<usize>]) {
+    let mut improved = true;
+    let mut rounds = 0;
+    while improved && rounds < 8 {
+        improved = false;
+        rounds += 1;
+        for layer in layers.iter_mut() {
+            for i in 0..layer.len().saturating_sub(1) {
+                let (u, v) = (layer[i], layer[i + 1]);
+                let kept = pair_crossings(u, v, pos, up) + pair_crossings(u, v, pos, down);
+                let swapped = pair_crossings(v, u, pos, up) + pair_crossings(v, u, pos, down);
+                if swapped < kept {
+                    layer.swap(i, i + 1);
+                    pos.swap(u, v);
+                    improved = true;
+                }
+            }
+        }
+    }
+}
+
+// Minimum center distance between neighbors in a layer; real nodes also keep the requested pitch
+fn min_distance(a: f64, b: f64, pitch: f64) -> f64 {
+    let sep = (a + b) / 2.0 + MIN_SEP;
+    if a > DUMMY_EXTENT && b > DUMMY_EXTENT { sep.max(pitch) } else { sep }
+}
+
+// Place a layer as close as possible to `desired` while keeping order and minimum separation.
+// Averaging a left-packed and a right-packed feasible solution keeps the result feasible.
+fn place_layer(layer: &[usize], desired: &[f64], extent: &[f64], pitch: f64, coord: &mut [f64]) {
+    let k = layer.len();
+    if k == 0 { return; }
+    let gap = |a: usize, b: usize| min_distance(extent[layer[a]], extent[layer[b]], pitch);
+    let mut fwd = vec![0.0; k];
+    for i in 0..k {
+        fwd[i] = if i == 0 { desired[0] } else { desired[i].max(fwd[i - 1] + gap(i - 1, i)) };
+    }
+    let mut bwd = vec![0.0; k];
+    for i in (0..k).rev() {
+        bwd[i] = if i == k - 1 { desired[i] } else { desired[i].min(bwd[i + 1] - gap(i, i + 1)) };
+    }
+    for i in 0..k { coord[layer[i]] = (fwd[i] + bwd[i]) / 2.0; }
+}
+
+/// Sugiyama-style layered layout. Positions are node centers; `rank_sep` is the minimum distance
+/// between consecutive ranks and `node_sep` the minimum pitch between neighbors within a rank,
+/// both grown as needed so shape-aware node boxes never overlap.
+pub fn layered_layout(g: &mut GraphData, rank_sep: f64, node_sep: f64, dir: &str) {
+    let n = g.nodes.len();
+    if n == 0 { return; }
+    let index: HashMap<&str, usize> = g.nodes.iter().enumerate().map(|(i, nd)| (nd.id.as_str(), i)).collect();
+    let mut raw: Vec<(usize, usize)> = Vec::new();
+    for e in &g.edges {
+        if let (Some(&s), Some(&t)) = (index.get(e.source.as_str()), index.get(e.target.as_str())) {
+            if s != t && !raw.contains(&(s, t)) { raw.push((s, t)); }
+        }
+    }
+    let edges = break_cycles(n, &raw);
+    let rank = assign_ranks(n, &edges);
+
+    let vertical = is_vertical(dir);
+    let sizes: Vec<(f64, f64)> = g.nodes.iter().map(node_box).collect();
+    // (secondary, primary) extent per virtual node
+    let mut extent: Vec<f64> = sizes.iter().map(|&(w, h)| if vertical { w } else { h }).collect();
+    let mut primary_extent: Vec<f64> = sizes.iter().map(|&(w, h)| if vertical { h } else { w }).collect();
+    let mut vrank = rank.clone();
+
+    // Split long edges with dummy nodes so every edge spans one rank
+    let mut down: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut up: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for &(s, t) in &edges {
+        let (s, t) = if rank[s] <= rank[t] { (s, t) } else { (t, s) };
+        let mut prev = s;
+        for r in rank[s] + 1..rank[t] {
+            let d = extent.len();
+            extent.push(DUMMY_EXTENT);
+            primary_extent.push(0.0);
+            vrank.push(r);
+            down.push(Vec::new());
+            up.push(Vec::new());
+            down[prev].push(d);
+            up[d].push(prev);
+            prev = d;
+        }
+        down[prev].push(t);
+        up[t].push(prev);
+    }
+    let total = vrank.len();
+    let max_rank = vrank.iter().copied().max().unwrap_or(0);
+
+    // Initial order: DFS from sources in input order keeps related nodes together
+    let mut layers: Vec<Vec<usize>> = vec![Vec::new(); max_rank + 1];
+    let mut seen = vec![false; total];
+    for root in 0..n {
+        if seen[root] || !up[root].is_empty() { continue; }
+        let mut stack = vec![root];
+        while let Some(u) = stack.pop() {
+            if seen[u] { continue; }
+            seen[u] = true;
+            layers[vrank[u]].push(u);
+            for &v in down[u].iter().rev() { stack.push(v); }
+        }
+    }
+    for v in 0..total {
+        if !seen[v] { layers[vrank[v]].push(v); }
+    }
+    let mut pos = vec![0usize; total];
+    for layer in &layers { reindex(layer, &mut pos); }
+
+    // Ordering sweeps: keep the best ordering seen
+    let mut best = layers.clone();
+    let mut best_crossings = total_crossings(&layers, &pos, &down);
+    for iter in 0..ORDER_SWEEPS {
+        if best_crossings == 0 { break; }
+        if iter % 2 == 0 {
+            for layer in layers.iter_mut().skip(1) { median_sweep(layer, &mut pos, &up); }
+        } else {
+            for layer in layers.iter_mut().rev().skip(1) { median_sweep(layer, &mut pos, &down); }
+        }
+        transpose(&mut layers, &mut pos, &up, &down);
+        let c = total_crossings(&layers, &pos, &down);
+        if c < best_crossings {
+            best_crossings = c;
+            best = layers.clone();
+        }
+    }
+    let layers = best;
+    for layer in &layers { reindex(layer, &mut pos); }
+
+    // Secondary coordinates: pack, then pull each layer toward its neighbors' average
+    let mut coord = vec![0.0f64; total];
+    for layer in &layers {
+        let mut x = 0.0;
+        for (i, &v) in layer.iter().enumerate() {
+            if i > 0 { x += min_distance(extent[layer[i - 1]], extent[v], node_sep); }
+            coord[v] = x;
+        }
+    }
+    for iter in 0..COORD_SWEEPS {
+        let (range, nbrs): (Vec<usize>, &Vec<Vec<usize>>) = if iter % 2 == 0 {
+            ((1..layers.len()).collect(), &up)
+        } else {
+            ((0..layers.len().saturating_sub(1)).rev().collect(), &down)
+        };
+        for r in range {
+            let layer = &layers[r];
+            let desired: Vec<f64> = layer
+                .iter()
+                .map(|&v| {
+                    if nbrs[v].is_empty() { coord[v] } else { nbrs[v].iter().map(|&u| coord[u]).sum::<f64>() / nbrs[v].len() as f64 }
+                })
+                .collect();
+            place_layer(layer, &desired, &extent, node_sep, &mut coord);
+        }
+    }
+
+    // Primary coordinates: ranks at least `rank_sep` apart and never overlapping
+    let mut rank_pos = vec![0.0f64; layers.len()];
+    let rank_extent: Vec<f64> = layers.iter().map(|l| l.iter().map(|&v| primary_extent[v]).fold(0.0, f64::max)).collect();
+    for r in 1..layers.len() {
+        rank_pos[r] = rank_pos[r - 1] + rank_sep.max((rank_extent[r - 1] + rank_extent[r]) / 2.0 + MIN_SEP * 2.0);
+    }
+
+    // Center the drawing on the secondary axis
+    let (lo, hi) = (0..n).fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| (lo.min(coord[v]), hi.max(coord[v])));
+    let mid = if lo.is_finite() { (lo + hi) / 2.0 } else { 0.0 };
+
+    let coords: Vec<(f64, f64)> = (0..n).map(|i| (coord[i] - mid, rank_pos[vrank[i]])).collect();
+    apply_directed(g, &coords, dir);
+}
+
+/// Spacing for a layout run. As in `apply_auto_layout`, `rank_sep` separates consecutive ranks
+/// and `node_sep` separates neighbors within a rank; `max_per_rank` wraps long ranks into rows.
+#[derive(Debug, Clone)]
+pub struct LayoutOptions {
+    pub direction: String,
+    pub rank_sep: f64,
+    pub node_sep: f64,
+    pub max_per_rank: usize,
+}
+
+impl LayoutOptions {
+    /// Caller defaults, overridden by positive gaps/limits set in `LayoutHints`.
+    pub fn from_hints(hints: Option<&LayoutHints>, direction: &str, rank_sep: f64, node_sep: f64, max_per_rank: usize) -> Self {
+        let positive = |v: Option<f32>| v.map(|v| v as f64).filter(|v| *v > 0.0);
+        LayoutOptions {
+            direction: direction.to_uppercase(),
+            rank_sep: hints.and_then(|h| positive(h.rank_sep)).unwrap_or(rank_sep),
+            node_sep: hints.and_then(|h| positive(h.node_sep)).unwrap_or(node_sep),
+            max_per_rank: hints.and_then(|h| h.max_per_rank).filter(|m| *m > 0).unwrap_or(max_per_rank).max(1),
+        }
+    }
+}
+
+/// A layout algorithm that can be selected via `LayoutHints.algorithm`.
+pub trait LayoutEngine: Sync {
+    /// Canonical algorithm name.
+    fn name(&self) -> &'static str;
+    /// Other names accepted for this engine (Graphviz/dagre vocabulary, synonyms).
+    fn aliases(&self) -> &'static [&'static str] { &[] }
+    fn layout(&self, g: &mut GraphData, opts: &LayoutOptions);
+}
+
+struct LongestPath;
+struct Layered;
+struct Force;
+struct Radial;
+struct Grid;
+struct Tree;
+struct Preserve;
+
+impl LayoutEngine for LongestPath {
+    fn name(&self) -> &'static str { "longest_path" }
+    fn aliases(&self) -> &'static [&'static str] { &["longestpath", "rank", "default"] }
+    fn layout(&self, g: &mut GraphData, opts: &LayoutOptions) { longest_path_layout(g, opts) }
+}
+
+impl LayoutEngine for Layered {
+    fn name(&self) -> &'static str { "layered" }
+    fn aliases(&self) -> &'static [&'static str] { &["sugiyama", "dagre", "dot", "hierarchical", "elk"] }
+    fn layout(&self, g: &mut GraphData, opts: &LayoutOptions) { layered_layout(g, opts.rank_sep, opts.node_sep, &opts.direction) }
+}
+
+impl LayoutEngine for Force {
+    fn name(&self) -> &'static str { "force" }
+    fn aliases(&self) -> &'static [&'static str] { &["force_directed", "spring", "fdp", "neato"] }
+    fn layout(&self, g: &mut GraphData, opts: &LayoutOptions) { force_layout(g, opts) }
+}
+
+impl LayoutEngine for Radial {
+    fn name(&self) -> &'static str { "radial" }
+    fn aliases(&self) -> &'static [&'static str] { &["twopi", "circular", "circo"] }
+    fn layout(&self, g: &mut GraphData, opts: &LayoutOptions) { radial_layout(g, opts) }
+}
+
+impl LayoutEngine for Grid {
+    fn name(&self) -> &'static str { "grid" }
+    fn aliases(&self) -> &'static [&'static str] { &["matrix"] }
+    fn layout(&self, g: &mut GraphData, opts: &LayoutOptions) { grid_layout(g, opts) }
+}
+
+impl LayoutEngine for Tree {
+    fn name(&self) -> &'static str { "tree" }
+    fn aliases(&self) -> &'static [&'static str] { &["tidy", "tidy_tree", "hierarchy"] }
+    fn layout(&self, g: &mut GraphData, opts: &LayoutOptions) { tree_layout(g, opts) }
+}
+
+impl LayoutEngine for Preserve {
+    fn name(&self) -> &'static str { "preserve" }
+    fn aliases(&self) -> &'static [&'static str] { &["manual", "fixed", "none"] }
+    fn layout(&self, g: &mut GraphData, opts: &LayoutOptions) {
+        // Keep the given coordinates, unless they are all the same point (e.g. the schema's 0,0)
+        let distinct = g.nodes.iter().any(|n| (n.x, n.y) != (g.nodes[0].x, g.nodes[0].y));
+        if !distinct { longest_path_layout(g, opts); }
+    }
+}
+
+static ENGINES: &[&dyn LayoutEngine] = &[&LongestPath, &Layered, &Force, &Radial, &Grid, &Tree, &Preserve];
+
+/// All registered layout engines, default first.
+pub fn engines() -> &'static [&'static dyn LayoutEngine] {
+    ENGINES
+}
+
+/// Look up an engine by name or alias (case-insensitive; `-` and spaces count as `_`).
+pub fn engine_for(algorithm: &str) -> Option<&'static dyn LayoutEngine> {
+    let key = algorithm.trim().to_lowercase().replace(['-', ' '], "_");
+    ENGINES.iter().copied().find(|e| e.name() == key || e.aliases().contains(&key.as_str()))
+}
+
+/// Lay out `g` with the engine named in its `LayoutHints`, falling back to longest_path.
+pub fn run_layout(g: &mut GraphData, opts: &LayoutOptions) {
+    let algorithm = g.layout_hints.as_ref().map(|h| h.algorithm.clone()).unwrap_or_default();
+    engine_for(&algorithm).unwrap_or(&LongestPath).layout(g, opts);
+}
+
+fn node_index(g: &GraphData) -> HashMap<&str, usize> {
+    g.nodes.iter().enumerate().map(|(i, nd)| (nd.id.as_str(), i)).collect()
+}
+
+// Distinct, non-self edges between known nodes, as index pairs in input order
+fn index_edges(g: &GraphData) -> Vec<(usize, usize)> {
+    let index = node_index(g);
+    let mut out: Vec<(usize, usize)> = Vec::new();
+    for e in &g.edges {
+        if let (Some(&s), Some(&t)) = (index.get(e.source.as_str()), index.get(e.target.as_str())) {
+            if s != t && !out.contains(&(s, t)) { out.push((s, t)); }
+        }
+    }
+    out
+}
+
+// Write (secondary, primary) coordinates back, mapping them onto the direction's axes
+fn apply_directed(g: &mut GraphData, coords: &[(f64, f64)], dir: &str) {
+    let vertical = is_vertical(dir);
+    for (nd, &(secondary, primary)) in g.nodes.iter_mut().zip(coords) {
+        let (x, y) = if vertical { (secondary, primary) } else { (primary, secondary) };
+        nd.x = if dir == "RL" { -x } else { x } as f32;
+        nd.y = if dir == "BT" { -y } else { y } as f32;
+    }
+}
+
+// Ranks by longest path from the sources, packed `max_per_rank` per row
+fn longest_path_layout(g: &mut GraphData, opts: &LayoutOptions) {
+    let (dir, rank_sep, node_sep) = (opts.direction.as_str(), opts.rank_sep, opts.node_sep);
+    // Build adjacency and indegree
+    let mut adj: HashMap<String, Vec<String>> = HashMap::new();
+    let mut indeg: HashMap<String, usize> = HashMap::new();
+    for n in &g.nodes { indeg.entry(n.id.clone()).or_insert(0); adj.entry(n.id.clone()).or_default(); }
+    for e in &g.edges {
+        if let (Some(_), Some(_)) = (indeg.get(&e.source), indeg.get(&e.target)) {
+            adj.entry(e.source.clone()).or_default().push(e.target.clone());
+            *indeg.entry(e.target.clone()).or_insert(0) += 1;
+        }
+    }
+
+    // Kahn topo, seeded in input order so the result is deterministic
+    let mut q: VecDeque<String> = g.nodes.iter().filter(|n| indeg[&n.id] == 0).map(|n| n.id.clone()).collect();
+    let mut order: Vec<String> = Vec::new();
+    let mut indeg_mut = indeg.clone();
+    while let Some(u) = q.pop_front() {
+        order.push(u.clone());
+        if let Some(vs) = adj.get(&u) {
+            for v in vs {
+                if let Some(d) = indeg_mut.get_mut(v) {
+                    if *d > 0 { *d -= 1; if *d == 0 { q.push_back(v.clone()); } }
+                }
+            }
+        }
+    }
+    if order.len() != g.nodes.len() {
+        // Graph may have cycles; fall back to input order
+        order = g.nodes.iter().map(|n| n.id.clone()).collect();
+    }
+
+    // Longest-path rank assignment
+    let mut rank: HashMap<String, usize> = HashMap::new();
+    for id in &order { rank.insert(id.clone(), 0); }
+    for u in &order {
+        let ru = *rank.get(u).unwrap_or(&0);
+        if let Some(vs) = adj.get(u) {
+            for v in vs {
+                let entry = rank.entry(v.clone()).or_insert(0);
+                if ru + 1 > *entry { *entry = ru + 1; }
+            }
+        }
+    }
+
+    // Group nodes by rank preserving relative order
+    let mut by_rank: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+    for id in &order {
+        let r = *rank.get(id).unwrap_or(&0);
+        by_rank.entry(r).or_default().push(id.clone());
+    }
+
+    // Map id -> position with multi-row packing per rank
+    let mut pos: HashMap<String, (f64, f64)> = HashMap::new();
+    for (r, ids) in &by_rank {
+        let r = *r as f64;
+        let chunks: Vec<&[String]> = ids.chunks(opts.max_per_rank).collect();
+        // center rows/cols around 0
+        let rows = chunks.len() as f64;
+        let start_secondary = -((rows - 1.0) * node_sep) / 2.0;
+        for (row_idx, chunk) in chunks.iter().enumerate() {
+            let count_primary = chunk.len() as f64;
+            let start_primary = -((count_primary - 1.0) * node_sep) / 2.0;
+            for (col_idx, id) in chunk.iter().enumerate() {
+                let col = col_idx as f64;
+                let row = row_idx as f64;
+                // ranks advance along the primary axis; rows of a wrapped rank stack along it too
+                let primary = r * rank_sep + (start_secondary + row * node_sep);
+                let secondary = start_primary + col * node_sep;
+                pos.insert((*id).clone(), (secondary, primary));
+            }
+        }
+    }
+
+    let coords: Vec<(f64, f64)> = g.nodes.iter().map(|n| pos.get(&n.id).copied().unwrap_or((0.0, 0.0))).collect();
+    apply_directed(g, &coords, dir);
+}
+
+// Row-major grid in input order; a "rank" is one row (TB) or one column (LR)
+fn grid_layout(g: &mut GraphData, opts: &LayoutOptions) {
+    if g.nodes.is_empty() { return; }
+    let vertical = is_vertical(&opts.direction);
+    let sizes: Vec<(f64, f64)> = g.nodes.iter().map(node_box).collect();
+    let (max_w, max_h) = sizes.iter().fold((0.0f64, 0.0f64), |(w, h), &(a, b)| (w.max(a), h.max(b)));
+    let (ext_secondary, ext_primary) = if vertical { (max_w, max_h) } else { (max_h, max_w) };
+    let pitch_secondary = (ext_secondary + MIN_SEP).max(opts.node_sep);
+    let pitch_primary = (ext_primary + MIN_SEP).max(opts.node_sep);
+    let per_line = opts.max_per_rank.min(g.nodes.len());
+    let lines = g.nodes.len().div_ceil(per_line);
+    let coords: Vec<(f64, f64)> = (0..g.nodes.len())
+        .map(|i| {
+            let (line, slot) = (i / per_line, i % per_line);
+            let secondary = (slot as f64 - (per_line as f64 - 1.0) / 2.0) * pitch_secondary;
+            let primary = (line as f64 - (lines as f64 - 1.0) / 2.0) * pitch_primary;
+            (secondary, primary)
+        })
+        .collect();
+    apply_directed(g, &coords, &opts.direction);
+}
+
+// Tidy tree: BFS spanning forest from the sources; leaves are packed side by side and each
+// parent is centered over its children. Non-tree edges are drawn but do not affect placement.
+fn tree_layout(g: &mut GraphData, opts: &LayoutOptions) {
+    let n = g.nodes.len();
+    if n == 0 { return; }
+    let edges = index_edges(g);
+    let mut succ: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut indeg = vec![0usize; n];
+    for &(s, t) in &edges {
+        succ[s].push(t);
+        indeg[t] += 1;
+    }
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut depth = vec![0usize; n];
+    let mut seen = vec![false; n];
+    let mut roots: Vec<usize> = Vec::new();
+    // Sources first, then any node left unreached (cycles)
+    let candidates: Vec<usize> = (0..n).filter(|&i| indeg[i] == 0).chain(0..n).collect();
+    for root in candidates {
+        if seen[root] { continue; }
+        seen[root] = true;
+        roots.push(root);
+        let mut q = VecDeque::from([root]);
+        while let Some(u) = q.pop_front() {
+            for &v in &succ[u] {
+                if seen[v] { continue; }
+                seen[v] = true;
+                depth[v] = depth[u] + 1;
+                children[u].push(v);
+                q.push_back(v);
+            }
+        }
+    }
+
+    let vertical = is_vertical(&opts.direction);
+    let sizes: Vec<(f64, f64)> = g.nodes.iter().map(node_box).collect();
+    let extent: Vec<f64> = sizes.iter().map(|&(w, h)| if vertical { w } else { h }).collect();
+    let primary_extent: Vec<f64> = sizes.iter().map(|&(w, h)| if vertical { h } else { w }).collect();
+
+    // Each subtree owns an interval as wide as the larger of its own box and its children's
+    // intervals; children are centered inside it, so subtrees can never overlap
+    let own = |u: usize| extent[u].max(opts.node_sep - MIN_SEP) + MIN_SEP;
+    let mut width = vec![0.0f64; n];
+    let mut post: Vec<usize> = Vec::new();
+    let mut stack: Vec<usize> = roots.clone();
+    while let Some(u) = stack.pop() {
+        post.push(u);
+        stack.extend(children[u].iter().copied());
+    }
+    for &u in post.iter().rev() {
+        width[u] = own(u).max(children[u].iter().map(|&c| width[c]).sum());
+    }
+    let mut secondary = vec![0.0f64; n];
+    let mut left = 0.0f64;
+    let mut stack: Vec<(usize, f64)> = Vec::new();
+    for &root in &roots {
+        stack.push((root, left));
+        left += width[root];
+    }
+    while let Some((u, start)) = stack.pop() {
+        secondary[u] = start + width[u] / 2.0;
+        let block: f64 = children[u].iter().map(|&c| width[c]).sum();
+        let mut x = start + (width[u] - block) / 2.0;
+        for &c in &children[u] {
+            stack.push((c, x));
+            x += width[c];
+        }
+    }
+
+    // Depths at least `rank_sep` apart and never overlapping
+    let max_depth = depth.iter().copied().max().unwrap_or(0);
+    let mut depth_extent = vec![0.0f64; max_depth + 1];
+    for i in 0..n { depth_extent[depth[i]] = depth_extent[depth[i]].max(primary_extent[i]); }
+    let mut depth_pos = vec![0.0f64; max_depth + 1];
+    for d in 1..=max_depth {
+        depth_pos[d] = depth_pos[d - 1] + opts.rank_sep.max((depth_extent[d - 1] + depth_extent[d]) / 2.0 + MIN_SEP * 2.0);
+    }
+
+    let mid = left / 2.0;
+    let coords: Vec<(f64, f64)> = (0..n).map(|i| (secondary[i] - mid, depth_pos[depth[i]])).collect();
+    apply_directed(g, &coords, &opts.direction);
+}
+
+/// Undirected BFS spanning tree around the central node (the best-connected source). Nodes of
+/// other components hang off the root as extra first-level branches.
+pub struct SpanningTree {
+    pub root: usize,
+    pub parent: Vec<Option<usize>>,
+    pub children: Vec<Vec<usize>>,
+    pub depth: Vec<usize>,
+}
+
+pub fn spanning_tree(g: &GraphData) -> Option<SpanningTree> {
+    let n = g.nodes.len();
+    if n == 0 { return None; }
+    let mut nbrs: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut indeg = vec![0usize; n];
+    for (s, t) in index_edges(g) {
+        nbrs[s].push(t);
+        nbrs[t].push(s);
+        indeg[t] += 1;
+    }
+    let best = |it: &mut dyn Iterator<Item = usize>| it.max_by_key(|&i| (nbrs[i].len(), std::cmp::Reverse(i)));
+    let root = best(&mut (0..n).filter(|&i| indeg[i] == 0)).or_else(|| best(&mut (0..n))).unwrap_or(0);
+
+    let mut parent: Vec<Option<usize>> = vec![None; n];
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut depth = vec![usize::MAX; n];
+    for start in std::iter::once(root).chain(0..n) {
+        if depth[start] != usize::MAX { continue; }
+        if start == root {
+            depth[start] = 0;
+        } else {
+            depth[start] = 1;
+            parent[start] = Some(root);
+            children[root].push(start);
+        }
+        let mut q = VecDeque::from([start]);
+        while let Some(u) = q.pop_front() {
+            for &v in &nbrs[u] {
+                if depth[v] == usize::MAX {
+                    depth[v] = depth[u] + 1;
+                    parent[v] = Some(u);
+                    children[u].push(v);
+                    q.push_back(v);
+                }
+            }
+        }
+    }
+    Some(SpanningTree { root, parent, children, depth })
+}
+
+// Radial tree: the root sits at the origin, every subtree gets an angular wedge proportional to
+// its leaf count, and depths lie on concentric rings spaced so boxes never overlap.
+fn radial_layout(g: &mut GraphData, opts: &LayoutOptions) {
+    let Some(tree) = spanning_tree(g) else { return };
+    let n = g.nodes.len();
+
+    // Leaf counts bottom-up (BFS order reversed)
+    let mut order: Vec<usize> = vec![tree.root];
+    let mut head = 0;
+    while head < order.len() {
+        let u = order[head];
+        head += 1;
+        order.extend(tree.children[u].iter().copied());
+    }
+    let mut leaves = vec![0usize; n];
+    for &u in order.iter().rev() {
+        leaves[u] = tree.children[u].iter().map(|&c| leaves[c]).sum::<usize>().max(1);
+    }
+
+    // Wedges top-down; the first level starts at 12 o'clock
+    let mut angle = vec![0.0f64; n];
+    let mut wedge = vec![0.0f64; n];
+    wedge[tree.root] = 2.0 * PI;
+    let mut wedge_start = vec![0.0f64; n];
+    // Center the first branch at 12 o'clock
+    let first_share = tree.children[tree.root].first().map(|&c| leaves[c] as f64 / leaves[tree.root] as f64).unwrap_or(0.0);
+    wedge_start[tree.root] = -PI / 2.0 - PI * first_share;
+    for &u in &order {
+        let mut start = wedge_start[u];
+        for &c in &tree.children[u] {
+            wedge[c] = wedge[u] * leaves[c] as f64 / leaves[u] as f64;
+            wedge_start[c] = start;
+            angle[c] = start + wedge[c] / 2.0;
+            start += wedge[c];
+        }
+    }
+
+    // Ring radii: at least `rank_sep` apart, far enough from the previous ring for the widest
+    // boxes, and large enough that neighbors on the ring (by chord length) don't touch
+    let sizes: Vec<(f64, f64)> = g.nodes.iter().map(node_box).collect();
+    let max_depth = tree.depth.iter().copied().max().unwrap_or(0);
+    let mut rings: Vec<Vec<usize>> = vec![Vec::new(); max_depth + 1];
+    for &u in &order { rings[tree.depth[u]].push(u); }
+    let mut radius = vec![0.0f64; max_depth + 1];
+    let widest = |ring: &[usize]| ring.iter().map(|&v| sizes[v].0).fold(0.0, f64::max);
+    for d in 1..=max_depth {
+        let mut r = radius[d - 1] + opts.rank_sep.max((widest(&rings[d - 1]) + widest(&rings[d])) / 2.0 + MIN_SEP);
+        let mut ring = rings[d].clone();
+        ring.sort_by(|a, b| angle[*a].total_cmp(&angle[*b]));
+        if ring.len() > 1 {
+            for (i, &a) in ring.iter().enumerate() {
+                let b = ring[(i + 1) % ring.len()];
+                let mut delta = angle[b] - angle[a];
+                if delta <= 0.0 { delta += 2.0 * PI; }
+                let need = (sizes[a].0 + sizes[b].0) / 2.0 + MIN_SEP;
+                r = r.max(need / (2.0 * (delta.min(PI) / 2.0).sin()));
+            }
+        }
+        radius[d] = r;
+    }
+
+    for (i, nd) in g.nodes.iter_mut().enumerate() {
+        let r = radius[tree.depth[i]];
+        nd.x = (r * angle[i].cos()) as f32;
+        nd.y = (r * angle[i].sin()) as f32;
+    }
+}
+
+const FORCE_ITERATIONS: usize = 300;
+
+// Fruchterman–Reingold from a deterministic circle, then a pass that pushes overlapping boxes apart
+fn force_layout(g: &mut GraphData, opts: &LayoutOptions) {
+    let n = g.nodes.len();
+    if n == 0 { return; }
+    let edges = index_edges(g);
+    let k = opts.rank_sep;
+    let r0 = (k * n as f64 / (2.0 * PI)).max(k);
+    let mut p: Vec<(f64, f64)> = (0..n)
+        .map(|i| {
+            let a = 2.0 * PI * i as f64 / n as f64;
+            (r0 * a.cos(), r0 * a.sin())
+        })
+        .collect();
+    for it in 0..FORCE_ITERATIONS {
+        let temp = k * (1.0 - it as f64 / FORCE_ITERATIONS as f64);
+        let mut disp = vec![(0.0f64, 0.0f64); n];
+        for i in 0..n {
+            for j in i + 1..n {
+                let (dx, dy) = (p[i].0 - p[j].0, p[i].1 - p[j].1);
+                let d = (dx * dx + dy * dy).sqrt().max(1.0);
+                let f = k * k / d;
+                disp[i].0 += dx / d * f;
+                disp[i].1 += dy / d * f;
+                disp[j].0 -= dx / d * f;
+                disp[j].1 -= dy / d * f;
+            }
+        }
+        for &(s, t) in &edges {
+            let (dx, dy) = (p[s].0 - p[t].0, p[s].1 - p[t].1);
+            let d = (dx * dx + dy * dy).sqrt().max(1.0);
+            let f = d * d / k;
+            disp[s].0 -= dx / d * f;
+            disp[s].1 -= dy / d * f;
+            disp[t].0 += dx / d * f;
+            disp[t].1 += dy / d * f;
+        }
+        for i in 0..n {
+            let len = (disp[i].0 * disp[i].0 + disp[i].1 * disp[i].1).sqrt();
+            if len > 0.0 {
+                let step = len.min(temp);
+                p[i].0 += disp[i].0 / len * step;
+                p[i].1 += disp[i].1 / len * step;
+            }
+        }
+    }
+    remove_overlaps(&mut p, &g.nodes.iter().map(node_box).collect::<Vec<_>>());
+
+    let (cx, cy) = p.iter().fold((0.0, 0.0), |(x, y), &(a, b)| (x + a / n as f64, y + b / n as f64));
+    for (nd, (x, y)) in g.nodes.iter_mut().zip(p) {
+        nd.x = (x - cx) as f32;
+        nd.y = (y - cy) as f32;
+    }
+}
+
+// Separate overlapping boxes along the axis of least overlap until none remain
+fn remove_overlaps(p: &mut [(f64, f64)], sizes: &[(f64, f64)]) {
+    for _ in 0..100 {
+        let mut moved = false;
+        for i in 0..p.len() {
+            for j in i + 1..p.len() {
+                let ox = (sizes[i].0 + sizes[j].0) / 2.0 + MIN_SEP - (p[i].0 - p[j].0).abs();
+                let oy = (sizes[i].1 + sizes[j].1) / 2.0 + MIN_SEP - (p[i].1 - p[j].1).abs();
+                if ox <= 0.0 || oy <= 0.0 { continue; }
+                moved = true;
+                if ox < oy {
+                    let s = if p[i].0 < p[j].0 || (p[i].0 == p[j].0 && i < j) { -1.0 } else { 1.0 };
+                    p[i].0 += s * ox / 2.0;
+                    p[j].0 -= s * ox / 2.0;
+                } else {
+                    let s = if p[i].1 < p[j].1 || (p[i].1 == p[j].1 && i < j) { -1.0 } else { 1.0 };
+                    p[i].1 += s * oy / 2.0;
+                    p[j].1 -= s * oy / 2.0;
+                }
+            }
+        }
+        if !moved { break; }
+    }
+}
@@ -2,5 +2,25 @@ pub mod flow;
 pub mod nodes;
 pub mod state;
 pub mod utils;
+pub mod edge_style;
 pub mod excalidraw;
 pub mod server;
+pub mod svg;
+pub mod render;
+pub mod mermaid;
+pub mod dot;
+pub mod drawio;
+pub mod excalidraw_import;
+pub mod labels;
+pub mod layout;
+pub mod llm;
+pub mod llm_json;
+pub mod llm_schema;
+pub mod mock_llm;
+pub mod cassette;
+pub mod routing;
+pub mod mindmap;
+pub mod sequence;
+pub mod shapes;
+pub mod text;
+pub mod validate;
@@ -0,0 +1,590 @@
+// LLM providers behind one trait, chosen per request through a routing table.
+//
+// Routes map (tier, diagram kind, or an explicit route name) to provider + model + limits. They
+// come from the JSON file named by `GRAPHFLOW_LLM_CONFIG`, re-read on every request so models can
+// be reassigned without a rebuild or restart; without a config the built-in table applies
+// (Free -> Anthropic, Pro -> OpenAI, models from `ANTHROPIC_MODEL` / `OPENAI_MODEL_PRO`, or both to
+// the OpenAI-compatible endpoint at `GRAPHFLOW_LLM_BASE_URL` when that is set; a `local` route
+// may name its own `base_url`). `GRAPHFLOW_LLM_PROVIDER` overrides the provider of whichever route
+// matches, so `mock` runs the whole pipeline offline. Provider clients are created once per
+// provider and endpoint and shared; calls can be recorded to and replayed from a cassette (see
+// `cassette`).
+
+use crate::cassette::{CassetteConfig, CassetteMode, CassetteProvider};
+use crate::mock_llm::MockProvider;
+use crate::state::UserTier;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use anthropic_sdk::types::ToolInputSchema;
+use anthropic_sdk::{Anthropic, MessageCreateBuilder, Tool, ToolChoice};
+use async_openai::config::OpenAIConfig;
+use async_openai::types::{
+    ChatCompletionRequestMessage, ChatCompletionRequestSystemMessage, ChatCompletionRequestSystemMessageContent,
+    ChatCompletionRequestUserMessage, ChatCompletionRequestUserMessageContent, CreateChatCompletionRequest,
+    CreateChatCompletionRequestArgs, ResponseFormat, ResponseFormatJsonSchema,
+};
+use async_openai::error::OpenAIError;
+use async_openai::Client as OpenAIClient;
+use futures::StreamExt;
+
+/// System prompt shared by every provider.
+pub const SYSTEM_PROMPT: &str = "You are a diagram generation engine. From any user input, infer the best diagram (flow, system architecture, sequence, or mindmap) and convert it into a clear, structured representation. If the input appears to be notes (bullets, numbered lists, paragraphs), summarize and organize them into the most helpful visual to accelerate understanding. Prefer JSON outputs that match the caller's requested schema. Use concise, readable naming, and pick layouts that minimize crossings. Keep responses compact and free of prose unless explicitly asked.";
+
+/// Environment variable naming the routing config file.
+pub const CONFIG_ENV: &str = "GRAPHFLOW_LLM_CONFIG";
+
+/// Environment variable with the base URL of an OpenAI-compatible endpoint (provider `local`).
+/// When set, the built-in routes use it for every tier.
+pub const BASE_URL_ENV: &str = "GRAPHFLOW_LLM_BASE_URL";
+
+/// Environment variable forcing one provider for every route (e.g. `mock` for offline runs).
+pub const PROVIDER_ENV: &str = "GRAPHFLOW_LLM_PROVIDER";
+
+/// One completion request, already resolved to a model.
+#[derive(Debug, Clone)]
+pub struct LlmRequest {
+    pub system: String,
+    pub prompt: String,
+    pub model: String,
+    pub max_tokens: u32,
+    pub temperature: Option<f32>,
+    pub schema: Option<Value>, // JSON Schema the reply must follow; only sent to structured-output providers
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Capabilities {
+    pub streaming: bool,
+    pub max_output_tokens: u32, // requests are clamped to this
+    pub structured_output: bool, // can enforce `LlmRequest::schema` on the reply
+}
+
+/// Credits charged for calls through a provider.
+#[derive(Debug, Clone, Copy)]
+pub struct CostModel {
+    pub credits_per_call: u32,  // one generation
+    pub credits_per_retry: u32, // one correction round-trip
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        CostModel { credits_per_call: 5, credits_per_retry: 1 }
+    }
+}
+
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    fn capabilities(&self) -> Capabilities;
+    fn cost_model(&self) -> CostModel {
+        CostModel::default()
+    }
+    async fn complete(&self, req: &LlmRequest) -> Result<String, String>;
+    /// Like `complete`, handing text deltas to `on_delta` as they arrive; returns the full text.
+    async fn stream(&self, req: &LlmRequest, on_delta: &mut (dyn for<'s> FnMut(&'s str) + Send)) -> Result<String, String> {
+        let text = self.complete(req).await?;
+        on_delta(&text);
+        Ok(text)
+    }
+}
+
+pub struct AnthropicProvider {
+    client: Anthropic,
+}
+
+impl AnthropicProvider {
+    pub fn from_env() -> Result<Self, String> {
+        let api_key = env::var("ANTHROPIC_API_KEY").map_err(|_| "Missing ANTHROPIC_API_KEY".to_string())?;
+        let client = Anthropic::new(&api_key).map_err(|e| format!("Anthropic client error: {}", e))?;
+        Ok(AnthropicProvider { client })
+    }
+
+    fn params(req: &LlmRequest) -> anthropic_sdk::MessageCreateParams {
+        let mut builder = MessageCreateBuilder::new(&req.model, req.max_tokens).system(req.system.as_str()).user(req.prompt.as_str());
+        if let Some(t) = req.temperature { builder = builder.temperature(t); }
+        // Structured output: the reply is the input of a tool Claude is forced to call
+        if let Some(schema) = &req.schema {
+            builder = builder.tools(vec![anthropic_tool(schema)]).tool_choice(ToolChoice::Tool { name: ANTHROPIC_TOOL.to_string() });
+        }
+        builder.build()
+    }
+}
+
+const ANTHROPIC_TOOL: &str = "emit_result";
+
+fn anthropic_tool(schema: &Value) -> Tool {
+    let mut rest = schema.as_object().cloned().unwrap_or_default();
+    let properties = rest.remove("properties").and_then(|p| p.as_object().cloned()).unwrap_or_default();
+    let required = rest.remove("required").and_then(|r| serde_json::from_value(r).ok()).unwrap_or_default();
+    rest.remove("type");
+    Tool {
+        name: ANTHROPIC_TOOL.to_string(),
+        description: "Return the result. The input must follow the schema exactly.".to_string(),
+        input_schema: ToolInputSchema { schema_type: "object".to_string(), properties, required, additional: rest },
+    }
+}
+
+// Reply text: the forced tool call's input as JSON, else the text blocks
+fn anthropic_text(message: &anthropic_sdk::Message) -> String {
+    let tool_input = message.content.iter().find_map(|block| match block {
+        anthropic_sdk::ContentBlock::ToolUse { name, input, .. } if name == ANTHROPIC_TOOL => Some(input.to_string()),
+        _ => None,
+    });
+    tool_input.unwrap_or_else(|| {
+        message
+            .content
+            .iter()
+            .filter_map(|block| if let anthropic_sdk::ContentBlock::Text { text } = block { Some(text.as_str()) } else { None })
+            .collect::<Vec<_>>()
+            .join(" ")
+    })
+}
+
+#[async_trait]
+impl LlmProvider for AnthropicProvider {
+    fn capabilities(&self) -> Capabilities {
+        Capabilities { streaming: true, max_output_tokens: 8192, structured_output: true }
+    }
+
+    async fn complete(&self, req: &LlmRequest) -> Result<String, String> {
+        let response = self.client.messages().create(Self::params(req)).await.map_err(|e| format!("Anthropic error: {}", e))?;
+        let text = anthropic_text(&response);
+        if text.is_empty() { Err("Anthropic empty response".to_string()) } else { Ok(text) }
+    }
+
+    async fn stream(&self, req: &LlmRequest, on_delta: &mut (dyn for<'s> FnMut(&'s str) + Send)) -> Result<String, String> {
+        // Forced tool calls stream JSON input fragments, not text; answer in one piece instead
+        if req.schema.is_some() {
+            let text = self.complete(req).await?;
+            on_delta(&text);
+            return Ok(text);
+        }
+        let stream = self.client.messages().create_stream(Self::params(req)).await.map_err(|e| format!("Anthropic error: {}", e))?;
+        // The SDK reports deltas through a 'static callback; forward them over a channel
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+        let finished = stream.on_text(move |delta, _| { let _ = tx.send(delta.to_string()); }).final_message();
+        tokio::pin!(finished);
+        let message = loop {
+            tokio::select! {
+                Some(delta) = rx.recv() => on_delta(&delta),
+                result = &mut finished => break result.map_err(|e| format!("Anthropic error: {}", e))?,
+            }
+        };
+        while let Ok(delta) = rx.try_recv() { on_delta(&delta); }
+        let text = anthropic_text(&message);
+        if text.is_empty() { Err("Anthropic empty response".to_string()) } else { Ok(text) }
+    }
+}
+
+pub struct OpenAiProvider {
+    client: OpenAIClient<OpenAIConfig>,
+}
+
+impl OpenAiProvider {
+    pub fn from_env() -> Result<Self, String> {
+        let api_key = env::var("OPENAI_API_KEY").map_err(|_| "Missing OPENAI_API_KEY".to_string())?;
+        Ok(OpenAiProvider { client: OpenAIClient::with_config(OpenAIConfig::new().with_api_key(api_key)) })
+    }
+
+    fn request(req: &LlmRequest) -> Result<CreateChatCompletionRequest, String> {
+        let messages = vec![
+            ChatCompletionRequestMessage::System(ChatCompletionRequestSystemMessage {
+                content: ChatCompletionRequestSystemMessageContent::Text(req.system.clone()),
+                name: None,
+            }),
+            ChatCompletionRequestMessage::User(ChatCompletionRequestUserMessage {
+                content: ChatCompletionRequestUserMessageContent::Text(req.prompt.clone()),
+                name: None,
+            }),
+        ];
+        let mut args = CreateChatCompletionRequestArgs::default();
+        args.model(req.model.clone()).messages(messages).max_completion_tokens(req.max_tokens);
+        if let Some(t) = req.temperature { args.temperature(t); }
+        if let Some(schema) = &req.schema { args.response_format(json_schema_format(schema)); }
+        args.build().map_err(|e| e.to_string())
+    }
+}
+
+// Non-strict: OpenAI's strict mode needs every property required and no additional properties,
+// while GraphData has optional sections
+fn json_schema_format(schema: &Value) -> ResponseFormat {
+    ResponseFormat::JsonSchema {
+        json_schema: ResponseFormatJsonSchema {
+            description: None,
+            name: "result".to_string(),
+            schema: Some(schema.clone()),
+            strict: Some(false),
+        },
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiProvider {
+    fn capabilities(&self) -> Capabilities {
+        Capabilities { streaming: true, max_output_tokens: 16384, structured_output: true }
+    }
+
+    async fn complete(&self, req: &LlmRequest) -> Result<String, String> {
+        let resp = self.client.chat().create(Self::request(req)?).await.map_err(|e| format!("OpenAI error: {}", e))?;
+        let text = resp.choices.first().and_then(|c| c.message.content.clone()).unwrap_or_default();
+        if text.is_empty() { Err("OpenAI empty response".to_string()) } else { Ok(text) }
+    }
+
+    async fn stream(&self, req: &LlmRequest, on_delta: &mut (dyn for<'s> FnMut(&'s str) + Send)) -> Result<String, String> {
+        let mut chunks = self.client.chat().create_stream(Self::request(req)?).await.map_err(|e| format!("OpenAI error: {}", e))?;
+        let mut text = String::new();
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk.map_err(|e| format!("OpenAI error: {}", e))?;
+            if let Some(delta) = chunk.choices.first().and_then(|c| c.delta.content.as_deref()) {
+                on_delta(delta);
+                text.push_str(delta);
+            }
+        }
+        if text.is_empty() { Err("OpenAI empty response".to_string()) } else { Ok(text) }
+    }
+}
+
+/// Any OpenAI-compatible `/v1/chat/completions` endpoint (Ollama, llama.cpp, vLLM, ...), for
+/// models that must stay on self-hosted infrastructure. JSON mode (`response_format`) is requested
+/// until the endpoint rejects it; the request is then repeated without it, and JSON mode stays off
+/// for this process.
+pub struct OpenAiCompatibleProvider {
+    client: OpenAIClient<OpenAIConfig>,
+    base_url: String,
+    json_mode: AtomicBool,
+}
+
+impl OpenAiCompatibleProvider {
+    /// `base_url` includes the API version, e.g. `http://localhost:11434/v1`.
+    pub fn new(base_url: &str, api_key: &str, json_mode: bool) -> Self {
+        let base_url = base_url.trim_end_matches('/').to_string();
+        let config = OpenAIConfig::new().with_api_base(base_url.clone()).with_api_key(api_key);
+        OpenAiCompatibleProvider { client: OpenAIClient::with_config(config), base_url, json_mode: AtomicBool::new(json_mode) }
+    }
+
+    /// Client for `base_url`, or `GRAPHFLOW_LLM_BASE_URL` when not given.
+    pub fn from_env(base_url: Option<&str>) -> Result<Self, String> {
+        let base_url = local_base_url(base_url)?;
+        let api_key = env::var("GRAPHFLOW_LLM_API_KEY").unwrap_or_default();
+        let json_mode = !matches!(env::var("GRAPHFLOW_LLM_JSON_MODE").unwrap_or_default().to_lowercase().as_str(), "0" | "false" | "off");
+        Ok(Self::new(&base_url, &api_key, json_mode))
+    }
+
+    fn request(req: &LlmRequest, json: bool) -> Result<CreateChatCompletionRequest, String> {
+        let mut request = OpenAiProvider::request(req)?;
+        // Local servers understand the classic `max_tokens`, not always `max_completion_tokens`
+        #[allow(deprecated)]
+        {
+            request.max_tokens = request.max_completion_tokens.take();
+        }
+        // JSON mode means the request's schema if it has one, else plain `json_object`
+        let schema = request.response_format.take();
+        if json {
+            request.response_format = Some(schema.unwrap_or(ResponseFormat::JsonObject));
+        }
+        Ok(request)
+    }
+
+    fn error(&self, e: OpenAIError) -> String {
+        format!("LLM endpoint {} error: {}", self.base_url, e)
+    }
+
+    // Only an invalid-request (400/422) error about `response_format` / JSON means the endpoint
+    // does not support JSON mode; rate limits, auth and server errors leave it on
+    fn may_reject_json(&self, json: bool, e: &OpenAIError) -> bool {
+        json && rejects_json_mode(e)
+    }
+
+    fn disable_json_mode(&self) {
+        if self.json_mode.swap(false, Ordering::Relaxed) {
+            eprintln!("LLM endpoint {} rejected JSON mode; continuing without response_format", self.base_url);
+        }
+    }
+}
+
+// Whether `e` is an endpoint refusing `response_format`. Servers whose error body async-openai
+// cannot deserialize (llama.cpp and vLLM send a numeric `code`) are inspected from the raw body.
+fn rejects_json_mode(e: &OpenAIError) -> bool {
+    let (kind, code, text) = match e {
+        OpenAIError::ApiError(api) => (api.r#type.clone(), api.code.clone(), format!("{} {}", api.message, api.param.as_deref().unwrap_or_default())),
+        OpenAIError::JSONDeserialize(_, body) => {
+            let Ok(value) = serde_json::from_str::<Value>(body) else { return false };
+            let error = value.get("error").filter(|e| e.is_object()).unwrap_or(&value);
+            let field = |key: &str| match error.get(key) {
+                Some(Value::String(s)) => Some(s.clone()),
+                Some(Value::Number(n)) => Some(n.to_string()),
+                _ => None,
+            };
+            let Some(message) = field("message") else { return false };
+            (field("type"), field("code"), format!("{} {}", message, field("param").unwrap_or_default()))
+        }
+        _ => return false,
+    };
+    let kind = kind.unwrap_or_default().to_lowercase();
+    let invalid = matches!(code.as_deref(), Some("400" | "422")) || ["invalid_request", "badrequest", "unprocessable"].iter().any(|k| kind.contains(k));
+    let text = text.to_lowercase();
+    invalid && (text.contains("response_format") || text.contains("json"))
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiCompatibleProvider {
+    fn capabilities(&self) -> Capabilities {
+        // Output length is bounded by the served model, not the API
+        Capabilities { streaming: true, max_output_tokens: u32::MAX, structured_output: self.json_mode.load(Ordering::Relaxed) }
+    }
+
+    async fn complete(&self, req: &LlmRequest) -> Result<String, String> {
+        let json = self.json_mode.load(Ordering::Relaxed);
+        let resp = match self.client.chat().create(Self::request(req, json)?).await {
+            Err(e) if self.may_reject_json(json, &e) => {
+                let resp = self.client.chat().create(Self::request(req, false)?).await.map_err(|e| self.error(e))?;
+                self.disable_json_mode();
+                resp
+            }
+            other => other.map_err(|e| self.error(e))?,
+        };
+        let text = resp.choices.first().and_then(|c| c.message.content.clone()).unwrap_or_default();
+        if text.is_empty() { Err(format!("LLM endpoint {} returned an empty response", self.base_url)) } else { Ok(text) }
+    }
+
+    async fn stream(&self, req: &LlmRequest, on_delta: &mut (dyn for<'s> FnMut(&'s str) + Send)) -> Result<String, String> {
+        let mut json = self.json_mode.load(Ordering::Relaxed);
+        let mut chunks = self.client.chat().create_stream(Self::request(req, json)?).await.map_err(|e| self.error(e))?;
+        let mut text = String::new();
+        while let Some(chunk) = chunks.next().await {
+            let chunk = match chunk {
+                // Rejections arrive as the first stream item; restart once without JSON mode
+                Err(e) if text.is_empty() && self.may_reject_json(json, &e) => {
+                    json = false;
+                    chunks = self.client.chat().create_stream(Self::request(req, false)?).await.map_err(|e| self.error(e))?;
+                    self.disable_json_mode();
+                    continue;
+                }
+                chunk => chunk.map_err(|e| self.error(e))?,
+            };
+            if let Some(delta) = chunk.choices.first().and_then(|c| c.delta.content.as_deref()) {
+                on_delta(delta);
+                text.push_str(delta);
+            }
+        }
+        if text.is_empty() { Err(format!("LLM endpoint {} returned an empty response", self.base_url)) } else { Ok(text) }
+    }
+}
+
+/// One row of the routing table. Empty `tier` / `kind` match anything; `name` lets a request
+/// pick the route explicitly.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Route {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub tier: String, // free | pro
+    #[serde(default)]
+    pub kind: String, // flow | system | sequence | mindmap | auto
+    pub provider: String,
+    pub model: String,
+    #[serde(default = "default_max_tokens")]
+    pub max_tokens: u32,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    #[serde(default)]
+    pub structured_output: Option<bool>, // `false` keeps the plain text prompt for this route
+    #[serde(default)]
+    pub base_url: Option<String>, // `local` endpoint for this route (default: `GRAPHFLOW_LLM_BASE_URL`)
+}
+
+fn default_max_tokens() -> u32 {
+    1024
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct LlmConfig {
+    pub routes: Vec<Route>,
+}
+
+/// What a request is routed by.
+#[derive(Debug, Clone, Copy)]
+pub struct RouteKey<'a> {
+    pub tier: &'a UserTier,
+    pub kind: &'a str,
+    pub route: Option<&'a str>, // explicit route name, e.g. from the API request
+}
+
+/// Built-in table: Free -> Anthropic, Pro -> OpenAI; both tiers go to the `local` endpoint (model
+/// from `GRAPHFLOW_LLM_MODEL`) when `GRAPHFLOW_LLM_BASE_URL` is set.
+pub fn default_config() -> LlmConfig {
+    let mut config = LlmConfig {
+        routes: vec![
+            Route {
+                name: "free".into(),
+                tier: "free".into(),
+                provider: "anthropic".into(),
+                model: env::var("ANTHROPIC_MODEL").unwrap_or_else(|_| "claude-3-5-haiku-latest".to_string()),
+                max_tokens: 1024,
+                ..Default::default()
+            },
+            Route {
+                name: "pro".into(),
+                tier: "pro".into(),
+                provider: "openai".into(),
+                model: env::var("OPENAI_MODEL_PRO").unwrap_or_else(|_| "gpt-4o".to_string()),
+                max_tokens: 4096,
+                temperature: Some(0.2),
+                ..Default::default()
+            },
+        ],
+    };
+    if env::var(BASE_URL_ENV).is_ok_and(|u| !u.is_empty()) {
+        let model = env::var("GRAPHFLOW_LLM_MODEL").unwrap_or_else(|_| "llama3.1".to_string());
+        for route in &mut config.routes {
+            route.provider = "local".into();
+            route.model = model.clone();
+        }
+    }
+    config
+}
+
+/// The routing table from `GRAPHFLOW_LLM_CONFIG`, or the built-in one when unset.
+pub fn load_config() -> Result<LlmConfig, String> {
+    match env::var(CONFIG_ENV) {
+        Ok(path) if !path.is_empty() => {
+            let text = fs::read_to_string(&path).map_err(|e| format!("Cannot read {} ({}): {}", CONFIG_ENV, path, e))?;
+            serde_json::from_str(&text).map_err(|e| format!("Invalid LLM config {}: {}", path, e))
+        }
+        _ => Ok(default_config()),
+    }
+}
+
+impl LlmConfig {
+    /// The named route if one is requested (and exists), else the first route matching tier and kind.
+    pub fn route_for(&self, key: &RouteKey) -> Option<&Route> {
+        let tier = match key.tier {
+            UserTier::Free => "free",
+            UserTier::Pro => "pro",
+        };
+        let matches = |want: &str, have: &str| want.is_empty() || want.eq_ignore_ascii_case(have);
+        key.route
+            .and_then(|name| self.routes.iter().find(|r| r.name == name))
+            .or_else(|| self.routes.iter().find(|r| matches(&r.tier, tier) && matches(&r.kind, key.kind)))
+    }
+}
+
+// Keyed by (provider, base URL); the base URL is empty for hosted providers
+type ProviderCache = Mutex<HashMap<(String, String), Arc<dyn LlmProvider>>>;
+
+fn providers() -> &'static ProviderCache {
+    static PROVIDERS: OnceLock<ProviderCache> = OnceLock::new();
+    PROVIDERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+// The route's endpoint, else `GRAPHFLOW_LLM_BASE_URL`, read on every request
+fn local_base_url(base_url: Option<&str>) -> Result<String, String> {
+    let url = match base_url.filter(|u| !u.is_empty()) {
+        Some(url) => url.to_string(),
+        None => env::var(BASE_URL_ENV).ok().filter(|u| !u.is_empty()).ok_or_else(|| format!("Missing {} (or a route `base_url`)", BASE_URL_ENV))?,
+    };
+    Ok(url.trim_end_matches('/').to_string())
+}
+
+/// Why a route could not be turned into a provider client.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RouteError {
+    /// The provider's API key or endpoint is not set
+    Unconfigured(String),
+    /// A broken config file, an unknown provider, or no route for the request
+    Invalid(String),
+}
+
+impl fmt::Display for RouteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RouteError::Unconfigured(e) | RouteError::Invalid(e) => f.write_str(e),
+        }
+    }
+}
+
+/// The shared provider client for `name` (and, for `local`, the endpoint at `base_url`), created
+/// on first use.
+pub fn provider(name: &str, base_url: Option<&str>) -> Result<Arc<dyn LlmProvider>, RouteError> {
+    let endpoint = if name == "local" { local_base_url(base_url).map_err(RouteError::Unconfigured)? } else { String::new() };
+    let key = (name.to_string(), endpoint);
+    let mut cache = providers().lock().map_err(|_| RouteError::Invalid("LLM provider registry poisoned".to_string()))?;
+    if let Some(p) = cache.get(&key) {
+        return Ok(p.clone());
+    }
+    let created: Arc<dyn LlmProvider> = match name {
+        "anthropic" => Arc::new(AnthropicProvider::from_env().map_err(RouteError::Unconfigured)?),
+        "openai" => Arc::new(OpenAiProvider::from_env().map_err(RouteError::Unconfigured)?),
+        "local" => Arc::new(OpenAiCompatibleProvider::from_env(Some(&key.1)).map_err(RouteError::Unconfigured)?),
+        "mock" => Arc::new(MockProvider),
+        other => return Err(RouteError::Invalid(format!("Unknown LLM provider `{}`", other))),
+    };
+    cache.insert(key, created.clone());
+    Ok(created)
+}
+
+/// A routed request: the provider to call and the request to send it.
+pub struct Routed {
+    pub provider: Arc<dyn LlmProvider>,
+    pub route: Route,
+}
+
+/// The route for `key` from the current config, with the `GRAPHFLOW_LLM_PROVIDER` override applied.
+pub fn resolve_route(key: &RouteKey) -> Result<Route, String> {
+    let config = load_config()?;
+    let mut route = config.route_for(key).cloned().ok_or_else(|| "No LLM route matches this request".to_string())?;
+    if let Ok(forced) = env::var(PROVIDER_ENV) {
+        if !forced.is_empty() { route.provider = forced; }
+    }
+    Ok(route)
+}
+
+impl Routed {
+    pub fn resolve(key: &RouteKey) -> Result<Routed, RouteError> {
+        let route = resolve_route(key).map_err(RouteError::Invalid)?;
+        Ok(Routed { provider: provider(&route.provider, route.base_url.as_deref())?, route })
+    }
+
+    /// Like `resolve`, with calls going through `cassette`. Strict replay never creates the real
+    /// provider, so it runs without API keys.
+    pub fn resolve_recorded(key: &RouteKey, cassette: &CassetteConfig) -> Result<Routed, RouteError> {
+        let route = resolve_route(key).map_err(RouteError::Invalid)?;
+        let inner = match cassette.mode {
+            CassetteMode::Strict => Err("strict cassette replay does not call providers".to_string()),
+            _ => provider(&route.provider, route.base_url.as_deref()).map_err(|e| e.to_string()),
+        };
+        let provider = Arc::new(CassetteProvider { config: cassette.clone(), provider: route.provider.clone(), inner });
+        Ok(Routed { provider, route })
+    }
+
+    /// Whether requests on this route carry their reply schema.
+    pub fn structured(&self) -> bool {
+        self.provider.capabilities().structured_output && self.route.structured_output != Some(false)
+    }
+
+    pub fn request(&self, prompt: &str, schema: Option<&Value>) -> LlmRequest {
+        LlmRequest {
+            system: SYSTEM_PROMPT.to_string(),
+            prompt: prompt.to_string(),
+            model: self.route.model.clone(),
+            max_tokens: self.route.max_tokens.min(self.provider.capabilities().max_output_tokens),
+            temperature: self.route.temperature,
+            schema: schema.filter(|_| self.structured()).cloned(),
+        }
+    }
+
+    /// Send `prompt` (asking for a reply matching `schema` where supported), streaming deltas to `on_delta` when given and the provider supports it.
+    pub async fn complete(&self, prompt: &str, schema: Option<&Value>, on_delta: Option<&mut (dyn for<'s> FnMut(&'s str) + Send)>) -> Result<String, String> {
+        let req = self.request(prompt, schema);
+        match on_delta {
+            Some(sink) if self.provider.capabilities().streaming => self.provider.stream(&req, sink).await,
+            _ => self.provider.complete(&req).await,
+        }
+    }
+}
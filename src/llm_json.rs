@@ -0,0 +1,372 @@
+use crate::state::GraphData;
+use serde_json::{Map, Value};
+
+// Tolerant GraphData extraction from LLM replies. Models wrap JSON in markdown fences, lead
+// with a sentence, leave trailing commas, use single quotes or bare keys, stop mid-object, or
+// send numbers as strings; each of these used to discard the whole (already paid for) reply.
+// Extraction runs in stages: strict parse, outermost object of each fenced block / the raw
+// text, lexical repairs, then loose mapping of values onto GraphData defaults.
+
+/// Parse GraphData out of an LLM reply, repairing what commonly goes wrong. A reply without
+/// any nodes (an error object, `{}`, an empty graph) is an error, not an empty diagram.
+pub fn parse_graph_data(reply: &str) -> Result<GraphData, String> {
+    if let Ok(g) = serde_json::from_str::<GraphData>(reply) {
+        return with_nodes(g);
+    }
+    let mut last_err = "no JSON object found".to_string();
+    for candidate in candidates(reply) {
+        let Some(object) = outermost_object(&candidate) else { continue };
+        let value = match serde_json::from_str::<Value>(object).or_else(|_| serde_json::from_str::<Value>(&repair_json(object))) {
+            Ok(v) => v,
+            Err(e) => {
+                last_err = e.to_string();
+                continue;
+            }
+        };
+        match serde_json::from_value::<GraphData>(normalize(value)).map_err(|e| e.to_string()).and_then(with_nodes) {
+            Ok(g) => return Ok(g),
+            Err(e) => last_err = e,
+        }
+    }
+    Err(last_err)
+}
+
+fn with_nodes(g: GraphData) -> Result<GraphData, String> {
+    if g.nodes.is_empty() { Err("the JSON object has no `nodes`".to_string()) } else { Ok(g) }
+}
+
+// Fenced code blocks (```json ... ```), then the whole reply
+fn candidates(reply: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut rest = reply;
+    while let Some(start) = rest.find("```") {
+        let after = &rest[start + 3..];
+        // skip the info string ("json", "JSON5", ...) up to the end of the line
+        let body_start = after.find('\n').map(|i| i + 1).unwrap_or(after.len());
+        let body = &after[body_start..];
+        match body.find("```") {
+            Some(end) => {
+                out.push(body[..end].to_string());
+                rest = &body[end + 3..];
+            }
+            None => {
+                // unterminated fence: the reply was cut off inside it
+                out.push(body.to_string());
+                break;
+            }
+        }
+    }
+    out.push(reply.to_string());
+    out
+}
+
+/// From the first `{`, the text up to its matching `}` (quote-aware); the rest of the text
+/// when the object is never closed, so truncated replies can still be repaired.
+pub fn outermost_object(text: &str) -> Option<&str> {
+    let start = text.find('{')?;
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for (i, c) in text[start..].char_indices() {
+        if let Some(q) = quote {
+            if escaped { escaped = false; } else if c == '\\' { escaped = true; } else if c == q { quote = None; }
+            continue;
+        }
+        match c {
+            '"' | '\'' => quote = Some(c),
+            '{' | '[' => depth += 1,
+            '}' | ']' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 { return Some(&text[start..start + i + 1]); }
+            }
+            _ => {}
+        }
+    }
+    Some(&text[start..])
+}
+
+// Strip trailing commas and whitespace from `out`
+fn trim_trailing_comma(out: &mut String) {
+    while out.ends_with(|c: char| c.is_whitespace() || c == ',') { out.pop(); }
+}
+
+/// Lexical repairs of almost-JSON: comments, single-quoted strings, bare keys and words,
+/// Python literals, smart quotes, trailing commas, and unclosed strings/brackets.
+pub fn repair_json(text: &str) -> String {
+    let chars: Vec<char> = text.chars().map(|c| match c { '\u{201C}' | '\u{201D}' => '"', _ => c }).collect();
+    let mut out = String::with_capacity(text.len());
+    let mut stack: Vec<char> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '"' | '\'' => {
+                // copy the string, re-quoted with double quotes
+                out.push('"');
+                i += 1;
+                let mut closed = false;
+                while i < chars.len() {
+                    let d = chars[i];
+                    if d == '\\' && i + 1 < chars.len() {
+                        if chars[i + 1] == '\'' { out.push('\''); } else { out.push(d); out.push(chars[i + 1]); }
+                        i += 2;
+                        continue;
+                    }
+                    if d == c { closed = true; break; }
+                    match d {
+                        '"' => out.push_str("\\\""),
+                        '\n' => out.push_str("\\n"),
+                        _ => out.push(d),
+                    }
+                    i += 1;
+                }
+                out.push('"');
+                if !closed { break; }
+            }
+            '-' | '0'..='9' => {
+                // numbers are copied whole so exponents are not taken for bare words
+                while i < chars.len() && matches!(chars[i], '0'..='9' | '.' | 'e' | 'E' | '+' | '-') {
+                    out.push(chars[i]);
+                    i += 1;
+                }
+                continue;
+            }
+            '/' if chars.get(i + 1) == Some(&'/') => {
+                while i < chars.len() && chars[i] != '\n' { i += 1; }
+                continue;
+            }
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) { i += 1; }
+                i += 2;
+                continue;
+            }
+            '{' | '[' => {
+                stack.push(if c == '{' { '}' } else { ']' });
+                out.push(c);
+            }
+            '}' | ']' => {
+                trim_trailing_comma(&mut out);
+                stack.pop();
+                out.push(c);
+            }
+            _ if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '-') { i += 1; }
+                let word: String = chars[start..i].iter().collect();
+                let is_key = chars[i..].iter().find(|c| !c.is_whitespace()) == Some(&':');
+                match word.as_str() {
+                    "true" | "false" | "null" if !is_key => out.push_str(&word),
+                    "True" if !is_key => out.push_str("true"),
+                    "False" if !is_key => out.push_str("false"),
+                    "None" | "undefined" if !is_key => out.push_str("null"),
+                    _ => out.push_str(&Value::String(word).to_string()),
+                }
+                continue;
+            }
+            _ => out.push(c),
+        }
+        i += 1;
+    }
+    // close whatever a truncated reply left open
+    trim_trailing_comma(&mut out);
+    if out.ends_with(':') || (stack.last() == Some(&'}') && ends_with_key(&out)) {
+        if !out.ends_with(':') { out.push(':'); }
+        out.push_str("null");
+    }
+    while let Some(close) = stack.pop() {
+        trim_trailing_comma(&mut out);
+        out.push(close);
+    }
+    out
+}
+
+// Whether `out` ends with a string that opens an object entry (a key still waiting for its value)
+fn ends_with_key(out: &str) -> bool {
+    let Some(body) = out.strip_suffix('"') else { return false };
+    let bytes = body.as_bytes();
+    let mut i = bytes.len();
+    while i > 0 {
+        i -= 1;
+        if bytes[i] == b'"' && (i == 0 || bytes[i - 1] != b'\\') {
+            return matches!(body[..i].trim_end().chars().last(), Some('{') | Some(','));
+        }
+    }
+    false
+}
+
+fn text_of(v: Option<&Value>) -> Option<String> {
+    match v? {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn number_of(v: Option<&Value>) -> Option<f64> {
+    match v? {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().trim_end_matches("px").parse().ok(),
+        _ => None,
+    }
+}
+
+fn set_text(o: &mut Map<String, Value>, key: &str, default: &str) {
+    let v = text_of(o.get(key)).unwrap_or_else(|| default.to_string());
+    o.insert(key.to_string(), Value::String(v));
+}
+
+fn set_number(o: &mut Map<String, Value>, key: &str, default: Option<f64>) {
+    match number_of(o.get(key)).or(default) {
+        Some(n) => { o.insert(key.to_string(), serde_json::json!(n)); }
+        None => { o.remove(key); }
+    }
+}
+
+fn set_index(o: &mut Map<String, Value>, key: &str) {
+    let n = number_of(o.get(key)).unwrap_or(0.0).max(0.0) as u64;
+    o.insert(key.to_string(), Value::from(n));
+}
+
+// Array of objects under `key` (a single object or null count as one / none)
+fn objects<'a>(o: &'a mut Map<String, Value>, key: &str) -> impl Iterator<Item = &'a mut Map<String, Value>> {
+    let v = o.entry(key.to_string()).or_insert(Value::Null);
+    *v = match v.take() {
+        Value::Array(a) => Value::Array(a.into_iter().filter(|x| x.is_object()).collect()),
+        obj @ Value::Object(_) => Value::Array(vec![obj]),
+        _ => Value::Array(Vec::new()),
+    };
+    v.as_array_mut().into_iter().flatten().filter_map(|x| x.as_object_mut())
+}
+
+// Object under `key`, created when missing or malformed
+fn object<'a>(o: &'a mut Map<String, Value>, key: &str) -> &'a mut Map<String, Value> {
+    let v = o.entry(key.to_string()).or_insert(Value::Null);
+    if !v.is_object() { *v = Value::Object(Map::new()); }
+    v.as_object_mut().unwrap()
+}
+
+// Optional section: dropped unless it is an object or array of the expected kind
+fn optional(o: &mut Map<String, Value>, key: &str, array: bool) -> bool {
+    let ok = match o.get(key) {
+        Some(Value::Array(_)) => array,
+        Some(Value::Object(_)) => true,
+        _ => false,
+    };
+    if !ok { o.remove(key); }
+    ok
+}
+
+/// Map loosely-typed JSON onto GraphData's shape: unwrap a `graph_data` envelope, turn
+/// numeric strings into numbers (and numbers into ids), and fill missing required fields
+/// (`style`, labels, edge ids, ...) with their defaults.
+pub fn normalize(value: Value) -> Value {
+    let mut root = match value {
+        Value::Object(mut o) => match o.remove("graph_data").or_else(|| o.remove("graph")) {
+            Some(Value::Object(inner)) => inner,
+            Some(_) | None => o,
+        },
+        _ => Map::new(),
+    };
+
+    for n in objects(&mut root, "nodes") {
+        set_text(n, "id", "");
+        let id = n["id"].as_str().unwrap_or_default().to_string();
+        set_text(n, "label", &id);
+        set_number(n, "x", Some(0.0));
+        set_number(n, "y", Some(0.0));
+        let style = object(n, "style");
+        set_text(style, "shape", "");
+        set_text(style, "color", "");
+    }
+
+    for e in objects(&mut root, "edges") {
+        for (key, alias) in [("source", "from"), ("target", "to")] {
+            if !e.contains_key(key) {
+                if let Some(v) = e.remove(alias) { e.insert(key.to_string(), v); }
+            }
+            set_text(e, key, "");
+        }
+        let id = format!("{}_{}", e["source"].as_str().unwrap_or_default(), e["target"].as_str().unwrap_or_default());
+        set_text(e, "id", &id);
+        set_text(e, "label", "");
+        let style = object(e, "style");
+        set_text(style, "line", "");
+        set_text(style, "arrow", "");
+    }
+
+    if optional(&mut root, "layout_hints", false) {
+        let h = object(&mut root, "layout_hints");
+        set_text(h, "direction", "");
+        set_text(h, "algorithm", "");
+        for key in ["rank_sep", "node_sep", "max_per_rank"] { set_number(h, key, None); }
+        if let Some(m) = h.get("max_per_rank").and_then(|v| v.as_f64()) { h.insert("max_per_rank".into(), Value::from(m.max(0.0) as u64)); }
+    }
+
+    if optional(&mut root, "global_style", false) {
+        let s = object(&mut root, "global_style");
+        set_text(s, "font", "");
+        set_text(s, "background", "");
+    }
+
+    if optional(&mut root, "decorations", true) {
+        for d in objects(&mut root, "decorations") {
+            set_text(d, "type", "icon");
+            set_number(d, "at_x", None);
+            set_number(d, "at_y", None);
+            for (key, a, b) in [("size", "w", "h"), ("offset", "dx", "dy")] {
+                if optional(d, key, false) {
+                    let o = object(d, key);
+                    set_number(o, a, Some(0.0));
+                    set_number(o, b, Some(0.0));
+                }
+            }
+        }
+    }
+
+    if optional(&mut root, "containers", true) {
+        for c in objects(&mut root, "containers") {
+            set_text(c, "id", "");
+            let id = c["id"].as_str().unwrap_or_default().to_string();
+            set_text(c, "label", &id);
+            let children: Vec<Value> = match c.get("children") {
+                Some(Value::Array(a)) => a.iter().filter_map(|v| text_of(Some(v))).map(Value::String).collect(),
+                _ => Vec::new(),
+            };
+            c.insert("children".into(), Value::Array(children));
+            if optional(c, "style", false) {
+                let s = object(c, "style");
+                set_number(s, "radius", None);
+            }
+        }
+    }
+
+    if optional(&mut root, "sequence", false) {
+        let s = object(&mut root, "sequence");
+        for p in objects(s, "participants") { set_text(p, "id", ""); }
+        for m in objects(s, "messages") {
+            set_text(m, "from", "");
+            set_text(m, "to", "");
+        }
+        if optional(s, "fragments", true) {
+            for f in objects(s, "fragments") {
+                set_text(f, "kind", "opt");
+                set_index(f, "start");
+                set_index(f, "end");
+                if optional(f, "sections", true) {
+                    for sec in objects(f, "sections") { set_index(sec, "start"); }
+                }
+            }
+        }
+        if optional(s, "activations", true) {
+            for a in objects(s, "activations") {
+                set_text(a, "participant", "");
+                set_index(a, "start");
+                set_index(a, "end");
+            }
+        }
+    }
+
+    Value::Object(root)
+}
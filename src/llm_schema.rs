@@ -0,0 +1,92 @@
+use crate::state::{
+    Activation, Container, ContainerStyle, Decoration, DecorationOffset, DecorationSize, EdgeData, EdgeStyle, FragmentSection, GlobalStyle,
+    GraphData, LayoutHints, NodeData, NodeStyle, Participant, SequenceData, SequenceFragment, SequenceMessage,
+};
+use serde_json::{Map, Value};
+use std::sync::OnceLock;
+use utoipa::ToSchema;
+
+// JSON Schema for GraphData, for providers that can enforce a reply format (OpenAI
+// `response_format: json_schema`, Anthropic tool input schemas). It is derived from the same
+// `ToSchema` derives the REST API documents, converted from OpenAPI 3.0 to plain JSON Schema:
+// `$ref`s are inlined so the schema is self-contained, `nullable` becomes a `null` alternative,
+// and OpenAPI-only annotations (`example`, numeric `format`s) are dropped.
+
+const COMPONENT_REF: &str = "#/components/schemas/";
+
+// GraphData and every type it references; a type missing here panics when the schema is built
+fn components() -> Map<String, Value> {
+    let schemas = [
+        GraphData::schema(), NodeData::schema(), NodeStyle::schema(), EdgeData::schema(), EdgeStyle::schema(), LayoutHints::schema(),
+        GlobalStyle::schema(), Decoration::schema(), DecorationSize::schema(), DecorationOffset::schema(), Container::schema(),
+        ContainerStyle::schema(), SequenceData::schema(), Participant::schema(), SequenceMessage::schema(), SequenceFragment::schema(),
+        FragmentSection::schema(), Activation::schema(),
+    ];
+    schemas
+        .into_iter()
+        .map(|(name, schema)| (name.to_string(), serde_json::to_value(schema).expect("ToSchema output serializes")))
+        .collect()
+}
+
+/// The GraphData JSON Schema, built once.
+pub fn graph_data_schema() -> &'static Value {
+    static SCHEMA: OnceLock<Value> = OnceLock::new();
+    SCHEMA.get_or_init(|| {
+        let components = components();
+        to_json_schema(&components["GraphData"], &components, 0)
+    })
+}
+
+fn to_json_schema(schema: &Value, components: &Map<String, Value>, depth: usize) -> Value {
+    let Some(object) = schema.as_object() else { return schema.clone() };
+    if let Some(name) = object.get("$ref").and_then(|r| r.as_str()).and_then(|r| r.strip_prefix(COMPONENT_REF)) {
+        // GraphData has no recursive types; the depth guard only protects against future ones
+        let target = components.get(name).unwrap_or_else(|| panic!("GraphData schema references `{}`, which llm_schema::components() does not list", name));
+        assert!(depth < 16, "GraphData schema nests `{}` too deeply", name);
+        return to_json_schema(target, components, depth + 1);
+    }
+
+    let mut out = Map::new();
+    for (key, value) in object {
+        match key.as_str() {
+            "nullable" | "example" => {}
+            "format" if matches!(value.as_str(), Some("float" | "double" | "int32" | "int64")) => {}
+            "properties" => {
+                let props = value.as_object().into_iter().flatten().map(|(k, v)| (k.clone(), to_json_schema(v, components, depth))).collect();
+                out.insert(key.clone(), Value::Object(props));
+            }
+            "items" | "additionalProperties" if value.is_object() => {
+                out.insert(key.clone(), to_json_schema(value, components, depth));
+            }
+            "allOf" | "anyOf" | "oneOf" => {
+                let parts = value.as_array().into_iter().flatten().map(|v| to_json_schema(v, components, depth)).collect();
+                out.insert(key.clone(), Value::Array(parts));
+            }
+            _ => {
+                out.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    // utoipa wraps a single referenced type in `allOf` to attach `nullable`
+    if out.len() == 1 {
+        if let Some(Value::Array(parts)) = out.get("allOf") {
+            if parts.len() == 1 {
+                out = parts[0].as_object().cloned().unwrap_or_default();
+            }
+        }
+    }
+
+    if object.get("nullable").and_then(|n| n.as_bool()) == Some(true) {
+        return match out.get("type").cloned() {
+            Some(Value::String(t)) if !out.contains_key("properties") => {
+                out.insert("type".into(), Value::Array(vec![Value::String(t), Value::String("null".into())]));
+                if let Some(Value::Array(values)) = out.get_mut("enum") {
+                    values.push(Value::Null);
+                }
+                Value::Object(out)
+            }
+            _ => serde_json::json!({"anyOf": [Value::Object(out), {"type": "null"}]}),
+        };
+    }
+    Value::Object(out)
+}
@@ -2,8 +2,28 @@ mod flow;
 mod nodes;
 mod state;
 mod utils;
+mod edge_style;
 mod excalidraw;
 mod server;
+mod svg;
+mod render;
+mod mermaid;
+mod dot;
+mod drawio;
+mod excalidraw_import;
+mod labels;
+mod layout;
+mod llm;
+mod llm_json;
+mod llm_schema;
+mod mock_llm;
+mod cassette;
+mod routing;
+mod mindmap;
+mod sequence;
+mod shapes;
+mod text;
+mod validate;
 
 use pocketflow_rs::Context;
 use flow::create_graph_flow;
@@ -28,13 +48,36 @@ async fn main() -> anyhow::Result<()> {
     //   --credits <u32> (default: 100)
     //   --input-file <path> (optional)
     //   --export-excalidraw <path.json> (optional)
+    //   --export-mermaid <path.mmd> (optional) Mermaid flowchart text of the final graph
+    //   --export-dot <path.dot> (optional) Graphviz DOT of the final graph
+    //   --export-drawio <path.drawio> (optional) diagrams.net file of the final (laid out) graph
+    //   --png-scale <f32> (default: 1.0) PNG pixel multiplier for rendered artifacts
+    //   --png-background <color> (optional) PNG background override, e.g. "transparent"
+    //   --repair (optional) fix structural issues in the generated graph instead of only reporting them
+    //   --llm-retries <u32> (default: 2) correction requests to the LLM when its reply is unusable
+    //   --llm-route <name> (optional) LLM route from the GRAPHFLOW_LLM_CONFIG routing table
+    //   --stream (optional) echo the LLM reply to stderr as it arrives
+    //   --mock-llm (optional) answer with the offline mock provider (same as GRAPHFLOW_LLM_PROVIDER=mock)
+    //   --cassette <path.json> (optional) record/replay LLM calls through this cassette file
+    //   --cassette-mode <record|replay|strict> (default: replay) how the cassette is used
     let args: Vec<String> = env::args().collect();
     let mut user_id = env::var("GF_USER").unwrap_or_else(|_| "test".to_string());
     let mut tier = UserTier::Free;
     let mut credits_remaining: u32 = 100;
     let mut input_file: Option<String> = None;
     let mut export_excalidraw: Option<String> = None;
+    let mut export_mermaid: Option<String> = None;
+    let mut export_dot: Option<String> = None;
+    let mut export_drawio: Option<String> = None;
+    let mut png_scale: f32 = 1.0;
+    let mut png_background: Option<String> = None;
     let mut allow_images: bool = false;
+    let mut repair_graph: bool = false;
+    let mut llm_retries: Option<u32> = None;
+    let mut llm_route: Option<String> = None;
+    let mut stream: bool = false;
+    let mut cassette: Option<String> = None;
+    let mut cassette_mode: Option<String> = None;
     let mut assets_dir: String = "assets/icons".to_string();
     let mut serve: bool = false;
     let mut port: u16 = 8080;
@@ -54,7 +97,19 @@ async fn main() -> anyhow::Result<()> {
             }
             "--input-file" if i + 1 < args.len() => { input_file = Some(args[i+1].clone()); i += 2; }
             "--export-excalidraw" if i + 1 < args.len() => { export_excalidraw = Some(args[i+1].clone()); i += 2; }
+            "--export-mermaid" if i + 1 < args.len() => { export_mermaid = Some(args[i+1].clone()); i += 2; }
+            "--export-dot" if i + 1 < args.len() => { export_dot = Some(args[i+1].clone()); i += 2; }
+            "--export-drawio" if i + 1 < args.len() => { export_drawio = Some(args[i+1].clone()); i += 2; }
+            "--png-scale" if i + 1 < args.len() => { png_scale = args[i+1].parse().unwrap_or(1.0); i += 2; }
+            "--png-background" if i + 1 < args.len() => { png_background = Some(args[i+1].clone()); i += 2; }
             "--allow-images" => { allow_images = true; i += 1; }
+            "--repair" => { repair_graph = true; i += 1; }
+            "--llm-retries" if i + 1 < args.len() => { llm_retries = args[i+1].parse().ok(); i += 2; }
+            "--llm-route" if i + 1 < args.len() => { llm_route = Some(args[i+1].clone()); i += 2; }
+            "--stream" => { stream = true; i += 1; }
+            "--cassette" if i + 1 < args.len() => { cassette = Some(args[i+1].clone()); i += 2; }
+            "--cassette-mode" if i + 1 < args.len() => { cassette_mode = Some(args[i+1].clone()); i += 2; }
+            "--mock-llm" => { env::set_var(llm::PROVIDER_ENV, "mock"); i += 1; }
             "--assets-dir" if i + 1 < args.len() => { assets_dir = args[i+1].clone(); i += 2; }
             "--serve" => { serve = true; i += 1; }
             "--port" if i + 1 < args.len() => { port = args[i+1].parse().unwrap_or(8080); i += 2; }
@@ -92,7 +147,7 @@ async fn main() -> anyhow::Result<()> {
             content: chat_content,
             timestamp: String::new(),
         },
-        ai_response: AiResponse { status: state::AiStatus::Success, message: None, graph_data: None, credits_cost: 0 },
+        ai_response: AiResponse { status: state::AiStatus::Success, message: None, graph_data: None, credits_cost: 0, diagnostics: Vec::new(), llm_retries: Vec::new() },
         current_graph: None,
         payment_info: None,
     };
@@ -102,18 +157,26 @@ async fn main() -> anyhow::Result<()> {
     context.set("shared_state", json!(initial_state.clone()));
     // Pass through export path so nodes can emit artifacts during the flow
     context.set("export_excalidraw_path", json!(export_excalidraw.clone()));
+    context.set("export_mermaid_path", json!(export_mermaid));
+    context.set("export_dot_path", json!(export_dot));
+    context.set("export_drawio_path", json!(export_drawio));
     context.set("allow_images", json!(allow_images));
+    context.set("repair_graph", json!(repair_graph));
+    if let Some(n) = llm_retries { context.set("llm_max_retries", json!(n)); }
+    if let Some(route) = llm_route { context.set("llm_route", json!(route)); }
+    context.set("llm_stream", json!(stream));
+    if let Some(path) = cassette { context.set("llm_cassette", json!(path)); }
+    if let Some(mode) = cassette_mode { context.set("llm_cassette_mode", json!(mode)); }
     context.set("assets_dir", json!(assets_dir));
+    context.set("png_scale", json!(png_scale));
+    context.set("png_background", json!(png_background));
 
     // Create and run the graph flow
     let graph_flow = create_graph_flow();
-    let final_context = graph_flow.run(context).await?;
+    let final_result = graph_flow.run(context).await?;
 
-    // Retrieve the final SharedState from the context
-    let final_shared_state: SharedState = final_context.get("shared_state")
-        .cloned()
-        .and_then(|v| serde_json::from_value(v).ok())
-        .unwrap_or_default();
+    // The flow's result is the final SharedState
+    let final_shared_state: SharedState = serde_json::from_value(final_result).unwrap_or_default();
 
     // Print the final state
     println!("\n=== Final SharedState ===");
@@ -0,0 +1,544 @@
+use crate::state::{Container, EdgeData, EdgeStyle, GlobalStyle, GraphData, LayoutHints, NodeData, NodeStyle};
+use std::collections::{HashMap, HashSet};
+
+// Deterministic Mermaid flowchart import (no LLM involved).
+// Supported: flowchart/graph headers with direction, node shapes, chained and `&` edges,
+// edge labels (`-->|x|`, `-- x -->`), dotted/thick links, subgraph ... end, classDef/class/:::/style fills.
+
+/// Pull the Mermaid source out of a ```mermaid fence, or take the text as-is.
+fn mermaid_source(text: &str) -> &str {
+    if let Some(start) = text.find("```mermaid") {
+        let body = &text[start + "```mermaid".len()..];
+        let end = body.find("```").unwrap_or(body.len());
+        return &body[..end];
+    }
+    text
+}
+
+// Split "flowchart LR" style headers; returns the normalized direction
+fn header_direction(line: &str) -> Option<String> {
+    let mut parts = line.split_whitespace();
+    let kw = parts.next()?;
+    if !matches!(kw, "flowchart" | "graph" | "flowchart-elk") { return None; }
+    let dir = parts.next().map(|d| d.trim_end_matches(';'));
+    if parts.next().is_some() { return None; }
+    match dir {
+        None | Some("") | Some("TB") | Some("TD") => Some("TB".to_string()),
+        Some("BT") => Some("BT".to_string()),
+        Some("LR") => Some("LR".to_string()),
+        Some("RL") => Some("RL".to_string()),
+        _ => None,
+    }
+}
+
+fn meaningful_lines(src: &str) -> impl Iterator<Item = &str> {
+    let mut in_frontmatter = false;
+    src.lines().map(|l| l.trim()).filter(move |l| {
+        if *l == "---" { in_frontmatter = !in_frontmatter; return false; }
+        !in_frontmatter && !l.is_empty() && !l.starts_with("%%")
+    })
+}
+
+/// True when the text is (or contains a fenced) Mermaid flowchart.
+pub fn is_mermaid_flowchart(text: &str) -> bool {
+    meaningful_lines(mermaid_source(text)).next().and_then(header).is_some()
+}
+
+// Direction from the first line, plus any statements sharing that line (`graph TD; A-->B`)
+fn header(line: &str) -> Option<(String, Vec<String>)> {
+    let mut stmts = split_statements(line).into_iter();
+    let direction = header_direction(&stmts.next()?)?;
+    Some((direction, stmts.collect()))
+}
+
+fn is_id_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn clean_label(s: &str) -> String {
+    let s = s.trim();
+    let s = s.strip_prefix('"').and_then(|x| x.strip_suffix('"')).unwrap_or(s);
+    s.replace("#quot;", "\"").replace("<br>", "\n").replace("<br/>", "\n").replace("<br />", "\n")
+}
+
+fn slug(s: &str) -> String {
+    let mut out = String::new();
+    for ch in s.chars() {
+        if ch.is_alphanumeric() { out.extend(ch.to_lowercase()); } else if !out.ends_with('_') { out.push('_'); }
+    }
+    out.trim_matches('_').to_string()
+}
+
+struct NodeRef {
+    id: String,
+    label: Option<String>,
+    shape: Option<&'static str>,
+    class: Option<String>,
+}
+
+struct Link {
+    label: String,
+    line: &'static str,
+    arrow: &'static str,
+    visible: bool,
+}
+
+struct Cursor {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Cursor {
+    fn new(s: &str) -> Self { Cursor { chars: s.chars().collect(), pos: 0 } }
+    fn peek(&self) -> Option<char> { self.chars.get(self.pos).copied() }
+    fn rest(&self) -> String { self.chars[self.pos..].iter().collect() }
+    fn starts_with(&self, p: &str) -> bool {
+        p.chars().enumerate().all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+    fn skip_ws(&mut self) {
+        while self.peek().map(|c| c.is_whitespace()).unwrap_or(false) { self.pos += 1; }
+    }
+    fn at_end(&self) -> bool { self.pos >= self.chars.len() }
+
+    // Read until `close`, skipping over quoted text; consumes the closer
+    fn until(&mut self, close: &str) -> Option<String> {
+        let mut out = String::new();
+        let mut quoted = false;
+        while !self.at_end() {
+            if !quoted && self.starts_with(close) {
+                self.pos += close.chars().count();
+                return Some(out);
+            }
+            let c = self.chars[self.pos];
+            if c == '"' { quoted = !quoted; }
+            out.push(c);
+            self.pos += 1;
+        }
+        None
+    }
+}
+
+// Shape openers, longest first, with their closer and GraphData shape name
+const SHAPES: &[(&str, &str, &str)] = &[
+    ("(((", ")))", "circle"),
+    ("((", "))", "circle"),
+    ("([", "])", "rounded"),
+    ("[[", "]]", "rectangle"),
+    ("[(", ")]", "cylinder"),
+    ("{{", "}}", "hexagon"),
+    ("[/", "]", "parallelogram"),
+    ("[\\", "]", "parallelogram"),
+    ("[", "]", "rectangle"),
+    ("(", ")", "rounded"),
+    ("{", "}", "diamond"),
+    (">", "]", "rectangle"),
+];
+
+fn parse_node(cur: &mut Cursor) -> Option<NodeRef> {
+    cur.skip_ws();
+    let start = cur.pos;
+    while cur.peek().map(is_id_char).unwrap_or(false) { cur.pos += 1; }
+    if cur.pos == start { return None; }
+    let id: String = cur.chars[start..cur.pos].iter().collect();
+    let mut node = NodeRef { id, label: None, shape: None, class: None };
+    for (open, close, shape) in SHAPES {
+        if cur.starts_with(open) {
+            cur.pos += open.chars().count();
+            let raw = cur.until(close)?;
+            let raw = raw.trim_end_matches(['/', '\\']);
+            node.label = Some(clean_label(raw));
+            node.shape = Some(shape);
+            break;
+        }
+    }
+    if cur.starts_with(":::") {
+        cur.pos += 3;
+        let cs = cur.pos;
+        while cur.peek().map(|c| is_id_char(c) || c == '-').unwrap_or(false) { cur.pos += 1; }
+        node.class = Some(cur.chars[cs..cur.pos].iter().collect());
+    }
+    Some(node)
+}
+
+fn is_link_char(c: char) -> bool {
+    matches!(c, '-' | '.' | '=' | '<' | '>' | '~')
+}
+
+// Consume a raw link operator such as "-->", "-.->", "==>", "---", "--o", "<-->"
+fn read_operator(cur: &mut Cursor) -> String {
+    let mut op = String::new();
+    while let Some(c) = cur.peek() {
+        if is_link_char(c) {
+            op.push(c);
+            cur.pos += 1;
+        } else if (c == 'o' || c == 'x') && op.ends_with(['-', '=', '.'])
+            && cur.chars.get(cur.pos + 1).map(|n| n.is_whitespace() || *n == '|').unwrap_or(true) {
+            op.push(c);
+            cur.pos += 1;
+            break;
+        } else {
+            break;
+        }
+    }
+    op
+}
+
+fn parse_link(cur: &mut Cursor) -> Option<Link> {
+    cur.skip_ws();
+    if !cur.peek().map(|c| is_link_char(c) || c == 'o' || c == 'x').unwrap_or(false) { return None; }
+    // Leading o/x markers are rare; treat them as plain links
+    if matches!(cur.peek(), Some('o') | Some('x')) && !cur.chars.get(cur.pos + 1).map(|c| is_link_char(*c)).unwrap_or(false) {
+        return None;
+    }
+    let mut op = read_operator(cur);
+    if op.len() < 2 { return None; }
+    let mut label = String::new();
+
+    // Text-in-link form: `-- text -->`, `-. text .->`, `== text ==>`
+    if matches!(op.as_str(), "--" | "-." | "==" | "<--" | "<-." | "<==") {
+        let rest = cur.rest();
+        let closers = ["-->", "---", ".->", ".-", "==>", "===", "--o", "--x"];
+        let hit = closers.iter().filter_map(|c| rest.find(c).map(|i| (i, *c))).min_by_key(|(i, _)| *i);
+        if let Some((idx, _)) = hit {
+            label = clean_label(&rest[..idx]);
+            cur.pos += rest[..idx].chars().count();
+            // Keep only the arrowhead of the closing half: `-->` adds '>', `---` adds nothing
+            let tail = read_operator(cur);
+            op.push_str(tail.trim_start_matches(['-', '.', '=']));
+        }
+    }
+
+    cur.skip_ws();
+    if cur.peek() == Some('|') {
+        cur.pos += 1;
+        label = clean_label(&cur.until("|").unwrap_or_default());
+    }
+
+    let visible = !op.contains('~');
+    let line = if op.contains('.') { "dashed" } else if op.contains('=') { "thick" } else { "solid" };
+    let start = op.starts_with('<');
+    let arrow = match (start, op.chars().last()) {
+        (true, Some('>')) => "both",
+        (true, _) => "start",
+        (false, Some('>')) => "end",
+        (false, Some('o')) => "dot",
+        (false, Some('x')) => "bar",
+        _ => "none",
+    };
+    Some(Link { label, line, arrow, visible })
+}
+
+fn parse_style_fill(spec: &str) -> Option<String> {
+    spec.split(',')
+        .filter_map(|kv| kv.split_once(':'))
+        .find(|(k, _)| k.trim() == "fill")
+        .map(|(_, v)| v.trim().trim_end_matches(';').to_string())
+}
+
+#[derive(Default)]
+struct Builder {
+    nodes: Vec<NodeData>,
+    index: HashMap<String, usize>,
+    node_class: HashMap<String, String>,
+    edges: Vec<EdgeData>,
+    containers: Vec<Container>,
+    stack: Vec<usize>,
+    placed: HashMap<String, usize>,
+    class_fill: HashMap<String, String>,
+    style_fill: HashMap<String, String>,
+}
+
+impl Builder {
+    fn touch(&mut self, n: NodeRef) -> String {
+        let idx = match self.index.get(&n.id) {
+            Some(i) => *i,
+            None => {
+                self.nodes.push(NodeData {
+                    id: n.id.clone(),
+                    label: n.id.clone(),
+                    x: 0.0,
+                    y: 0.0,
+                    style: NodeStyle { shape: "rectangle".to_string(), color: String::new() },
+                });
+                self.index.insert(n.id.clone(), self.nodes.len() - 1);
+                self.nodes.len() - 1
+            }
+        };
+        if let Some(label) = n.label { self.nodes[idx].label = label; }
+        if let Some(shape) = n.shape { self.nodes[idx].style.shape = shape.to_string(); }
+        if let Some(class) = n.class { self.node_class.insert(n.id.clone(), class); }
+        // First subgraph that mentions a node owns it
+        if let Some(&ci) = self.stack.last() {
+            if !self.placed.contains_key(&n.id) {
+                self.placed.insert(n.id.clone(), ci);
+                self.containers[ci].children.push(n.id.clone());
+            }
+        }
+        n.id
+    }
+
+    fn add_edge(&mut self, source: &str, target: &str, link: &Link) {
+        if !link.visible { return; }
+        let base = format!("{}_{}", source, target);
+        let mut id = base.clone();
+        let mut k = 2;
+        while self.edges.iter().any(|e| e.id == id) {
+            id = format!("{}_{}", base, k);
+            k += 1;
+        }
+        self.edges.push(EdgeData {
+            id,
+            source: source.to_string(),
+            target: target.to_string(),
+            label: link.label.clone(),
+            style: EdgeStyle { line: link.line.to_string(), arrow: link.arrow.to_string() },
+        });
+    }
+
+    fn statement(&mut self, stmt: &str) {
+        let lower = stmt.to_lowercase();
+        if lower == "end" {
+            self.stack.pop();
+            return;
+        }
+        if let Some(rest) = stmt.strip_prefix("subgraph ").or_else(|| (stmt == "subgraph").then_some("")) {
+            let rest = rest.trim();
+            let (id, label) = if let Some(open) = rest.find('[') {
+                let id = rest[..open].trim().to_string();
+                let label = clean_label(rest[open + 1..].trim_end_matches(']'));
+                (id, label)
+            } else {
+                let label = clean_label(rest);
+                let id = if label.chars().all(is_id_char) && !label.is_empty() { label.clone() } else { slug(&label) };
+                (id, label)
+            };
+            let id = if id.is_empty() { format!("subgraph_{}", self.containers.len()) } else { id };
+            self.containers.push(Container { id, label, children: Vec::new(), style: None });
+            self.stack.push(self.containers.len() - 1);
+            return;
+        }
+        if lower.starts_with("direction ") || lower.starts_with("linkstyle ") || lower.starts_with("click ") {
+            return;
+        }
+        if let Some(rest) = stmt.strip_prefix("classDef ") {
+            if let Some((names, spec)) = rest.trim().split_once(char::is_whitespace) {
+                if let Some(fill) = parse_style_fill(spec) {
+                    for name in names.split(',') { self.class_fill.insert(name.trim().to_string(), fill.clone()); }
+                }
+            }
+            return;
+        }
+        if let Some(rest) = stmt.strip_prefix("class ") {
+            if let Some((ids, class)) = rest.trim().rsplit_once(char::is_whitespace) {
+                for id in ids.split(',') { self.node_class.insert(id.trim().to_string(), class.trim().to_string()); }
+            }
+            return;
+        }
+        if let Some(rest) = stmt.strip_prefix("style ") {
+            if let Some((id, spec)) = rest.trim().split_once(char::is_whitespace) {
+                if let Some(fill) = parse_style_fill(spec) { self.style_fill.insert(id.to_string(), fill); }
+            }
+            return;
+        }
+
+        // Node chain: group (link group)*, where group = node (& node)*
+        let mut cur = Cursor::new(stmt);
+        let mut prev: Vec<String> = Vec::new();
+        let mut pending: Option<Link> = None;
+        loop {
+            let mut group = Vec::new();
+            while let Some(n) = parse_node(&mut cur) {
+                group.push(self.touch(n));
+                cur.skip_ws();
+                if cur.peek() == Some('&') { cur.pos += 1; } else { break; }
+            }
+            if group.is_empty() { break; }
+            if let Some(link) = pending.take() {
+                for s in &prev {
+                    for t in &group { self.add_edge(s, t, &link); }
+                }
+            }
+            prev = group;
+            match parse_link(&mut cur) {
+                Some(link) => pending = Some(link),
+                None => break,
+            }
+        }
+    }
+
+    fn finish(mut self, direction: String) -> GraphData {
+        for n in &mut self.nodes {
+            let fill = self.style_fill.get(&n.id)
+                .or_else(|| self.node_class.get(&n.id).and_then(|c| self.class_fill.get(c)));
+            if let Some(fill) = fill { n.style.color = fill.clone(); }
+        }
+        GraphData {
+            nodes: self.nodes,
+            edges: self.edges,
+            layout_hints: Some(LayoutHints { direction, algorithm: "longest_path".to_string(), ..Default::default() }),
+            global_style: Some(GlobalStyle { font: "Inter".to_string(), background: "#ffffff".to_string(), theme: Some("minimal".to_string()) }),
+            decorations: None,
+            containers: if self.containers.is_empty() { None } else { Some(self.containers) },
+            sequence: None,
+        }
+    }
+}
+
+// Split on `;` outside quotes/brackets so `A[x; y]` survives
+fn split_statements(line: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut cur = String::new();
+    let (mut quoted, mut depth) = (false, 0i32);
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            '[' | '(' | '{' if !quoted => depth += 1,
+            ']' | ')' | '}' if !quoted => depth -= 1,
+            ';' if !quoted && depth <= 0 => {
+                out.push(std::mem::take(&mut cur));
+                continue;
+            }
+            _ => {}
+        }
+        cur.push(c);
+    }
+    out.push(cur);
+    out.into_iter().map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect()
+}
+
+/// Parse a Mermaid flowchart (optionally inside a ```mermaid fence) into GraphData.
+/// Returns None when the text is not a flowchart.
+pub fn parse_mermaid_flowchart(text: &str) -> Option<GraphData> {
+    let mut lines = meaningful_lines(mermaid_source(text));
+    let (direction, rest) = header(lines.next()?)?;
+    let mut b = Builder::default();
+    for stmt in rest.into_iter().chain(lines.flat_map(split_statements)) {
+        b.statement(&stmt);
+    }
+    if b.nodes.is_empty() { return None; }
+    Some(b.finish(direction))
+}
+
+// Mermaid ids are restricted to word characters and must not collide with keywords.
+// `key` identifies the source object (node ids and container ids share Mermaid's namespace).
+fn mermaid_id(key: &str, raw: &str, used: &mut HashMap<String, String>) -> String {
+    if let Some(id) = used.get(key) { return id.clone(); }
+    let mut id: String = raw.chars().map(|c| if is_id_char(c) { c } else { '_' }).collect();
+    if id.is_empty() || matches!(id.as_str(), "end" | "graph" | "subgraph" | "flowchart" | "style" | "class" | "classDef") {
+        id = format!("{}_", id);
+    }
+    let base = id.clone();
+    let mut k = 2;
+    while used.values().any(|v| *v == id) {
+        id = format!("{}_{}", base, k);
+        k += 1;
+    }
+    used.insert(key.to_string(), id.clone());
+    id
+}
+
+fn mermaid_text(label: &str) -> String {
+    format!("\"{}\"", label.replace('"', "#quot;").replace('\n', "<br>"))
+}
+
+fn mermaid_node(id: &str, label: &str, shape: &str) -> String {
+    let text = mermaid_text(label);
+    match shape.to_lowercase().as_str() {
+        "rounded" | "stadium" => format!("{}([{}])", id, text),
+        "circle" | "ellipse" => format!("{}(({}))", id, text),
+        "diamond" | "decision" | "rhombus" => format!("{}{{{}}}", id, text),
+        "cylinder" | "database" | "db" => format!("{}[({})]", id, text),
+        "hexagon" => format!("{}{{{{{}}}}}", id, text),
+        "parallelogram" => format!("{}[/{}/]", id, text),
+        _ => format!("{}[{}]", id, text),
+    }
+}
+
+fn mermaid_link(line: &str, arrow: &str) -> &'static str {
+    let line = line.to_lowercase();
+    let arrow = arrow.to_lowercase();
+    let dashed = line == "dashed" || line == "dotted";
+    let thick = line == "thick";
+    let head = match arrow.as_str() {
+        "none" => "",
+        "both" => "both",
+        "dot" | "circle" => "o",
+        "bar" | "cross" => "x",
+        _ => ">",
+    };
+    match (dashed, thick, head) {
+        (true, _, "") => "-.-",
+        (true, _, "both") => "<-.->",
+        (true, _, "o") => "-.-o",
+        (true, _, "x") => "-.-x",
+        (true, _, _) => "-.->",
+        (_, true, "") => "===",
+        (_, true, "both") => "<==>",
+        (_, true, "o") => "==o",
+        (_, true, "x") => "==x",
+        (_, true, _) => "==>",
+        (_, _, "") => "---",
+        (_, _, "both") => "<-->",
+        (_, _, "o") => "--o",
+        (_, _, "x") => "--x",
+        _ => "-->",
+    }
+}
+
+/// Export GraphData as Mermaid flowchart text (paste-able into GitHub markdown).
+pub fn graphdata_to_mermaid(g: &GraphData) -> String {
+    let dir = match g.layout_hints.as_ref().map(|h| h.direction.to_uppercase()).as_deref() {
+        Some("LR") => "LR",
+        Some("RL") => "RL",
+        Some("BT") => "BT",
+        _ => "TB",
+    };
+    let mut ids: HashMap<String, String> = HashMap::new();
+    let mut out = format!("flowchart {}\n", dir);
+
+    // Reserve node ids first so containers get the suffix on collisions
+    for n in &g.nodes { mermaid_id(&n.id, &n.id, &mut ids); }
+
+    let mut in_container: HashSet<&str> = HashSet::new();
+    for c in g.containers.iter().flatten() {
+        let cid = mermaid_id(&format!("container:{}", c.id), &c.id, &mut ids);
+        out.push_str(&format!("    subgraph {} [{}]\n", cid, mermaid_text(&c.label)));
+        for child in &c.children {
+            if in_container.contains(child.as_str()) { continue; }
+            if let Some(n) = g.nodes.iter().find(|n| &n.id == child) {
+                in_container.insert(child.as_str());
+                let id = mermaid_id(&n.id, &n.id, &mut ids);
+                out.push_str(&format!("        {}\n", mermaid_node(&id, &n.label, &n.style.shape)));
+            }
+        }
+        out.push_str("    end\n");
+    }
+    for n in &g.nodes {
+        if in_container.contains(n.id.as_str()) { continue; }
+        let id = mermaid_id(&n.id, &n.id, &mut ids);
+        out.push_str(&format!("    {}\n", mermaid_node(&id, &n.label, &n.style.shape)));
+    }
+
+    for e in &g.edges {
+        if !g.nodes.iter().any(|n| n.id == e.source) || !g.nodes.iter().any(|n| n.id == e.target) { continue; }
+        // Mermaid has no start-only head: flip the edge instead
+        let (s, t) = if e.style.arrow.eq_ignore_ascii_case("start") { (&e.target, &e.source) } else { (&e.source, &e.target) };
+        let s = mermaid_id(s, s, &mut ids);
+        let t = mermaid_id(t, t, &mut ids);
+        let link = mermaid_link(&e.style.line, if e.style.arrow.eq_ignore_ascii_case("start") { "end" } else { &e.style.arrow });
+        if e.label.is_empty() {
+            out.push_str(&format!("    {} {} {}\n", s, link, t));
+        } else {
+            out.push_str(&format!("    {} {}|{}| {}\n", s, link, mermaid_text(&e.label), t));
+        }
+    }
+
+    for n in &g.nodes {
+        let color = n.style.color.trim();
+        if !color.is_empty() {
+            let id = mermaid_id(&n.id, &n.id, &mut ids);
+            out.push_str(&format!("    style {} fill:{}\n", id, color));
+        }
+    }
+    out
+}
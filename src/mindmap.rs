@@ -0,0 +1,43 @@
+use crate::edge_style::with_routing;
+use crate::layout::{engine_for, spanning_tree};
+use crate::state::GraphData;
+
+// Mindmap styling: the central topic keeps its own color, each first-level branch keeps its own
+// color (or gets a palette color when it has none) that its whole subtree inherits, and
+// connectors are drawn as curves (keeping their stroke and width).
+
+const BRANCH_PALETTE: &[&str] = &["#BFDBFE", "#BBF7D0", "#FDE68A", "#FBCFE8", "#DDD6FE", "#FED7AA", "#A5F3FC", "#FECACA"];
+
+/// Prepare a mindmap for rendering: radial layout (unless a tree layout was requested),
+/// curved edges, and branch colors inherited from the first-level ancestor.
+pub fn style_mindmap(g: &mut GraphData) {
+    let hints = g.layout_hints.get_or_insert_with(Default::default);
+    if !matches!(engine_for(&hints.algorithm).map(|e| e.name()), Some("radial" | "tree")) {
+        hints.algorithm = "radial".to_string();
+    }
+    for e in &mut g.edges {
+        e.style.line = with_routing(&e.style.line, "curved");
+    }
+
+    let Some(tree) = spanning_tree(g) else { return };
+    let branches = &tree.children[tree.root];
+    let colors: Vec<String> = branches
+        .iter()
+        .enumerate()
+        .map(|(b, &n)| match g.nodes[n].style.color.as_str() {
+            "" => BRANCH_PALETTE[b % BRANCH_PALETTE.len()].to_string(),
+            own => own.to_string(),
+        })
+        .collect();
+    for i in 0..g.nodes.len() {
+        if i == tree.root { continue; }
+        let mut u = i;
+        while let Some(p) = tree.parent[u] {
+            if p == tree.root { break; }
+            u = p;
+        }
+        if let Some(b) = branches.iter().position(|&c| c == u) {
+            g.nodes[i].style.color = colors[b].clone();
+        }
+    }
+}
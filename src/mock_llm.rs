@@ -0,0 +1,326 @@
+// Offline, deterministic LLM provider for tests and demos (provider name "mock").
+//
+// Replies come from a fixtures directory (`GRAPHFLOW_MOCK_FIXTURES`) holding `<hash>.txt` files,
+// where `<hash>` is `prompt_hash` of the exact prompt; the file content is returned verbatim, so
+// fixtures can also capture malformed replies. Without a matching fixture the reply is generated
+// from the prompt's USER_INPUT by simple rules: `A -> B` chains and one step per line, shapes and
+// containers chosen from keywords, and built-in icon decorations. Same prompt, same reply.
+
+use crate::llm::{Capabilities, LlmProvider, LlmRequest};
+use crate::state::{
+    Container, ContainerStyle, Decoration, DecorationOffset, DecorationSize, EdgeData, EdgeStyle, GlobalStyle,
+    GraphData, LayoutHints, NodeData, NodeStyle,
+};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// Environment variable naming the mock provider's fixtures directory.
+pub const FIXTURES_ENV: &str = "GRAPHFLOW_MOCK_FIXTURES";
+
+/// Stable fixture key for a prompt: 64-bit FNV-1a, as 16 hex digits.
+pub fn prompt_hash(prompt: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in prompt.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+pub struct MockProvider;
+
+impl MockProvider {
+    /// The fixture reply for `prompt`, if the fixtures directory has one.
+    pub fn fixture(prompt: &str) -> Option<String> {
+        let dir = env::var(FIXTURES_ENV).ok().filter(|d| !d.is_empty())?;
+        fs::read_to_string(PathBuf::from(dir).join(format!("{}.txt", prompt_hash(prompt)))).ok()
+    }
+}
+
+#[async_trait]
+impl LlmProvider for MockProvider {
+    fn capabilities(&self) -> Capabilities {
+        Capabilities { streaming: false, max_output_tokens: u32::MAX, structured_output: false }
+    }
+
+    async fn complete(&self, req: &LlmRequest) -> Result<String, String> {
+        if let Some(reply) = Self::fixture(&req.prompt) {
+            return Ok(reply);
+        }
+        serde_json::to_string(&generate(&req.prompt)).map_err(|e| e.to_string())
+    }
+}
+
+// Node roles picked from label keywords: shape, fill, and the container the node goes in
+#[derive(Clone, Copy, PartialEq)]
+enum Role {
+    Terminal,
+    Decision,
+    Person,
+    Data,
+    Document,
+    Step,
+}
+
+impl Role {
+    fn of(label: &str) -> Role {
+        let lower = label.to_lowercase();
+        let words: Vec<&str> = lower.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).collect();
+        let any = |keys: &[&str]| words.iter().any(|w| keys.contains(w));
+        if lower.ends_with('?') || any(&["if", "decide", "decision", "check", "valid", "approved"]) {
+            Role::Decision
+        } else if any(&["start", "begin", "end", "done", "finish", "stop"]) {
+            Role::Terminal
+        } else if any(&["user", "users", "customer", "client", "admin", "person", "visitor"]) {
+            Role::Person
+        } else if any(&["db", "database", "store", "storage", "cache", "queue", "table", "bucket"]) {
+            Role::Data
+        } else if any(&["report", "file", "document", "invoice", "pdf"]) {
+            Role::Document
+        } else {
+            Role::Step
+        }
+    }
+
+    fn shape(self) -> &'static str {
+        match self {
+            Role::Terminal => "rounded",
+            Role::Decision => "diamond",
+            Role::Person => "actor",
+            Role::Data => "cylinder",
+            Role::Document => "document",
+            Role::Step => "rectangle",
+        }
+    }
+
+    fn color(self) -> &'static str {
+        match self {
+            Role::Terminal => "#DCFCE7",
+            Role::Decision => "#FEF3C7",
+            Role::Person => "#E0E7FF",
+            Role::Data => "#DBEAFE",
+            Role::Document => "#F3E8FF",
+            Role::Step => "#F3F4F6",
+        }
+    }
+
+    // Container id and label; documents and terminals sit with the steps
+    fn group(self) -> (&'static str, &'static str) {
+        match self {
+            Role::Person => ("people", "People"),
+            Role::Data => ("data", "Data"),
+            _ => ("steps", "Steps"),
+        }
+    }
+}
+
+/// Rule-based GraphData for a generation (or correction) prompt.
+pub fn generate(prompt: &str) -> GraphData {
+    let input = user_input(prompt);
+    let kind = slot(prompt, "- Kind: ", ".").unwrap_or("auto");
+    let direction = slot(prompt, "layout_hints.direction to \"", "\"").unwrap_or("TB");
+    let algorithm = slot(prompt, "algorithm \"", "\"").unwrap_or("");
+
+    let mut g = GraphData {
+        layout_hints: Some(LayoutHints { direction: direction.to_string(), algorithm: algorithm.to_string(), ..Default::default() }),
+        global_style: Some(GlobalStyle { font: "Inter".to_string(), background: "#FFFFFF".to_string(), theme: Some("minimal".to_string()) }),
+        ..Default::default()
+    };
+    let mut ids: HashMap<String, String> = HashMap::new();
+    let mut previous_step: Option<String> = None;
+    for statement in statements(&input) {
+        let tokens: Vec<&str> = statement.split("->").map(str::trim).collect();
+        if tokens.len() >= 2 {
+            for pair in tokens.windows(2) {
+                let (to, label) = match pair[1].split_once(':') {
+                    Some((to, label)) => (to.trim(), label.trim()),
+                    None => (pair[1], ""),
+                };
+                let (Some(from), Some(to)) = (node_for(&mut g, &mut ids, pair[0]), node_for(&mut g, &mut ids, to)) else { continue };
+                add_edge(&mut g, &from, &to, label);
+            }
+            previous_step = None;
+        } else if let Some(id) = node_for(&mut g, &mut ids, &statement) {
+            // Plain lines form a sequence of steps
+            if let Some(prev) = previous_step.replace(id.clone()) {
+                add_edge(&mut g, &prev, &id, "");
+            }
+        }
+    }
+    label_branches(&mut g);
+
+    if !matches!(kind, "mindmap" | "sequence") {
+        let mut containers: Vec<Container> = Vec::new();
+        for node in &g.nodes {
+            let (id, label) = Role::of(&node.label).group();
+            match containers.iter_mut().find(|c| c.id == id) {
+                Some(c) => c.children.push(node.id.clone()),
+                None => containers.push(Container {
+                    id: id.to_string(),
+                    label: label.to_string(),
+                    children: vec![node.id.clone()],
+                    style: Some(ContainerStyle { bg: Some("#FFFFFF".to_string()), border: Some("#D1D5DB".to_string()), radius: Some(12.0), label_tag: None }),
+                }),
+            }
+        }
+        if !containers.is_empty() {
+            g.containers = Some(containers);
+        }
+    }
+
+    let decorations = decorations(&g);
+    if !decorations.is_empty() {
+        g.decorations = Some(decorations);
+    }
+    g
+}
+
+// Text after the USER_INPUT header, without a correction prompt's trailing sections
+fn user_input(prompt: &str) -> String {
+    let body = prompt.split_once("USER_INPUT\n").map(|(_, b)| b).unwrap_or(prompt);
+    let body = body.split_once("\n\nYOUR PREVIOUS REPLY").map(|(b, _)| b).unwrap_or(body);
+    // Kind tags only steer the prompt; they are not diagram content
+    let mut text = body.to_string();
+    for kind in ["flow", "system", "sequence", "mindmap"] {
+        for tag in [format!("[mode: {}]", kind), format!("<{}>", kind), format!(":{}", kind)] {
+            while let Some(at) = text.to_ascii_lowercase().find(&tag) {
+                text.replace_range(at..at + tag.len(), "");
+            }
+        }
+    }
+    text
+}
+
+fn slot<'a>(prompt: &'a str, before: &str, after: &str) -> Option<&'a str> {
+    let rest = &prompt[prompt.find(before)? + before.len()..];
+    Some(&rest[..rest.find(after)?])
+}
+
+// One statement per line (or `;`); commas separate arrow chains, and the items of a single
+// line without arrows
+fn statements(input: &str) -> Vec<String> {
+    let strip = |s: &str| -> String {
+        let s = s.trim().trim_start_matches(['-', '*', '•']).trim_start();
+        let s = match s.split_once(". ") {
+            Some((n, rest)) if !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()) => rest,
+            _ => s,
+        };
+        s.trim().to_string()
+    };
+    let lines: Vec<&str> = input.split(['\n', ';']).filter(|l| !l.trim().is_empty()).collect();
+    let single = lines.len() == 1;
+    lines
+        .into_iter()
+        .flat_map(|l| if single || l.contains("->") { l.split(',').collect() } else { vec![l] })
+        .map(strip)
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+fn slug(s: &str) -> String {
+    let mut out = String::new();
+    for ch in s.chars() {
+        if ch.is_alphanumeric() { out.extend(ch.to_lowercase()); } else if !out.ends_with('_') { out.push('_'); }
+    }
+    out.trim_matches('_').to_string()
+}
+
+// Id of the node labelled `label`, adding it on first mention
+fn node_for(g: &mut GraphData, ids: &mut HashMap<String, String>, label: &str) -> Option<String> {
+    let label = label.trim();
+    if label.is_empty() {
+        return None;
+    }
+    if let Some(id) = ids.get(&label.to_lowercase()) {
+        return Some(id.clone());
+    }
+    let base = match slug(label) {
+        s if s.is_empty() => "node".to_string(),
+        s => s,
+    };
+    let mut id = base.clone();
+    let mut n = 2;
+    while g.nodes.iter().any(|node| node.id == id) {
+        id = format!("{}_{}", base, n);
+        n += 1;
+    }
+    let role = Role::of(label);
+    g.nodes.push(NodeData {
+        id: id.clone(),
+        label: label.to_string(),
+        x: 0.0,
+        y: 0.0,
+        style: NodeStyle { shape: role.shape().to_string(), color: role.color().to_string() },
+    });
+    ids.insert(label.to_lowercase(), id.clone());
+    Some(id)
+}
+
+fn add_edge(g: &mut GraphData, source: &str, target: &str, label: &str) {
+    if g.edges.iter().any(|e| e.source == source && e.target == target && e.label == label) {
+        return;
+    }
+    let base = format!("{}_{}", source, target);
+    let mut id = base.clone();
+    let mut n = 2;
+    while g.edges.iter().any(|e| e.id == id) {
+        id = format!("{}_{}", base, n);
+        n += 1;
+    }
+    g.edges.push(EdgeData {
+        id,
+        source: source.to_string(),
+        target: target.to_string(),
+        label: label.to_string(),
+        style: EdgeStyle { line: "orthogonal".to_string(), arrow: "end".to_string() },
+    });
+}
+
+// Unlabelled branches out of a decision read "yes" then "no"; the "no" path is drawn dashed
+fn label_branches(g: &mut GraphData) {
+    let decisions: Vec<String> = g.nodes.iter().filter(|n| n.style.shape == "diamond").map(|n| n.id.clone()).collect();
+    for id in decisions {
+        for (i, edge) in g.edges.iter_mut().filter(|e| e.source == id && e.label.is_empty()).take(2).enumerate() {
+            edge.label = if i == 0 { "yes" } else { "no" }.to_string();
+            if i == 1 {
+                edge.style.line = "orthogonal dashed".to_string();
+            }
+        }
+    }
+}
+
+// Built-in icons on the first node each keyword set matches, at most three
+fn decorations(g: &GraphData) -> Vec<Decoration> {
+    let rules: [(&str, &[&str]); 5] = [
+        ("database", &["db", "database", "store", "storage", "cache"]),
+        ("salesperson", &["user", "users", "customer", "client", "admin"]),
+        ("email", &["email", "mail", "notify", "notification"]),
+        ("search", &["search", "lookup", "find", "query"]),
+        ("model", &["model", "ai", "llm", "ml"]),
+    ];
+    let mut out: Vec<Decoration> = Vec::new();
+    for (builtin, keys) in rules {
+        let hit = g.nodes.iter().find(|n| {
+            let lower = n.label.to_lowercase();
+            let taken = out.iter().any(|d| d.target.as_deref() == Some(n.id.as_str()));
+            !taken && lower.split(|c: char| !c.is_alphanumeric()).any(|w| keys.contains(&w))
+        });
+        if let Some(node) = hit {
+            out.push(Decoration {
+                r#type: "icon".to_string(),
+                target: Some(node.id.clone()),
+                builtin: Some(builtin.to_string()),
+                size: Some(DecorationSize { w: 20.0, h: 20.0 }),
+                offset: Some(DecorationOffset { dx: -24.0, dy: -24.0 }),
+                ..Default::default()
+            });
+        }
+        if out.len() == crate::validate::MAX_DECORATIONS {
+            break;
+        }
+    }
+    out
+}
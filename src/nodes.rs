@@ -4,10 +4,20 @@ use async_trait::async_trait;
 use std::io::{self, Write};
 use std::fs;
 use std::path::Path;
-use std::process::Command;
-use std::collections::{HashMap, BTreeMap, VecDeque};
-use crate::state::{AiStatus, SharedState, UserSession, UserTier, ChatInput, InputType, AiResponse, Graph, GraphData, PaymentInfo, PaymentStatus};
-use crate::utils::{call_llm_ai_model, parse_media, db_save_graph, db_update_user_credits, process_payment, auth_authenticate, auth_validate_session, db_retrieve_graph};
+use crate::state::{AiStatus, SharedState, UserSession, UserTier, ChatInput, InputType, AiResponse, Graph, GraphData, LlmRetry, PaymentInfo, PaymentStatus};
+use crate::utils::{parse_media, db_save_graph, db_update_user_credits, process_payment, auth_authenticate, auth_validate_session, db_retrieve_graph};
+use crate::render::{render_scene, RasterOptions, RenderBackend};
+use crate::layout::{engine_for, engines, run_layout, LayoutOptions};
+use crate::cassette::{CassetteConfig, CassetteMode};
+use crate::llm::{RouteError, RouteKey, Routed};
+use crate::llm_json::parse_graph_data;
+use crate::llm_schema::graph_data_schema;
+use crate::mermaid::{is_mermaid_flowchart, parse_mermaid_flowchart, graphdata_to_mermaid};
+use crate::dot::{is_dot_graph, parse_dot, graphdata_to_dot};
+use crate::drawio::graphdata_to_drawio_with_opts;
+use crate::mindmap::style_mindmap;
+use crate::sequence::ensure_sequence;
+use crate::validate::{repair, summarize, validate, Severity};
 use serde_json::json;
 use chrono::Utc;
 // use crate::excalidraw::graphdata_to_excalidraw_scene; // not needed here
@@ -27,110 +37,114 @@ fn suggest_filename(text: &str) -> String {
     if s.is_empty() { "graph".to_string() } else { s.to_string() }
 }
 
+fn write_export(path: &str, what: &str, content: String) {
+    if let Err(e) = fs::write(path, content) {
+        eprintln!("Failed to write {} to {}: {}", what, path, e);
+    } else {
+        eprintln!("{} exported to {}", what, path);
+    }
+}
+
 // --- Auto layout helpers ---
 
-fn approx_node_size(label: &str) -> (f64, f64) {
-    let w = (label.len() as f64 * 10.0 + 30.0).max(100.0);
-    let h = 48.0;
-    (w, h)
+pub fn apply_auto_layout(g: &mut GraphData, rank_sep: f64, node_sep: f64, dir: &str, max_per_rank: usize) {
+    // The engine comes from layout_hints.algorithm; hinted gaps override the caller's defaults
+    let opts = LayoutOptions::from_hints(g.layout_hints.as_ref(), dir, rank_sep, node_sep, max_per_rank);
+    run_layout(g, &opts);
 }
 
-fn apply_auto_layout(g: &mut GraphData, node_gap: f64, rank_gap: f64, dir: &str, _max_per_rank: usize) {
-    // Build adjacency and indegree
-    let mut adj: HashMap<String, Vec<String>> = HashMap::new();
-    let mut indeg: HashMap<String, usize> = HashMap::new();
-    for n in &g.nodes { indeg.entry(n.id.clone()).or_insert(0); adj.entry(n.id.clone()).or_insert_with(Vec::new); }
-    for e in &g.edges {
-        if let (Some(_), Some(_)) = (indeg.get(&e.source), indeg.get(&e.target)) {
-            adj.entry(e.source.clone()).or_default().push(e.target.clone());
-            *indeg.entry(e.target.clone()).or_insert(0) += 1;
-        }
-    }
+/// `apply_auto_layout` on tokio's blocking pool, so laying out and routing a large graph does
+/// not stall other requests.
+pub async fn auto_layout_blocking(mut g: GraphData, rank_sep: f64, node_sep: f64, dir: String, max_per_rank: usize) -> Result<GraphData> {
+    tokio::task::spawn_blocking(move || {
+        apply_auto_layout(&mut g, rank_sep, node_sep, &dir, max_per_rank);
+        g
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!("Layout task failed: {}", e))
+}
 
-    // Kahn topo
-    let mut q: VecDeque<String> = indeg.iter().filter(|(_, &d)| d==0).map(|(k,_)| k.clone()).collect();
-    let mut order: Vec<String> = Vec::new();
-    let mut indeg_mut = indeg.clone();
-    while let Some(u) = q.pop_front() {
-        order.push(u.clone());
-        if let Some(vs) = adj.get(&u) {
-            for v in vs {
-                if let Some(d) = indeg_mut.get_mut(v) {
-                    if *d > 0 { *d -= 1; if *d == 0 { q.push_back(v.clone()); } }
-                }
-            }
-        }
-    }
-    if order.len() != g.nodes.len() {
-        // Graph may have cycles; fall back to input order
-        order = g.nodes.iter().map(|n| n.id.clone()).collect();
+/// The Excalidraw scene for `g`, built on tokio's blocking pool (edge routing dominates).
+pub async fn excalidraw_scene_blocking(g: GraphData, allow_images: bool, assets_dir: String) -> Result<serde_json::Value> {
+    tokio::task::spawn_blocking(move || crate::excalidraw::graphdata_to_excalidraw_scene_with_opts(&g, allow_images, &assets_dir))
+        .await
+        .map_err(|e| anyhow::anyhow!("Scene task failed: {}", e))
+}
+
+// --- Intent helpers ---
+// Layout engine suited to each diagram kind; used when the LLM leaves the algorithm unset
+fn default_algorithm(kind: &str) -> &'static str {
+    match kind {
+        "system" => "layered",
+        "mindmap" => "radial",
+        _ => "longest_path",
     }
+}
 
-    // Longest-path rank assignment
-    let mut rank: HashMap<String, usize> = HashMap::new();
-    for id in &order { rank.insert(id.clone(), 0); }
-    for u in &order {
-        let ru = *rank.get(u).unwrap_or(&0);
-        if let Some(vs) = adj.get(u) {
-            for v in vs {
-                let entry = rank.entry(v.clone()).or_insert(0);
-                if ru + 1 > *entry { *entry = ru + 1; }
-            }
-        }
+// Kind-specific structure and styling on top of the generic graph
+fn apply_kind_extensions(kind: &str, g: &mut GraphData) {
+    match kind {
+        "mindmap" => style_mindmap(g),
+        "sequence" => ensure_sequence(g),
+        _ => {}
     }
+}
 
-    // Group nodes by rank preserving relative order
-    let mut by_rank: BTreeMap<usize, Vec<String>> = BTreeMap::new();
-    for id in &order {
-        let r = *rank.get(id).unwrap_or(&0);
-        by_rank.entry(r).or_default().push(id.clone());
-    }
-
-    // (Reserved for future centering calculations)
-
-    // Map id -> position with multi-row packing per rank
-    let mut pos: HashMap<String, (f64, f64)> = HashMap::new();
-    for (r, ids) in &by_rank {
-        let r = *r as f64;
-        let chunks: Vec<&[String]> = ids.chunks(_max_per_rank.max(1)).collect();
-        // center rows/cols around 0
-        let rows = chunks.len() as f64;
-        let start_secondary = -((rows - 1.0) * rank_gap) / 2.0;
-        for (row_idx, chunk) in chunks.iter().enumerate() {
-            let count_primary = chunk.len() as f64;
-            let start_primary = -((count_primary - 1.0) * rank_gap) / 2.0;
-            for (col_idx, id) in chunk.iter().enumerate() {
-                let _ = approx_node_size(&g.nodes.iter().find(|n| n.id == **id).map(|n| n.label.clone()).unwrap_or_default());
-                let col = col_idx as f64;
-                let row = row_idx as f64;
-                let (x, y) = if dir == "TB" {
-                    // ranks go down (y), primary spread is x (within a row), rows stacked vertically
-                    let y = r * node_gap + (start_secondary + row * rank_gap);
-                    let x = start_primary + col * rank_gap;
-                    (x, y)
-                } else {
-                    // ranks go right (x), primary spread is y (within a column), rows stacked horizontally
-                    let x = r * node_gap + (start_secondary + row * rank_gap);
-                    let y = start_primary + col * rank_gap;
-                    (x, y)
-                };
-                pos.insert((*id).clone(), (x, y));
-            }
+/// Correction round-trips allowed when the LLM reply is unusable (see `--llm-retries`).
+const DEFAULT_LLM_RETRIES: u32 = 2;
+
+// Parsed graph (if any) and the problems that warrant asking the model again: unparseable
+// output or error-level diagnostics. Warnings alone are accepted.
+fn check_reply(reply: &str) -> (Option<GraphData>, Vec<String>) {
+    match parse_graph_data(reply) {
+        Ok(g) => {
+            let problems = validate(&g).into_iter().filter(|d| d.severity == Severity::Error).map(|d| d.message).collect();
+            (Some(g), problems)
         }
+        Err(e) => (None, vec![format!("the reply is not a valid GraphData JSON object ({})", e)]),
     }
+}
+
+// One LLM call through the resolved route, constrained to the GraphData schema where the provider
+// supports it; with `stream`, the reply is echoed to stderr as it arrives
+async fn ask(routed: &Result<Routed, RouteError>, prompt: &str, stream: bool) -> std::result::Result<String, String> {
+    let routed = routed.as_ref().map_err(|e| e.to_string())?;
+    let mut echo = |delta: &str| eprint!("{}", delta);
+    let reply = routed.complete(prompt, Some(graph_data_schema()), if stream { Some(&mut echo) } else { None }).await;
+    if stream { eprintln!(); }
+    reply
+}
 
-    // Apply positions back into nodes
-    for n in &mut g.nodes {
-        if let Some((x, y)) = pos.get(&n.id) {
-            n.x = *x as f32;
-            n.y = *y as f32;
+// Cassette from the run's `llm_cassette` / `llm_cassette_mode`, else from the environment
+fn cassette_config(context: &Context) -> std::result::Result<Option<CassetteConfig>, String> {
+    match context.get("llm_cassette").and_then(|v| v.as_str()) {
+        Some(path) if !path.is_empty() => {
+            let mode = CassetteMode::parse(context.get("llm_cassette_mode").and_then(|v| v.as_str()).unwrap_or(""))?;
+            Ok(Some(CassetteConfig { path: path.into(), mode }))
         }
+        _ => CassetteConfig::from_env(),
     }
 }
 
-// --- Intent helpers ---
+fn correction_prompt(prompt: &str, reply: &str, problems: &[String]) -> String {
+    let list: Vec<String> = problems.iter().map(|p| format!("- {}", p)).collect();
+    format!(
+        "{}\n\nYOUR PREVIOUS REPLY\n{}\n\nPROBLEMS\n{}\n\nReturn the corrected JSON object only: fix every problem above and keep everything else unchanged.",
+        prompt.trim_end(),
+        reply,
+        list.join("\n")
+    )
+}
+
 fn infer_diagram_kind(text: &str) -> (&'static str, &'static str) {
     let lower = text.to_lowercase();
+    // Structured diagram sources are imported as-is
+    if is_mermaid_flowchart(text) {
+        return ("mermaid", "TB");
+    }
+    if is_dot_graph(text) {
+        return ("dot", "TB");
+    }
     // Explicit tags have priority
     if lower.contains(":sequence") || lower.contains("[mode: sequence]") || lower.contains("<sequence>") {
         return ("sequence", "LR");
@@ -163,6 +177,85 @@ fn infer_diagram_kind(text: &str) -> (&'static str, &'static str) {
     ("auto", "TB")
 }
 
+/// The generation prompt sent to the LLM for `content`: the GraphData schema, structural rules,
+/// and guidance for the inferred diagram kind. Mock fixtures are keyed by a hash of this text.
+pub fn generation_prompt(content: &str) -> String {
+    let (kind, default_dir) = infer_diagram_kind(content);
+    // Ask the LLM to output ONLY valid JSON matching our GraphData schema.
+    let template = r##"
+You are the Logic Engine of a two-stage diagram system. Focus ONLY on logic & structure. Output JSON ONLY (no prose, no markdown).
+
+CONSTRAINTS
+- Do NOT include SVG, images, or styling.
+- Prefer DAGs unless cycles are explicit and labeled.
+- Avoid ambiguity; design a balanced, readable structure.
+
+WHEN TO INFER
+- If not provided, infer the suitable diagram family.
+- Fill minimal missing connections only when clearly implied.
+
+GRAPHFLOW SCHEMA (exact keys)
+{
+  "nodes": [{"id":"string_snake_case","label":"string","x":0,"y":0,"style":{"shape":"rectangle"|"rounded"|"ellipse"|"circle"|"diamond"|"cylinder"|"parallelogram"|"hexagon"|"document"|"actor","color":"#F3F4F6"}}],
+  "edges": [{"id":"string_snake_case","source":"node_id","target":"node_id","label":"","style":{"line":"orthogonal"|"straight"|"curved" + optional " dashed"|" dotted"|" thick","arrow":"end"|"start"|"both"|"none" + optional " triangle"|" bar"|" dot"}}],
+  "layout_hints": {"direction":"LR"|"TB","algorithm":ALGOLIST},
+  "global_style": {"font":"Inter","background":"#FFFFFF","theme":"minimal"},
+  "decorations": null | [{
+     "type": "icon"|"note",
+     "target": "node_id_or_edge_id"|null,
+     "builtin": "database"|"model"|"search"|"email"|"salesperson"|null,
+     "url": "",
+     "size": {"w":number,"h":number}|null,
+     "offset": {"dx":number,"dy":number}|null,
+     "text": ""|null
+  }],
+  "containers": null | [{"id":"string_snake_case","label":"string","children":["node_id"],"style":{"bg":"#FFFFFF","border":"#D1D5DB","radius":12,"label_tag":"string"}}],
+  "sequence": null | {
+     "participants": [{"id":"node_id","label":"string","kind":"participant"|"actor"|"database"}],
+     "messages": [{"from":"node_id","to":"node_id","label":"string","kind":"sync"|"async"|"return"}],
+     "fragments": null | [{"kind":"alt"|"loop"|"opt","label":"guard","start":0,"end":0,"sections":null|[{"label":"else","start":0}]}]
+  }
+}
+
+RULES
+- IDs unique, snake_case; no dangling edges; no duplicate edges.
+- Containers reference existing nodes only; limit decorations ≤ 3.
+- Decisions use edge labels; only add gateway nodes when required.
+- Edge styles carry meaning: keep the main path solid; draw optional, fallback, or async paths "dashed" and weak associations "dotted".
+- Shapes carry meaning: diamond for decisions, rounded/circle for start and end, cylinder for data stores, parallelogram for input/output, document for files and reports, actor for people; rectangle otherwise.
+
+DIAGRAM GUIDANCE
+- Kind: KINDSLOT. If "auto", choose among flow, system, sequence, mindmap.
+- Layout: set layout_hints.direction to "DIRSLOT" unless readability is better otherwise; algorithm "ALGOSLOT" unless another fits better: "layered" for dense cross-links, "tree" for strict hierarchies, "force" for peer networks, "grid" for unconnected items, "preserve" only when you set meaningful x/y.
+- Flowchart: clear start/end, labeled branches, balanced symmetry.
+- System: group components in meaningful containers; orthogonal connectors.
+- Sequence: fill "sequence": participants left→right, messages top→bottom in send order (replies as "return"); fragment start/end/section start are 0-based message indices. Mirror participants as nodes and messages as edges.
+- Mindmap: one central topic with branches (a tree); avoid cycles.
+
+DECORATIONS (icons by the model)
+- Only add when they materially improve comprehension (max 3).
+- Prefer built-in icon names matching the assets dir: database, model, search, email, salesperson.
+- Use one of:
+  - builtin: "<name>"
+  - url: "builtin:<name>"
+- Place relative to target center with small offset to corners (e.g., dx:-24, dy:-24) and size 16–24.
+- If no target is provided, you may use absolute at_x/at_y placement.
+
+OUTPUT
+Return ONE valid JSON object matching the schema above. No extra keys, no comments.
+
+USER_INPUT
+{content}
+"##;
+    let algorithms: Vec<String> = engines().iter().map(|e| format!("\"{}\"", e.name())).collect();
+    template
+        .replace("KINDSLOT", kind)
+        .replace("DIRSLOT", default_dir)
+        .replace("ALGOSLOT", default_algorithm(kind))
+        .replace("ALGOLIST", &algorithms.join("|"))
+        .replace("{content}", content)
+}
+
 pub struct GetQuestionNode;
 
 #[async_trait]
@@ -221,7 +314,8 @@ impl Node for AnswerNode {
         let tier = shared_state.user_session.tier.clone();
         
         // Call LLM to get the answer
-        let answer = call_llm_ai_model(&question, &tier).await.map_err(|e| anyhow::anyhow!(e))?;
+        let routed = Routed::resolve(&RouteKey { tier: &tier, kind: "auto", route: None }).map_err(|e| anyhow::anyhow!(e))?;
+        let answer = routed.complete(&question, None, None).await.map_err(|e| anyhow::anyhow!(e))?;
         
         Ok(json!({"answer": answer}))
     }
@@ -381,95 +475,76 @@ impl Node for AIProcessingNode {
         let chat_input = shared_state.chat_input.clone();
         let tier = shared_state.user_session.tier.clone();
         let user_session = shared_state.user_session.clone();
+        let (kind, default_dir) = infer_diagram_kind(&chat_input.content);
 
-        let credits_cost = 5u32;
+        // Route by tier, diagram kind, or an explicit route name; charges follow the provider's cost model
+        let route_name = context.get("llm_route").and_then(|v| v.as_str()).map(str::to_string);
+        let route_key = RouteKey { tier: &tier, kind, route: route_name.as_deref() };
 
         if matches!(user_session.tier, UserTier::Free) && !matches!(chat_input.input_type, InputType::Text) {
             let ai_response = AiResponse {
                 status: AiStatus::Failure,
                 message: Some("Feature unavailable: upgrade to Pro for image/link/video inputs".to_string()),
                 graph_data: None,
-                credits_cost,
+                credits_cost: 0,
+                diagnostics: Vec::new(),
+                llm_retries: Vec::new(),
             };
             return Ok(json!(ai_response));
         }
 
+        // Mermaid/DOT sources are converted deterministically: no LLM call, no credits
+        let imported = match kind {
+            "mermaid" => parse_mermaid_flowchart(&chat_input.content).map(|g| ("Mermaid flowchart", g)),
+            "dot" => parse_dot(&chat_input.content).map(|g| ("DOT graph", g)),
+            _ => None,
+        };
+        if let Some((source, graph_data)) = imported {
+            let ai_response = AiResponse {
+                status: AiStatus::Success,
+                message: Some(format!("Imported {} ({} nodes, {} edges)", source, graph_data.nodes.len(), graph_data.edges.len())),
+                graph_data: Some(graph_data),
+                credits_cost: 0,
+                diagnostics: Vec::new(),
+                llm_retries: Vec::new(),
+            };
+            return Ok(json!(ai_response));
+        }
+
+        // Only the LLM path needs the cassette and the route
+        let cassette = cassette_config(context).map_err(|e| anyhow::anyhow!(e))?;
+        let routed = match &cassette {
+            Some(c) => Routed::resolve_recorded(&route_key, c),
+            None => Routed::resolve(&route_key),
+        };
+        // With a cassette, failed or unrecorded calls surface instead of falling back to heuristics
+        let recorded = cassette.is_some();
+        let cost = routed.as_ref().map(|r| r.provider.cost_model()).unwrap_or_default();
+        let stream = context.get("llm_stream").and_then(|v| v.as_bool()).unwrap_or(false);
+        let credits_cost = cost.credits_per_call;
+        if let Err(RouteError::Invalid(e)) = &routed {
+            return Err(anyhow::anyhow!("LLM configuration error: {}", e));
+        }
+
         if user_session.credits_remaining < credits_cost {
             let ai_response = AiResponse {
                 status: AiStatus::Failure,
                 message: Some("Insufficient credits".to_string()),
                 graph_data: None,
                 credits_cost,
+                diagnostics: Vec::new(),
+                llm_retries: Vec::new(),
             };
             return Ok(json!(ai_response));
         }
 
-        // Ask the LLM to output ONLY valid JSON matching our GraphData schema.
-        let (kind, default_dir) = infer_diagram_kind(&chat_input.content);
-        let mut prompt = r##"
-You are the Logic Engine of a two-stage diagram system. Focus ONLY on logic & structure. Output JSON ONLY (no prose, no markdown).
-
-CONSTRAINTS
-- Do NOT include SVG, images, or styling.
-- Prefer DAGs unless cycles are explicit and labeled.
-- Avoid ambiguity; design a balanced, readable structure.
-
-WHEN TO INFER
-- If not provided, infer the suitable diagram family.
-- Fill minimal missing connections only when clearly implied.
-
-GRAPHFLOW SCHEMA (exact keys)
-{
-  "nodes": [{"id":"string_snake_case","label":"string","x":0,"y":0,"style":{"shape":"rect","color":"#F3F4F6"}}],
-  "edges": [{"id":"string_snake_case","source":"node_id","target":"node_id","label":"","style":{"line":"orthogonal","arrow":"end"}}],
-  "layout_hints": {"direction":"LR"|"TB","algorithm":"longest_path"},
-  "global_style": {"font":"Inter","background":"#FFFFFF","theme":"minimal"},
-  "decorations": null | [{
-     "type": "icon"|"note",
-     "target": "node_id_or_edge_id"|null,
-     "builtin": "database"|"model"|"search"|"email"|"salesperson"|null,
-     "url": "",
-     "size": {"w":number,"h":number}|null,
-     "offset": {"dx":number,"dy":number}|null,
-     "text": ""|null
-  }],
-  "containers": null | [{"id":"string_snake_case","label":"string","children":["node_id"],"style":{"bg":"#FFFFFF","border":"#D1D5DB","radius":12,"label_tag":"string"}}]
-}
-
-RULES
-- IDs unique, snake_case; no dangling edges; no duplicate edges.
-- Containers reference existing nodes only; limit decorations ≤ 3.
-- Decisions use edge labels; only add gateway nodes when required.
-
-DIAGRAM GUIDANCE
-- Kind: KINDSLOT. If "auto", choose among flow, system, sequence, mindmap.
-- Layout: set layout_hints.direction to "DIRSLOT" unless readability is better otherwise; algorithm "longest_path".
-- Flowchart: clear start/end, labeled branches, balanced symmetry.
-- System: group components in meaningful containers; orthogonal connectors.
-- Sequence: actors left→right; messages as labeled edges; consider TB if clearer.
-- Mindmap: central topic with branches; avoid cycles.
-
-DECORATIONS (icons by the model)
-- Only add when they materially improve comprehension (max 3).
-- Prefer built-in icon names matching the assets dir: database, model, search, email, salesperson.
-- Use one of:
-  - builtin: "<name>"
-  - url: "builtin:<name>"
-- Place relative to target center with small offset to corners (e.g., dx:-24, dy:-24) and size 16–24.
-- If no target is provided, you may use absolute at_x/at_y placement.
-
-OUTPUT
-Return ONE valid JSON object matching the schema above. No extra keys, no comments.
-
-USER_INPUT
-{content}
-"##.to_string();
-        prompt = prompt.replace("KINDSLOT", kind).replace("DIRSLOT", default_dir).replace("{content}", &chat_input.content);
+        let prompt = generation_prompt(&chat_input.content);
 
-        // Try LLM; if it fails (e.g., missing API keys), fallback to deterministic heuristic
-        let ai_response_str = match call_llm_ai_model(&prompt, &tier).await {
+        // Try LLM; without an API key or endpoint, fall back to the deterministic heuristic for free
+        let ai_response_str = match ask(&routed, &prompt, stream).await {
             Ok(s) => s,
-            Err(_e) => {
+            Err(e) if recorded || routed.is_ok() => return Err(anyhow::anyhow!(e)),
+            Err(_) => {
                 // Heuristic fallback: build a minimal GraphData from the raw user content
                 let mut nodes: std::collections::BTreeMap<String, crate::state::NodeData> = std::collections::BTreeMap::new();
                 let mut edges: Vec<crate::state::EdgeData> = Vec::new();
@@ -497,30 +572,61 @@ USER_INPUT
                     }
                 }
 
-                let graph_data = GraphData {
+                let mut graph_data = GraphData {
                     nodes: nodes.into_values().collect(),
                     edges,
-                    layout_hints: Some(crate::state::LayoutHints { direction: default_dir.to_string(), algorithm: "longest_path".to_string() }),
+                    layout_hints: Some(crate::state::LayoutHints { direction: default_dir.to_string(), algorithm: default_algorithm(kind).to_string(), ..Default::default() }),
                     global_style: Some(crate::state::GlobalStyle { font: "Inter".to_string(), background: "#ffffff".to_string(), theme: Some("minimal".to_string()) }),
                     decorations: None,
                     containers: None,
+                    sequence: None,
                 };
+                apply_kind_extensions(kind, &mut graph_data);
 
                 let ai_response = AiResponse {
                     status: AiStatus::Success,
                     message: Some("ok".to_string()),
                     graph_data: Some(graph_data),
-                    credits_cost,
+                    credits_cost: 0,
+                    diagnostics: Vec::new(),
+                    llm_retries: Vec::new(),
                 };
 
                 return Ok(json!(ai_response));
             }
         };
 
-        // Try to parse strict JSON GraphData from the LLM.
-        let graph_data: GraphData = match serde_json::from_str(&ai_response_str) {
-            Ok(gd) => gd,
-            Err(_e) => {
+        // Parse GraphData from the LLM reply, tolerating fences, prose and near-JSON. Replies that
+        // still fail (or break the structural rules) go back to the model with the problems listed,
+        // up to `llm_max_retries` times, as long as the user can pay for the extra calls.
+        let max_retries = context.get("llm_max_retries").and_then(|v| v.as_u64()).map(|n| n as u32).unwrap_or(DEFAULT_LLM_RETRIES);
+        let mut reply = ai_response_str;
+        let mut llm_retries: Vec<LlmRetry> = Vec::new();
+        let parsed = loop {
+            let (graph, problems) = check_reply(&reply);
+            let charged = llm_retries.iter().filter(|r| r.error.is_none()).count() as u32;
+            let affordable = user_session.credits_remaining >= credits_cost + cost.credits_per_retry * (charged + 1);
+            if problems.is_empty() || llm_retries.len() as u32 >= max_retries || !affordable {
+                break graph;
+            }
+            let attempt = llm_retries.len() as u32 + 1;
+            match ask(&routed, &correction_prompt(&prompt, &reply, &problems), stream).await {
+                Ok(next) => {
+                    llm_retries.push(LlmRetry { attempt, problems, error: None });
+                    reply = next;
+                }
+                Err(e) if recorded => return Err(anyhow::anyhow!(e)),
+                Err(e) => {
+                    llm_retries.push(LlmRetry { attempt, problems, error: Some(e) });
+                    break graph;
+                }
+            }
+        };
+        let credits_cost = credits_cost + cost.credits_per_retry * llm_retries.iter().filter(|r| r.error.is_none()).count() as u32;
+
+        let mut graph_data: GraphData = match parsed {
+            Some(gd) => gd,
+            None => {
                 // Fallback: heuristic edge-list parser (A -> B -> C, commas separate statements)
                 let mut nodes: std::collections::BTreeMap<String, crate::state::NodeData> = std::collections::BTreeMap::new();
                 let mut edges: Vec<crate::state::EdgeData> = Vec::new();
@@ -551,19 +657,35 @@ USER_INPUT
                 GraphData {
                     nodes: nodes.into_values().collect(),
                     edges,
-                    layout_hints: Some(crate::state::LayoutHints { direction: "TB".to_string(), algorithm: "dagre".to_string() }),
+                    layout_hints: Some(crate::state::LayoutHints { direction: "TB".to_string(), algorithm: "dagre".to_string(), ..Default::default() }),
                     global_style: Some(crate::state::GlobalStyle { font: "Inter".to_string(), background: "#ffffff".to_string(), theme: Some("minimal".to_string()) }),
                     decorations: None,
                     containers: None,
+                    sequence: None,
                 }
             }
         };
+        let hints = graph_data.layout_hints.get_or_insert_with(|| crate::state::LayoutHints { direction: default_dir.to_string(), ..Default::default() });
+        if engine_for(&hints.algorithm).is_none() {
+            hints.algorithm = default_algorithm(kind).to_string();
+        }
+        apply_kind_extensions(kind, &mut graph_data);
+
+        // Check the model's output against the prompt's structural rules; fix it when asked to
+        let repair_graph = context.get("repair_graph").and_then(|v| v.as_bool()).unwrap_or(false);
+        let diagnostics = if repair_graph { repair(&mut graph_data) } else { validate(&graph_data) };
+        let message = match summarize(&diagnostics) {
+            Some(summary) => format!("ok; {}", summary),
+            None => "ok".to_string(),
+        };
 
         let ai_response = AiResponse {
             status: AiStatus::Success,
-            message: Some("ok".to_string()),
+            message: Some(message),
             graph_data: Some(graph_data),
             credits_cost,
+            diagnostics,
+            llm_retries,
         };
 
         Ok(json!(ai_response))
@@ -631,14 +753,27 @@ impl Node for GraphRenderingNode {
             Ok(value) => {
                 // Assuming `rendered_graph_data` is the actual graph data
                 if let Some(rendered_graph_data_value) = value.get("rendered_graph_data") {
-                    let mut gd: GraphData = serde_json::from_value(rendered_graph_data_value.clone())
+                    let gd: GraphData = serde_json::from_value(rendered_graph_data_value.clone())
                         .map_err(|e| anyhow::anyhow!("Failed to deserialize GraphData: {}", e))?;
-                    // Auto-layout to avoid overlaps, ignoring LLM-provided coordinates
-                    let (mut dir, node_gap, rank_gap) = ("LR".to_string(), 180.0, 140.0);
+                    // Auto-layout with the hinted engine (only "preserve" keeps LLM-provided coordinates)
+                    let (mut dir, rank_sep, node_sep) = ("LR".to_string(), 180.0, 140.0);
                     if let Some(h) = gd.layout_hints.as_ref() {
                         if !h.direction.is_empty() { dir = h.direction.to_uppercase(); }
                     }
-                    apply_auto_layout(&mut gd, node_gap, rank_gap, &dir, 4);
+                    let gd = auto_layout_blocking(gd, rank_sep, node_sep, dir, 4).await?;
+                    // Text/diagram exports of the laid-out graph
+                    let export_path = |key: &str| context.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+                    if let Some(path) = export_path("export_mermaid_path") {
+                        write_export(&path, "Mermaid flowchart", graphdata_to_mermaid(&gd));
+                    }
+                    if let Some(path) = export_path("export_dot_path") {
+                        write_export(&path, "DOT graph", graphdata_to_dot(&gd));
+                    }
+                    if let Some(path) = export_path("export_drawio_path") {
+                        let allow_images = context.get("allow_images").and_then(|v| v.as_bool()).unwrap_or(false);
+                        let assets_dir = context.get("assets_dir").and_then(|v| v.as_str()).unwrap_or("");
+                        write_export(&path, "draw.io file", graphdata_to_drawio_with_opts(&gd, allow_images, assets_dir));
+                    }
                     // Write Excalidraw scene if requested
                     if let Some(path_val) = context.get("export_excalidraw_path").cloned() {
                         if let Ok(opt_path) = serde_json::from_value::<Option<String>>(path_val) {
@@ -646,7 +781,7 @@ impl Node for GraphRenderingNode {
                                 // read options
                                 let allow_images = context.get("allow_images").and_then(|v| v.as_bool()).unwrap_or(false);
                                 let assets_dir = context.get("assets_dir").and_then(|v| v.as_str()).unwrap_or("");
-                                let scene = crate::excalidraw::graphdata_to_excalidraw_scene_with_opts(&gd, allow_images, assets_dir);
+                                let scene = excalidraw_scene_blocking(gd.clone(), allow_images, assets_dir.to_string()).await?;
                                 let scene_str = serde_json::to_string_pretty(&scene).unwrap_or_else(|_| scene.to_string());
                                 if let Err(e) = fs::write(&path, scene_str) {
                                     eprintln!("Failed to write Excalidraw scene to {}: {}", path, e);
@@ -659,30 +794,21 @@ impl Node for GraphRenderingNode {
                                     if let Err(e) = fs::create_dir_all(&out_dir_abs) { eprintln!("Failed to ensure docs/screens: {}", e); }
                                     let out_png_abs = out_dir_abs.join(format!("{}.png", suggested));
                                     let out_svg_abs = out_dir_abs.join(format!("{}.svg", suggested));
-                                    let render_script_abs = project_root.join("tools/render-excalidraw/render.js");
                                     // Canonicalize scene path if possible
                                     let scene_abs = Path::new(&path).canonicalize().unwrap_or_else(|_| Path::new(&path).to_path_buf());
-                                    let status = Command::new("node")
-                                        .arg(render_script_abs)
-                                        .arg(&scene_abs)
-                                        .arg(&out_png_abs)
-                                        .status();
-                                    match status {
-                                        Ok(s) if s.success() => eprintln!("Rendered PNG -> {}", out_png_abs.display()),
-                                        Ok(s) => eprintln!("Renderer exited with status {}", s),
-                                        Err(e) => eprintln!("Failed to run renderer: {}", e),
+                                    let backend = RenderBackend::from_env();
+                                    let raster = RasterOptions::new(
+                                        context.get("png_scale").and_then(|v| v.as_f64()).map(|v| v as f32),
+                                        None,
+                                        context.get("png_background").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                                    );
+                                    match render_scene(&scene, &scene_abs, &out_png_abs, backend, &raster) {
+                                        Ok(()) => eprintln!("Rendered PNG -> {}", out_png_abs.display()),
+                                        Err(e) => eprintln!("PNG render failed: {}", e),
                                     }
-                                    // Render SVG
-                                    let render_script_abs = project_root.join("tools/render-excalidraw/render.js");
-                                    let status_svg = Command::new("node")
-                                        .arg(render_script_abs)
-                                        .arg(&scene_abs)
-                                        .arg(&out_svg_abs)
-                                        .status();
-                                    match status_svg {
-                                        Ok(s) if s.success() => eprintln!("Rendered SVG -> {}", out_svg_abs.display()),
-                                        Ok(s) => eprintln!("Renderer (SVG) exited with status {}", s),
-                                        Err(e) => eprintln!("Failed to run renderer for SVG: {}", e),
+                                    match render_scene(&scene, &scene_abs, &out_svg_abs, backend, &raster) {
+                                        Ok(()) => eprintln!("Rendered SVG -> {}", out_svg_abs.display()),
+                                        Err(e) => eprintln!("SVG render failed: {}", e),
                                     }
                                 }
                             }
@@ -925,12 +1051,15 @@ impl Node for UserFeedbackNode {
                 // shared_state.user_feedback_status = value.get("feedback_status").map(|s| s.to_string());
                 shared_state.ai_response.status = AiStatus::Success;
                 context.set("shared_state", json!(shared_state.clone()));
+                // Every path ends here; `Flow::run` returns the context's "result" entry
+                context.set("result", json!(shared_state.clone()));
                 Ok(ProcessResult::new(shared_state.clone(), shared_state.to_condition()))
             },
             Err(e) => {
                 shared_state.ai_response.status = AiStatus::Failure;
                 shared_state.ai_response.message = Some(format!("Feedback collection error: {}", e));
                 context.set("shared_state", json!(shared_state.clone()));
+                context.set("result", json!(shared_state.clone()));
                 Ok(ProcessResult::new(shared_state.clone(), shared_state.to_condition()))
             },
         }
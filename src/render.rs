@@ -0,0 +1,126 @@
+use resvg::{tiny_skia, usvg};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::sync::{Arc, OnceLock};
+
+use crate::svg::scene_to_svg;
+
+/// Which renderer turns an Excalidraw scene into image artifacts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderBackend {
+    /// In-process Rust renderer (default, no external tools)
+    Native,
+    /// tools/render-excalidraw/render.js via Node + Puppeteer (exact Excalidraw styling)
+    Node,
+}
+
+impl RenderBackend {
+    /// Read `GRAPHFLOW_RENDERER` ("native" | "node"); defaults to native.
+    pub fn from_env() -> Self {
+        match std::env::var("GRAPHFLOW_RENDERER").map(|v| v.to_lowercase()).as_deref() {
+            Ok("node") => RenderBackend::Node,
+            _ => RenderBackend::Native,
+        }
+    }
+}
+
+/// Options for PNG rasterization.
+#[derive(Debug, Clone)]
+pub struct RasterOptions {
+    /// Pixel multiplier over the scene's CSS pixels (1.0 = 96 DPI)
+    pub scale: f32,
+    /// Overrides the scene's `appState.viewBackgroundColor`; "transparent" drops the background
+    pub background: Option<String>,
+}
+
+impl Default for RasterOptions {
+    fn default() -> Self {
+        RasterOptions { scale: 1.0, background: None }
+    }
+}
+
+impl RasterOptions {
+    /// Build options from an optional scale and/or DPI; DPI wins when both are given.
+    pub fn new(scale: Option<f32>, dpi: Option<f32>, background: Option<String>) -> Self {
+        let scale = dpi.map(|d| d / 96.0).or(scale).unwrap_or(1.0);
+        let scale = if scale.is_finite() && scale > 0.0 { scale.min(8.0) } else { 1.0 };
+        RasterOptions { scale, background }
+    }
+}
+
+// System fonts are loaded once per process; scanning them is slow
+fn font_db() -> Arc<usvg::fontdb::Database> {
+    static DB: OnceLock<Arc<usvg::fontdb::Database>> = OnceLock::new();
+    DB.get_or_init(|| {
+        let mut db = usvg::fontdb::Database::new();
+        db.load_system_fonts();
+        // Map the generic families used by svg.rs onto fonts that are actually installed
+        let installed: Vec<String> = db.faces().filter_map(|f| f.families.first().map(|(name, _)| name.clone())).collect();
+        let pick = |prefs: &[&str]| prefs.iter().find(|p| installed.iter().any(|i| i == *p)).map(|p| p.to_string()).or_else(|| installed.first().cloned());
+        if let Some(sans) = pick(&["Helvetica", "Arial", "Liberation Sans", "DejaVu Sans", "Noto Sans"]) {
+            db.set_sans_serif_family(sans.clone());
+            db.set_cursive_family(sans);
+        }
+        if let Some(mono) = pick(&["Cascadia Code", "Consolas", "Liberation Mono", "DejaVu Sans Mono", "Noto Sans Mono"]) {
+            db.set_monospace_family(mono);
+        }
+        Arc::new(db)
+    })
+    .clone()
+}
+
+/// Rasterize an Excalidraw scene to PNG bytes without a browser.
+pub fn scene_to_png(scene: &Value, opts: &RasterOptions) -> Result<Vec<u8>, String> {
+    let svg = if let Some(bg) = &opts.background {
+        let mut scene = scene.clone();
+        if let Some(obj) = scene.as_object_mut() {
+            let app_state = obj.entry("appState").or_insert_with(|| serde_json::json!({}));
+            if let Some(app_state) = app_state.as_object_mut() {
+                app_state.insert("viewBackgroundColor".to_string(), Value::String(bg.clone()));
+            }
+        }
+        scene_to_svg(&scene)
+    } else {
+        scene_to_svg(scene)
+    };
+
+    let usvg_opts = usvg::Options {
+        font_family: "sans-serif".to_string(),
+        fontdb: font_db(),
+        ..usvg::Options::default()
+    };
+    let tree = usvg::Tree::from_str(&svg, &usvg_opts).map_err(|e| format!("SVG parse error: {}", e))?;
+    let size = tree.size().to_int_size().scale_by(opts.scale).ok_or("Invalid raster size")?;
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height()).ok_or("Failed to allocate pixmap")?;
+    resvg::render(&tree, tiny_skia::Transform::from_scale(opts.scale, opts.scale), &mut pixmap.as_mut());
+    pixmap.encode_png().map_err(|e| format!("PNG encode error: {}", e))
+}
+
+fn render_with_node(scene_path: &Path, out_path: &Path) -> Result<(), String> {
+    let project_root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let render_script = project_root.join("tools/render-excalidraw/render.js");
+    let status = Command::new("node")
+        .arg(render_script)
+        .arg(scene_path)
+        .arg(out_path)
+        .status()
+        .map_err(|e| format!("Failed to run renderer: {}", e))?;
+    if status.success() { Ok(()) } else { Err(format!("Renderer exited with status {}", status)) }
+}
+
+/// Render `scene` to `out_path`; the format is picked from the extension (.svg or .png).
+/// `scene_path` must point at the same scene on disk, it is only read by the Node backend.
+pub fn render_scene(scene: &Value, scene_path: &Path, out_path: &Path, backend: RenderBackend, opts: &RasterOptions) -> Result<(), String> {
+    let is_svg = out_path
+        .extension()
+        .map(|e| e.to_string_lossy().eq_ignore_ascii_case("svg"))
+        .unwrap_or(false);
+    let write_err = |e: std::io::Error| format!("Failed to write {}: {}", out_path.display(), e);
+    match backend {
+        RenderBackend::Native if is_svg => fs::write(out_path, scene_to_svg(scene)).map_err(write_err),
+        RenderBackend::Native => fs::write(out_path, scene_to_png(scene, opts)?).map_err(write_err),
+        RenderBackend::Node => render_with_node(scene_path, out_path),
+    }
+}
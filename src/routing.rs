@@ -0,0 +1,438 @@
+use crate::edge_style::{EdgeLook, Routing};
+use crate::shapes::{node_box, outline_point, Shape};
+use crate::state::GraphData;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+// Orthogonal edge routing. Node boxes (and containers an edge does not enter) are obstacles;
+// paths run on a sparse grid made of lines at a clearance around every obstacle plus the port
+// stubs, and A* picks the cheapest path where each bend costs BEND_COST. Ports sit on the
+// sides facing the layout direction and are spread along a side shared by several edges, and
+// grid segments already taken by another edge cost extra so parallel edges use separate lanes.
+// Each search stays in a window around its endpoints, widened only when no path fits, and
+// graphs whose grid would be too large keep the L-shaped fallback.
+
+/// Clearance between a path and the obstacles it runs around.
+const MARGIN: f64 = 16.0;
+/// Cost of one bend, in pixels of path length.
+const BEND_COST: f64 = 120.0;
+/// Extra cost per pixel of a segment already used by another edge.
+const SHARED_COST: f64 = 1.0;
+/// Share of a side that spread ports may use.
+const PORT_SPREAD: f64 = 0.7;
+/// Padding between a container and its children (the Excalidraw emitter draws the same boxes).
+pub(crate) const CONTAINER_PADDING: f64 = 40.0;
+/// Margin around an edge's endpoints that its first search may use; retries widen it fourfold.
+const WINDOW_MARGIN: f64 = 240.0;
+/// Grid points one search may visit; an edge that needs a larger window gets an L-shape.
+const MAX_WINDOW_POINTS: usize = 250_000;
+/// Grids with more points than this are not routed at all.
+const MAX_GRID_POINTS: usize = 1_500_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl Side {
+    fn normal(self) -> (f64, f64) {
+        match self {
+            Side::Top => (0.0, -1.0),
+            Side::Bottom => (0.0, 1.0),
+            Side::Left => (-1.0, 0.0),
+            Side::Right => (1.0, 0.0),
+        }
+    }
+
+    // Index into the router's four headings: +x, -x, +y, -y
+    fn heading(self) -> usize {
+        match self {
+            Side::Right => 0,
+            Side::Left => 1,
+            Side::Bottom => 2,
+            Side::Top => 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Rect {
+    x0: f64,
+    y0: f64,
+    x1: f64,
+    y1: f64,
+}
+
+impl Rect {
+    fn around(cx: f64, cy: f64, w: f64, h: f64) -> Rect {
+        Rect { x0: cx - w / 2.0, y0: cy - h / 2.0, x1: cx + w / 2.0, y1: cy + h / 2.0 }
+    }
+
+    fn inflate(self, m: f64) -> Rect {
+        Rect { x0: self.x0 - m, y0: self.y0 - m, x1: self.x1 + m, y1: self.y1 + m }
+    }
+
+    // Strictly inside: running along the border is allowed
+    fn blocks(&self, x: f64, y: f64) -> bool {
+        x > self.x0 + 0.5 && x < self.x1 - 0.5 && y > self.y0 + 0.5 && y < self.y1 - 0.5
+    }
+
+    fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x0 && x <= self.x1 && y >= self.y0 && y <= self.y1
+    }
+}
+
+/// Container boxes as drawn by the emitter (children's bounding box plus padding).
+pub(crate) fn container_boxes(g: &GraphData) -> Vec<Option<(f64, f64, f64, f64)>> {
+    g.containers
+        .iter()
+        .flatten()
+        .map(|c| {
+            let (mut x0, mut y0, mut x1, mut y1) = (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+            for n in c.children.iter().filter_map(|id| g.nodes.iter().find(|n| &n.id == id)) {
+                let (w, h) = node_box(n);
+                x0 = x0.min(n.x as f64 - w / 2.0);
+                y0 = y0.min(n.y as f64 - h / 2.0);
+                x1 = x1.max(n.x as f64 + w / 2.0);
+                y1 = y1.max(n.y as f64 + h / 2.0);
+            }
+            x0.is_finite().then_some((x0 - CONTAINER_PADDING, y0 - CONTAINER_PADDING, x1 + CONTAINER_PADDING, y1 + CONTAINER_PADDING))
+        })
+        .collect()
+}
+
+/// Sides an edge leaves its source and enters its target. The layout direction decides which
+/// axis is tried first: in a top-to-bottom layout, nodes in different ranks connect bottom to
+/// top, and nodes in the same rank connect side by side.
+pub fn port_sides(dir: &str, s: (f64, f64), s_size: (f64, f64), t: (f64, f64), t_size: (f64, f64)) -> (Side, Side) {
+    let vertical = matches!(dir.to_uppercase().as_str(), "TB" | "TD" | "BT");
+    let (dx, dy) = (t.0 - s.0, t.1 - s.1);
+    let apart_y = dy.abs() > (s_size.1 + t_size.1) / 2.0;
+    let apart_x = dx.abs() > (s_size.0 + t_size.0) / 2.0;
+    let by_y = if dy > 0.0 { (Side::Bottom, Side::Top) } else { (Side::Top, Side::Bottom) };
+    let by_x = if dx >= 0.0 { (Side::Right, Side::Left) } else { (Side::Left, Side::Right) };
+    if dx == 0.0 && dy == 0.0 {
+        // self-loop around the top-right corner
+        (Side::Right, Side::Top)
+    } else if vertical {
+        if apart_y || !apart_x { by_y } else { by_x }
+    } else if apart_x || !apart_y {
+        by_x
+    } else {
+        by_y
+    }
+}
+
+struct Grid {
+    xs: Vec<f64>,
+    ys: Vec<f64>,
+    // Free of node obstacles: grid points, segments to the right neighbor, segments to the one below
+    free_pt: Vec<bool>,
+    free_h: Vec<bool>,
+    free_v: Vec<bool>,
+}
+
+impl Grid {
+    // Sorted, deduplicated grid lines; None when the grid would exceed MAX_GRID_POINTS
+    fn lines(mut xs: Vec<f64>, mut ys: Vec<f64>) -> Option<(Vec<f64>, Vec<f64>)> {
+        for v in [&mut xs, &mut ys] {
+            v.sort_by(f64::total_cmp);
+            v.dedup_by(|a, b| (*a - *b).abs() < 0.5);
+        }
+        (xs.len() * ys.len() <= MAX_GRID_POINTS).then_some((xs, ys))
+    }
+
+    fn new(xs: Vec<f64>, ys: Vec<f64>, obstacles: &[Rect]) -> Grid {
+        let (nx, ny) = (xs.len(), ys.len());
+        let mid = |v: &[f64]| v.windows(2).map(|w| (w[0] + w[1]) / 2.0).collect::<Vec<f64>>();
+        let (mx, my) = (mid(&xs), mid(&ys));
+        // Indices of the sorted coordinates strictly inside (lo, hi), as `Rect::blocks` tests them
+        let inside = |v: &[f64], lo: f64, hi: f64| v.partition_point(|&c| c <= lo + 0.5)..v.partition_point(|&c| c < hi - 0.5);
+        let mut free_pt = vec![true; nx * ny];
+        let mut free_h = vec![true; nx * ny];
+        let mut free_v = vec![true; nx * ny];
+        // Mark only the cells each obstacle covers
+        for r in obstacles {
+            for (flags, cols, rows) in [(&mut free_pt, &xs, &ys), (&mut free_h, &mx, &ys), (&mut free_v, &xs, &my)] {
+                for iy in inside(rows, r.y0, r.y1) {
+                    for ix in inside(cols, r.x0, r.x1) {
+                        flags[iy * nx + ix] = false;
+                    }
+                }
+            }
+        }
+        // No segments leave the last column or row
+        for iy in 0..ny { free_h[iy * nx + nx - 1] = false; }
+        for ix in 0..nx { free_v[(ny - 1) * nx + ix] = false; }
+        Grid { xs, ys, free_pt, free_h, free_v }
+    }
+
+    // Grid line at coordinate `c` in sorted `v`
+    fn line(v: &[f64], c: f64) -> Option<usize> {
+        let i = v.partition_point(|&x| x < c - 0.5);
+        (i < v.len() && (v[i] - c).abs() < 0.5).then_some(i)
+    }
+
+    fn index(&self, (x, y): (f64, f64)) -> Option<usize> {
+        Some(Grid::line(&self.ys, y)? * self.xs.len() + Grid::line(&self.xs, x)?)
+    }
+
+    // Columns and rows within `margin` of the box spanned by points `a` and `b`
+    fn window(&self, a: usize, b: usize, margin: f64) -> Window {
+        let ((ax, ay), (bx, by)) = (self.point(a), self.point(b));
+        let span = |v: &[f64], lo: f64, hi: f64| (v.partition_point(|&c| c < lo - margin), v.partition_point(|&c| c <= hi + margin));
+        let (ix0, ix1) = span(&self.xs, ax.min(bx), ax.max(bx));
+        let (iy0, iy1) = span(&self.ys, ay.min(by), ay.max(by));
+        Window { nx: self.xs.len(), ix0, iy0, w: ix1 - ix0, h: iy1 - iy0 }
+    }
+
+    // Slot of the unit segment between neighboring points a and b: horizontal at 2k, vertical at 2k + 1
+    fn segment(&self, a: usize, b: usize) -> usize {
+        let k = a.min(b);
+        if a.abs_diff(b) == self.xs.len() { 2 * k + 1 } else { 2 * k }
+    }
+
+    fn point(&self, k: usize) -> (f64, f64) {
+        (self.xs[k % self.xs.len()], self.ys[k / self.xs.len()])
+    }
+
+    // Neighbor of grid point k along heading d (+x, -x, +y, -y) when the segment is free
+    fn step(&self, k: usize, d: usize) -> Option<usize> {
+        let nx = self.xs.len();
+        let (ix, iy) = (k % nx, k / nx);
+        match d {
+            0 if ix + 1 < nx && self.free_h[k] => Some(k + 1),
+            1 if ix > 0 && self.free_h[k - 1] => Some(k - 1),
+            2 if iy + 1 < self.ys.len() && self.free_v[k] => Some(k + nx),
+            3 if iy > 0 && self.free_v[k - nx] => Some(k - nx),
+            _ => None,
+        }
+    }
+}
+
+// A rectangle of grid points a search may visit, with its own compact numbering
+struct Window {
+    nx: usize,
+    ix0: usize,
+    iy0: usize,
+    w: usize,
+    h: usize,
+}
+
+impl Window {
+    fn whole(grid: &Grid) -> Window {
+        Window { nx: grid.xs.len(), ix0: 0, iy0: 0, w: grid.xs.len(), h: grid.ys.len() }
+    }
+
+    fn covers(&self, other: &Window) -> bool {
+        self.ix0 <= other.ix0 && self.iy0 <= other.iy0 && self.ix0 + self.w >= other.ix0 + other.w && self.iy0 + self.h >= other.iy0 + other.h
+    }
+
+    // Window-local number of grid point k, if the window holds it
+    fn local(&self, k: usize) -> Option<usize> {
+        let (ix, iy) = (k % self.nx, k / self.nx);
+        let inside = ix >= self.ix0 && ix < self.ix0 + self.w && iy >= self.iy0 && iy < self.iy0 + self.h;
+        inside.then(|| (iy - self.iy0) * self.w + (ix - self.ix0))
+    }
+}
+
+fn opposite(d: usize) -> usize {
+    d ^ 1
+}
+
+/// Cheapest path on the grid from `from` (leaving along heading `d0`) to `to` (arriving along
+/// heading `d1`) through points of `window`, avoiding `extra` obstacles on top of the grid's own.
+fn shortest_path(grid: &Grid, window: &Window, (from, d0): (usize, usize), (to, d1): (usize, usize), extra: &[Rect], used: &[bool]) -> Option<Vec<usize>> {
+    let free = |k: usize| {
+        let (x, y) = grid.point(k);
+        grid.free_pt[k] && !extra.iter().any(|r| r.blocks(x, y))
+    };
+    let seg_free = |a: usize, b: usize| {
+        let ((ax, ay), (bx, by)) = (grid.point(a), grid.point(b));
+        !extra.iter().any(|r| r.blocks((ax + bx) / 2.0, (ay + by) / 2.0))
+    };
+    // States are (grid point, heading); `dist` and `prev` are indexed by window-local state
+    let states = window.w * window.h * 4;
+    let slot = |s: usize| window.local(s / 4).map(|l| l * 4 + s % 4);
+    let mut dist = vec![f64::INFINITY; states];
+    let mut prev = vec![usize::MAX; states];
+    // A*: the Manhattan distance left, plus one bend unless the target lies straight ahead and is
+    // entered along `d1`, never overestimates, so the first path found is cheapest
+    let target = grid.point(to);
+    let rest = |k: usize, d: usize| {
+        let (x, y) = grid.point(k);
+        let (dx, dy) = (target.0 - x, target.1 - y);
+        let ahead = match d {
+            0 => dy.abs() < 0.5 && dx > -0.5,
+            1 => dy.abs() < 0.5 && dx < 0.5,
+            2 => dx.abs() < 0.5 && dy > -0.5,
+            _ => dx.abs() < 0.5 && dy < 0.5,
+        };
+        dx.abs() + dy.abs() + if ahead && d == d1 { 0.0 } else { BEND_COST }
+    };
+    let mut heap = BinaryHeap::new();
+    let start = from * 4 + d0;
+    dist[slot(start)?] = 0.0;
+    heap.push(Reverse(((rest(from, d0) * 100.0) as u64, start)));
+    let mut best: Option<(f64, usize)> = None;
+    while let Some(Reverse((key, s))) = heap.pop() {
+        let (k, d) = (s / 4, s % 4);
+        let cost = dist[slot(s)?];
+        if key > ((cost + rest(k, d)) * 100.0) as u64 { continue; }
+        if best.map(|(b, _)| cost + rest(k, d) >= b).unwrap_or(false) { break; }
+        if k == to {
+            let total = cost + if d == d1 { 0.0 } else { BEND_COST };
+            if best.map(|(b, _)| total < b).unwrap_or(true) { best = Some((total, s)); }
+        }
+        for d2 in 0..4 {
+            if d2 == opposite(d) { continue; }
+            let Some(q) = grid.step(k, d2) else { continue };
+            let Some(ns_slot) = slot(q * 4 + d2) else { continue };
+            if !free(q) && q != to { continue; }
+            if !seg_free(k, q) { continue; }
+            let ((ax, ay), (bx, by)) = (grid.point(k), grid.point(q));
+            let len = (bx - ax).abs() + (by - ay).abs();
+            let shared = if used[grid.segment(k, q)] { len * SHARED_COST } else { 0.0 };
+            let next = cost + len + shared + if d2 == d { 0.0 } else { BEND_COST };
+            let ns = q * 4 + d2;
+            if next + 1e-9 < dist[ns_slot] {
+                dist[ns_slot] = next;
+                prev[ns_slot] = s;
+                heap.push(Reverse((((next + rest(q, d2)) * 100.0) as u64, ns)));
+            }
+        }
+    }
+    let (_, mut s) = best?;
+    let mut path = vec![s / 4];
+    while prev[slot(s)?] != usize::MAX {
+        s = prev[slot(s)?];
+        if path.last() != Some(&(s / 4)) { path.push(s / 4); }
+    }
+    path.reverse();
+    Some(path)
+}
+
+// Drop repeated and collinear interior points
+fn simplify(points: Vec<(f64, f64)>) -> Vec<(f64, f64)> {
+    let mut out: Vec<(f64, f64)> = Vec::new();
+    for p in points {
+        if out.last().map(|q| (q.0 - p.0).abs() < 0.01 && (q.1 - p.1).abs() < 0.01).unwrap_or(false) { continue; }
+        if out.len() >= 2 {
+            let (a, b) = (out[out.len() - 2], out[out.len() - 1]);
+            let collinear = ((a.0 - b.0).abs() < 0.01 && (b.0 - p.0).abs() < 0.01) || ((a.1 - b.1).abs() < 0.01 && (b.1 - p.1).abs() < 0.01);
+            if collinear { out.pop(); }
+        }
+        out.push(p);
+    }
+    out
+}
+
+/// Absolute polyline from the source outline to the target outline.
+pub type Route = Vec<(f64, f64)>;
+
+/// Orthogonal routes for every edge whose `EdgeStyle.line` routes orthogonally, indexed like
+/// `g.edges`. `None` for other edges, dangling edges, edges no path was found for, and every
+/// edge of a graph too large to route (callers fall back to an L-shape).
+pub fn route_edges(g: &GraphData) -> Vec<Option<Route>> {
+    let dir = g.layout_hints.as_ref().map(|h| h.direction.as_str()).unwrap_or("TB");
+    let index: HashMap<&str, usize> = g.nodes.iter().enumerate().map(|(i, n)| (n.id.as_str(), i)).collect();
+    let sizes: Vec<(f64, f64)> = g.nodes.iter().map(node_box).collect();
+    let centers: Vec<(f64, f64)> = g.nodes.iter().map(|n| (n.x as f64, n.y as f64)).collect();
+    let boxes: Vec<Rect> = (0..g.nodes.len()).map(|i| Rect::around(centers[i].0, centers[i].1, sizes[i].0, sizes[i].1)).collect();
+    let containers: Vec<Rect> = container_boxes(g).into_iter().flatten().map(|(x0, y0, x1, y1)| Rect { x0, y0, x1, y1 }).collect();
+
+    // Edges to route with their endpoints and sides
+    let mut jobs: Vec<(usize, usize, usize, Side, Side)> = Vec::new();
+    for (ei, e) in g.edges.iter().enumerate() {
+        if EdgeLook::of(&e.style).routing != Routing::Orthogonal { continue; }
+        let (Some(&s), Some(&t)) = (index.get(e.source.as_str()), index.get(e.target.as_str())) else { continue };
+        let (ss, ts) = port_sides(dir, centers[s], sizes[s], centers[t], sizes[t]);
+        jobs.push((ei, s, t, ss, ts));
+    }
+
+    // Spread ports sharing a side, ordered by where the other end lies to avoid crossings
+    let mut by_side: HashMap<(usize, Side), Vec<_>> = HashMap::new();
+    for (j, &(_, s, t, ss, ts)) in jobs.iter().enumerate() {
+        let along = |side: Side, other: usize| if side.normal().0 == 0.0 { centers[other].0 } else { centers[other].1 };
+        by_side.entry((s, ss)).or_default().push((j, true, along(ss, t)));
+        by_side.entry((t, ts)).or_default().push((j, false, along(ts, s)));
+    }
+    let mut ports: HashMap<(usize, bool), (f64, f64)> = HashMap::new();
+    for ((node, side), mut list) in by_side {
+        list.sort_by(|a, b| a.2.total_cmp(&b.2).then(a.0.cmp(&b.0)));
+        let (w, h) = sizes[node];
+        let span = if side.normal().0 == 0.0 { w } else { h } * PORT_SPREAD;
+        let shape = Shape::parse(&g.nodes[node].style.shape);
+        for (k, &(j, is_source, _)) in list.iter().enumerate() {
+            let offset = span * ((k + 1) as f64 / (list.len() + 1) as f64 - 0.5);
+            let (px, py) = outline_point(shape, w, h, side.normal(), offset);
+            ports.insert((j, is_source), (centers[node].0 + px, centers[node].1 + py));
+        }
+    }
+    let stub = |p: (f64, f64), side: Side, node: usize| {
+        // Leave the bounding box before turning, whatever the outline looks like
+        let (nx, ny) = side.normal();
+        let b = boxes[node];
+        let edge = match side {
+            Side::Top => b.y0,
+            Side::Bottom => b.y1,
+            Side::Left => b.x0,
+            Side::Right => b.x1,
+        };
+        if nx == 0.0 { (p.0, edge + ny * MARGIN) } else { (edge + nx * MARGIN, p.1) }
+    };
+
+    // Grid lines: clearance around every obstacle plus every stub
+    let mut xs = Vec::new();
+    let mut ys = Vec::new();
+    for r in boxes.iter().chain(containers.iter()) {
+        let m = r.inflate(MARGIN);
+        xs.extend([m.x0, m.x1]);
+        ys.extend([m.y0, m.y1]);
+    }
+    let mut stubs: HashMap<(usize, bool), (f64, f64)> = HashMap::new();
+    for (j, &(_, s, t, ss, ts)) in jobs.iter().enumerate() {
+        for (is_source, node, side) in [(true, s, ss), (false, t, ts)] {
+            let p = stub(ports[&(j, is_source)], side, node);
+            xs.push(p.0);
+            ys.push(p.1);
+            stubs.insert((j, is_source), p);
+        }
+    }
+    let mut routes: Vec<Option<Route>> = vec![None; g.edges.len()];
+    let Some((xs, ys)) = Grid::lines(xs, ys) else { return routes };
+    let obstacles: Vec<Rect> = boxes.iter().map(|b| b.inflate(1.0)).collect();
+    let grid = Grid::new(xs, ys, &obstacles);
+    let whole = Window::whole(&grid);
+
+    let mut used = vec![false; grid.free_pt.len() * 2];
+    for (j, &(ei, s, t, ss, ts)) in jobs.iter().enumerate() {
+        // Containers holding either end may be crossed, all others are walls
+        let (cs, ct) = (centers[s], centers[t]);
+        let extra: Vec<Rect> = containers.iter().filter(|c| !c.contains(cs.0, cs.1) && !c.contains(ct.0, ct.1)).copied().collect();
+        let (Some(from), Some(to)) = (grid.index(stubs[&(j, true)]), grid.index(stubs[&(j, false)])) else { continue };
+        // Search near the endpoints first; widen the window only when no path fits in it
+        let mut found = None;
+        let mut margin = WINDOW_MARGIN;
+        loop {
+            let window = grid.window(from, to, margin);
+            if window.w * window.h > MAX_WINDOW_POINTS { break; }
+            found = shortest_path(&grid, &window, (from, ss.heading()), (to, opposite(ts.heading())), &extra, &used);
+            if found.is_some() || window.covers(&whole) { break; }
+            margin *= 4.0;
+        }
+        let Some(path) = found else { continue };
+        for w in path.windows(2) {
+            // mark every unit grid segment between consecutive path points
+            used[grid.segment(w[0], w[1])] = true;
+        }
+        let mut points = vec![ports[&(j, true)]];
+        points.extend(path.iter().map(|&k| grid.point(k)));
+        points.push(ports[&(j, false)]);
+        routes[ei] = Some(simplify(points));
+    }
+    routes
+}
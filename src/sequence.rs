@@ -0,0 +1,378 @@
+use crate::edge_style::{EdgeLook, Stroke};
+use crate::excalidraw::seed_from;
+use crate::layout::approx_node_size;
+use crate::state::{Activation, EdgeData, EdgeStyle, GraphData, NodeData, NodeStyle, Participant, SequenceData, SequenceMessage};
+use crate::text::{node_label, text_width, LABEL_FONT};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+// Sequence diagrams: participants across the top with dashed lifelines, messages as horizontal
+// arrows stacked in send order, activation bars on the lifelines, and alt/loop/opt frames.
+
+const PARTICIPANT_GAP: f64 = 60.0;
+const MIN_PITCH: f64 = 200.0;
+const HEADER_H: f64 = 48.0;
+const LABEL_SPACE: f64 = 20.0;
+const ROW_GAP: f64 = 24.0;
+const SELF_LOOP_W: f64 = 40.0;
+const SELF_LOOP_H: f64 = 24.0;
+const ACTIVATION_W: f64 = 12.0;
+const FRAME_HEADER: f64 = 32.0;
+const FRAME_PAD: f64 = 40.0;
+const STROKE: &str = "#111827";
+
+fn is_return(m: &SequenceMessage) -> bool {
+    matches!(m.kind.to_lowercase().as_str(), "return" | "reply" | "response")
+}
+
+fn is_async(m: &SequenceMessage) -> bool {
+    m.kind.eq_ignore_ascii_case("async")
+}
+
+fn label_width(text: &str) -> f64 {
+    (text_width(text, LABEL_FONT, 14.0) + 8.0).max(24.0)
+}
+
+/// Keep `GraphData.sequence` and the plain nodes/edges in step: a graph without a sequence gets
+/// one from its edges (edge order is message order, dashed edges are replies), and a sequence
+/// without nodes gets participants as nodes and messages as edges for the text exports.
+pub fn ensure_sequence(g: &mut GraphData) {
+    let empty = g.sequence.as_ref().map(|s| s.participants.is_empty() && s.messages.is_empty()).unwrap_or(true);
+    if empty {
+        if g.edges.is_empty() { return; }
+        let messages = g
+            .edges
+            .iter()
+            .map(|e| {
+                let dashed = EdgeLook::of(&e.style).stroke != Stroke::Solid;
+                SequenceMessage { from: e.source.clone(), to: e.target.clone(), label: e.label.clone(), kind: if dashed { "return" } else { "sync" }.to_string() }
+            })
+            .collect();
+        let participants = g.nodes.iter().map(|n| Participant { id: n.id.clone(), label: n.label.clone(), kind: None }).collect();
+        g.sequence = Some(SequenceData { participants, messages, ..Default::default() });
+    }
+    let Some(seq) = g.sequence.as_mut() else { return };
+
+    // Every message endpoint is a participant, in order of first appearance
+    for m in seq.messages.clone() {
+        for id in [m.from, m.to] {
+            if !seq.participants.iter().any(|p| p.id == id) {
+                seq.participants.push(Participant { id: id.clone(), label: id, kind: None });
+            }
+        }
+    }
+    for p in &mut seq.participants {
+        if p.label.is_empty() { p.label = p.id.clone(); }
+    }
+
+    if g.nodes.is_empty() {
+        g.nodes = seq
+            .participants
+            .iter()
+            .map(|p| NodeData { id: p.id.clone(), label: p.label.clone(), x: 0.0, y: 0.0, style: NodeStyle { shape: "rectangle".to_string(), color: "#F3F4F6".to_string() } })
+            .collect();
+        g.edges = seq
+            .messages
+            .iter()
+            .enumerate()
+            .map(|(i, m)| EdgeData {
+                id: format!("m{}", i),
+                source: m.from.clone(),
+                target: m.to.clone(),
+                label: m.label.clone(),
+                style: EdgeStyle { line: if is_return(m) { "dashed" } else { "solid" }.to_string(), arrow: "end".to_string() },
+            })
+            .collect();
+    }
+}
+
+/// Activation bars implied by the messages: a sync call activates the receiver until it
+/// replies to that caller (closing anything nested inside, such as self calls); calls that
+/// never get a reply end at the receiver's last message.
+pub fn derive_activations(seq: &SequenceData) -> Vec<Activation> {
+    // participant -> stack of (start message, caller)
+    let mut open: HashMap<&str, Vec<(usize, &str)>> = HashMap::new();
+    let mut out: Vec<Activation> = Vec::new();
+    for (i, m) in seq.messages.iter().enumerate() {
+        if is_return(m) {
+            let stack = open.entry(m.from.as_str()).or_default();
+            if let Some(pos) = stack.iter().rposition(|&(_, caller)| caller == m.to) {
+                for (start, _) in stack.drain(pos..) {
+                    out.push(Activation { participant: m.from.clone(), start, end: i });
+                }
+            }
+        } else if !is_async(m) {
+            open.entry(m.to.as_str()).or_default().push((i, m.from.as_str()));
+        }
+    }
+    for (p, starts) in open {
+        for (start, _) in starts {
+            let end = (start..seq.messages.len()).rev().find(|&i| seq.messages[i].from == p || seq.messages[i].to == p).unwrap_or(start);
+            out.push(Activation { participant: p.to_string(), start, end });
+        }
+    }
+    out.sort_by_key(|a| (a.start, std::cmp::Reverse(a.end)));
+    out
+}
+
+// Fragment with its message range clamped to the diagram
+struct Frame {
+    kind: String,
+    label: String,
+    start: usize,
+    end: usize,
+    sections: Vec<(usize, String)>,
+}
+
+fn frames(seq: &SequenceData) -> Vec<Frame> {
+    let Some(last) = seq.messages.len().checked_sub(1) else { return Vec::new() };
+    seq.fragments
+        .iter()
+        .flatten()
+        .filter(|f| f.start <= f.end.min(last))
+        .map(|f| {
+            let end = f.end.min(last);
+            let sections = f.sections.iter().flatten().filter(|s| s.start > f.start && s.start <= end).map(|s| (s.start, s.label.clone())).collect();
+            Frame { kind: f.kind.to_lowercase(), label: f.label.clone(), start: f.start, end, sections }
+        })
+        .collect()
+}
+
+fn element(kind: &str, id: String, x: f64, y: f64, w: f64, h: f64) -> Value {
+    let seed = seed_from(&id);
+    json!({
+        "type": kind,
+        "version": 1,
+        "versionNonce": (seed as i64),
+        "isDeleted": false,
+        "id": id,
+        "seed": seed,
+        "fillStyle": "solid",
+        "strokeWidth": 2,
+        "strokeStyle": "solid",
+        "roughness": 1,
+        "opacity": 100,
+        "angle": 0,
+        "x": x,
+        "y": y,
+        "strokeColor": STROKE,
+        "backgroundColor": "transparent",
+        "width": w,
+        "height": h,
+        "boundElements": [],
+        "updated": 0
+    })
+}
+
+fn text(id: String, x: f64, y: f64, w: f64, content: &str, size: f64, align: &str) -> Value {
+    let mut el = element("text", id, x, y, w, size + 6.0);
+    el["strokeWidth"] = json!(1);
+    el["roughness"] = json!(0);
+    el["text"] = json!(content);
+    el["fontSize"] = json!(size);
+    el["fontFamily"] = json!(1);
+    el["textAlign"] = json!(align);
+    el["verticalAlign"] = json!("middle");
+    el["baseline"] = json!(size + 2.0);
+    el
+}
+
+fn polyline(kind: &str, id: String, x: f64, y: f64, points: &[(f64, f64)], dashed: bool, end_arrowhead: Option<&str>) -> Value {
+    let (minx, maxx) = points.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(a, b), p| (a.min(p.0), b.max(p.0)));
+    let (miny, maxy) = points.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(a, b), p| (a.min(p.1), b.max(p.1)));
+    let mut el = element(kind, id, x, y, maxx - minx, maxy - miny);
+    el["strokeStyle"] = json!(if dashed { "dashed" } else { "solid" });
+    el["points"] = json!(points.iter().map(|p| vec![p.0, p.1]).collect::<Vec<_>>());
+    el["lastCommittedPoint"] = Value::Null;
+    if kind == "arrow" {
+        el["startArrowhead"] = Value::Null;
+        el["endArrowhead"] = json!(end_arrowhead);
+    }
+    el
+}
+
+/// Excalidraw elements for a sequence diagram (lifelines, messages, activations, frames).
+pub fn sequence_to_excalidraw_elements(seq: &SequenceData) -> Vec<Value> {
+    // Message endpoints missing from the participant list get their own lifeline
+    let mut participants = seq.participants.clone();
+    for m in &seq.messages {
+        for id in [&m.from, &m.to] {
+            if !participants.iter().any(|p| &p.id == id) {
+                participants.push(Participant { id: id.clone(), label: id.clone(), kind: None });
+            }
+        }
+    }
+    let n = participants.len();
+    if n == 0 { return Vec::new(); }
+    let index: HashMap<&str, usize> = participants.iter().enumerate().map(|(i, p)| (p.id.as_str(), i)).collect();
+    let label_of = |p: &Participant| if p.label.is_empty() { p.id.clone() } else { p.label.clone() };
+    let widths: Vec<f64> = participants.iter().map(|p| approx_node_size(&label_of(p)).0).collect();
+    let messages: Vec<(usize, usize, &SequenceMessage)> = seq.messages.iter().map(|m| (index[m.from.as_str()], index[m.to.as_str()], m)).collect();
+
+    // Columns: wide enough for the boxes and for the labels of the messages between them
+    let mut pitch: Vec<f64> = (0..n.saturating_sub(1)).map(|i| MIN_PITCH.max((widths[i] + widths[i + 1]) / 2.0 + PARTICIPANT_GAP)).collect();
+    for &(a, b, m) in &messages {
+        let need = label_width(&m.label) + 2.0 * ACTIVATION_W;
+        if a == b {
+            if a + 1 < n { pitch[a] = pitch[a].max(SELF_LOOP_W + need + 16.0); }
+            continue;
+        }
+        let (lo, hi) = (a.min(b), a.max(b));
+        let span: f64 = pitch[lo..hi].iter().sum();
+        if span < need {
+            let extra = (need - span) / (hi - lo) as f64;
+            for p in &mut pitch[lo..hi] { *p += extra; }
+        }
+    }
+    let mut cx = vec![0.0f64; n];
+    for i in 1..n { cx[i] = cx[i - 1] + pitch[i - 1]; }
+
+    // Rows: messages stack downwards; frames and alt sections reserve a header row
+    let frames = frames(seq);
+    let mut top = vec![0.0f64; frames.len()];
+    let mut bottom = vec![0.0f64; frames.len()];
+    let mut dividers: Vec<Vec<f64>> = frames.iter().map(|f| vec![0.0; f.sections.len()]).collect();
+    let mut msg_y = vec![0.0f64; messages.len()];
+    let mut y = HEADER_H + ROW_GAP;
+    for (i, &(a, b, _)) in messages.iter().enumerate() {
+        // Sections of enclosing frames come before frames that open inside them
+        for (f, frame) in frames.iter().enumerate() {
+            for (k, (start, _)) in frame.sections.iter().enumerate() {
+                if *start == i {
+                    dividers[f][k] = y;
+                    y += FRAME_HEADER;
+                }
+            }
+        }
+        let mut opening: Vec<usize> = (0..frames.len()).filter(|&f| frames[f].start == i).collect();
+        opening.sort_by_key(|&f| (std::cmp::Reverse(frames[f].end), f));
+        for f in opening {
+            top[f] = y;
+            y += FRAME_HEADER;
+        }
+        y += LABEL_SPACE;
+        msg_y[i] = y;
+        y += ROW_GAP + if a == b { SELF_LOOP_H } else { 0.0 };
+        let mut closing: Vec<usize> = (0..frames.len()).filter(|&f| frames[f].end == i).collect();
+        closing.sort_by_key(|&f| (std::cmp::Reverse(frames[f].start), std::cmp::Reverse(f)));
+        for f in closing {
+            y += 8.0;
+            bottom[f] = y;
+            y += 8.0;
+        }
+    }
+    let lifeline_end = y + ROW_GAP;
+
+    let activations: Vec<Activation> = match &seq.activations {
+        Some(list) => list.iter().filter(|a| index.contains_key(a.participant.as_str()) && a.start <= a.end && a.end < messages.len()).cloned().collect(),
+        None => derive_activations(seq),
+    };
+    let active_at = |p: usize, i: usize| activations.iter().any(|a| index[a.participant.as_str()] == p && a.start <= i && i <= a.end);
+
+    let mut frame_els: Vec<Value> = Vec::new();
+    let mut lines: Vec<Value> = Vec::new();
+    let mut boxes: Vec<Value> = Vec::new();
+    let mut arrows: Vec<Value> = Vec::new();
+    let mut texts: Vec<Value> = Vec::new();
+
+    for (f, frame) in frames.iter().enumerate() {
+        let covered = messages[frame.start..=frame.end].iter();
+        let (lo, hi) = covered.clone().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &(a, b, _)| (lo.min(cx[a].min(cx[b])), hi.max(cx[a].max(cx[b]))));
+        let self_loop = covered.filter(|(a, b, _)| a == b).map(|&(a, _, m)| cx[a] + SELF_LOOP_W + label_width(&m.label)).fold(hi, f64::max);
+        // Nested frames sit slightly inside their parents
+        let encloses = |o: usize| {
+            let other = &frames[o];
+            o != f && other.start <= frame.start && other.end >= frame.end && ((other.start, other.end) != (frame.start, frame.end) || o < f)
+        };
+        let depth = (0..frames.len()).filter(|&o| encloses(o)).count();
+        let pad = (FRAME_PAD - 10.0 * depth as f64).max(10.0);
+        let (x1, x2) = (lo - pad, self_loop.max(hi) + pad);
+        let mut rect = element("rectangle", format!("fragment-{}", f), x1, top[f], x2 - x1, bottom[f] - top[f]);
+        rect["roughness"] = json!(0);
+        frame_els.push(rect);
+        let tag_w = label_width(&frame.kind);
+        let mut tag = element("rectangle", format!("fragment-tag-{}", f), x1, top[f], tag_w, 22.0);
+        tag["strokeWidth"] = json!(1);
+        tag["roughness"] = json!(0);
+        tag["backgroundColor"] = json!("#F3F4F6");
+        frame_els.push(tag);
+        texts.push(text(format!("fragment-tag-label-{}", f), x1, top[f] + 2.0, tag_w, &frame.kind, 14.0, "center"));
+        if !frame.label.is_empty() {
+            let guard = format!("[{}]", frame.label);
+            texts.push(text(format!("fragment-guard-{}", f), x1 + tag_w + 8.0, top[f] + 2.0, label_width(&guard), &guard, 14.0, "left"));
+        }
+        for (k, (_, label)) in frame.sections.iter().enumerate() {
+            let dy = dividers[f][k];
+            lines.push(polyline("line", format!("fragment-divider-{}-{}", f, k), x1, dy, &[(0.0, 0.0), (x2 - x1, 0.0)], true, None));
+            if !label.is_empty() {
+                let guard = format!("[{}]", label);
+                texts.push(text(format!("fragment-section-{}-{}", f, k), x1 + 8.0, dy + 4.0, label_width(&guard), &guard, 14.0, "left"));
+            }
+        }
+    }
+
+    for (i, p) in participants.iter().enumerate() {
+        let label = label_of(p);
+        let shape = if p.kind.as_deref().map(|k| k.eq_ignore_ascii_case("actor")).unwrap_or(false) { "ellipse" } else { "rectangle" };
+        let mut rect = element(shape, format!("participant-{}", p.id), cx[i] - widths[i] / 2.0, 0.0, widths[i], HEADER_H);
+        rect["backgroundColor"] = json!("#F3F4F6");
+        if shape == "rectangle" { rect["roundness"] = json!({"type": 3}); }
+        // Label is container text of the header box, so both move together in the editor
+        let label_id = format!("participant-label-{}", p.id);
+        rect["boundElements"] = json!([{"id": label_id, "type": "text"}]);
+        boxes.push(rect);
+        let wrapped = node_label(&label);
+        let tw = wrapped.width.max(24.0);
+        let th = wrapped.height.max(22.0);
+        let mut label_el = text(label_id, cx[i] - tw / 2.0, HEADER_H / 2.0 - th / 2.0, tw, &wrapped.text(), 16.0, "center");
+        label_el["height"] = json!(th);
+        label_el["containerId"] = json!(format!("participant-{}", p.id));
+        label_el["originalText"] = json!(label);
+        texts.push(label_el);
+        lines.push(polyline("line", format!("lifeline-{}", p.id), cx[i], HEADER_H, &[(0.0, 0.0), (0.0, lifeline_end - HEADER_H)], true, None));
+    }
+
+    for (k, a) in activations.iter().enumerate() {
+        let p = index[a.participant.as_str()];
+        // Nested activations of the same participant step to the right
+        let nesting = activations[..k].iter().filter(|o| o.participant == a.participant && o.start <= a.start && o.end >= a.end).count();
+        let x = cx[p] - ACTIVATION_W / 2.0 + nesting as f64 * ACTIVATION_W / 2.0;
+        let y1 = msg_y[a.start] - 6.0;
+        let self_tail = if messages[a.end].0 == messages[a.end].1 { SELF_LOOP_H } else { 0.0 };
+        let y2 = msg_y[a.end] + 6.0 + self_tail;
+        let mut bar = element("rectangle", format!("activation-{}", k), x, y1, ACTIVATION_W, y2 - y1);
+        bar["strokeWidth"] = json!(1);
+        bar["roughness"] = json!(0);
+        bar["backgroundColor"] = json!("#FFFFFF");
+        boxes.push(bar);
+    }
+
+    for (i, &(a, b, m)) in messages.iter().enumerate() {
+        let dashed = is_return(m);
+        let head = if dashed || is_async(m) { "arrow" } else { "triangle" };
+        let off = |p: usize| if active_at(p, i) { ACTIVATION_W / 2.0 } else { 0.0 };
+        if a == b {
+            let x = cx[a] + off(a);
+            let pts = [(0.0, 0.0), (SELF_LOOP_W, 0.0), (SELF_LOOP_W, SELF_LOOP_H), (0.0, SELF_LOOP_H)];
+            arrows.push(polyline("arrow", format!("message-{}", i), x, msg_y[i], &pts, dashed, Some(head)));
+            if !m.label.is_empty() {
+                texts.push(text(format!("message-label-{}", i), x + SELF_LOOP_W + 6.0, msg_y[i] - 4.0, label_width(&m.label), &m.label, 14.0, "left"));
+            }
+            continue;
+        }
+        let dir = if cx[b] > cx[a] { 1.0 } else { -1.0 };
+        let (x1, x2) = (cx[a] + dir * off(a), cx[b] - dir * off(b));
+        arrows.push(polyline("arrow", format!("message-{}", i), x1, msg_y[i], &[(0.0, 0.0), (x2 - x1, 0.0)], dashed, Some(head)));
+        if !m.label.is_empty() {
+            let lw = label_width(&m.label);
+            texts.push(text(format!("message-label-{}", i), (x1 + x2) / 2.0 - lw / 2.0, msg_y[i] - LABEL_SPACE, lw, &m.label, 14.0, "center"));
+        }
+    }
+
+    let mut elements = frame_els;
+    elements.extend(lines);
+    elements.extend(boxes);
+    elements.extend(arrows);
+    elements.extend(texts);
+    elements
+}
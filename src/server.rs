@@ -4,7 +4,6 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::sync::Arc;
 use std::path::Path;
-use std::process::Command;
 use pocketflow_rs::Context as PfContext;
 use utoipa::{OpenApi, ToSchema};
 use utoipa::openapi::server::Server;
@@ -14,8 +13,14 @@ use tower_http::cors::{CorsLayer, Any};
 use axum::http::{Method, header};
 
 use crate::flow::create_graph_flow;
-use crate::state::{SharedState, UserSession, UserTier, ChatInput, InputType, AiResponse, GraphData};
-use crate::excalidraw::graphdata_to_excalidraw_scene_with_opts;
+use crate::state::{SharedState, UserSession, UserTier, ChatInput, InputType, AiResponse, GraphData, LlmRetry};
+use crate::render::{render_scene, RasterOptions, RenderBackend};
+use crate::mermaid::graphdata_to_mermaid;
+use crate::dot::graphdata_to_dot;
+use crate::drawio::graphdata_to_drawio_with_opts;
+use crate::excalidraw_import::excalidraw_scene_to_graphdata;
+use crate::nodes::{auto_layout_blocking, excalidraw_scene_blocking};
+use crate::validate::Diagnostic;
 
 #[derive(Clone)]
 pub struct AppConfig {
@@ -32,6 +37,15 @@ pub struct GenerateRequest {
     pub allow_images: Option<bool>,
     #[serde(default)]
     pub assets_dir: Option<String>,
+    /// Fix structural issues in the generated graph (duplicates, dangling edges, ...)
+    #[serde(default)]
+    pub repair: Option<bool>,
+    /// Correction round-trips allowed when the LLM reply is unusable (default 2)
+    #[serde(default)]
+    pub max_retries: Option<u32>,
+    /// Name of an LLM route from the routing config, overriding tier/kind routing
+    #[serde(default)]
+    pub llm_route: Option<String>,
 }
 
 #[derive(Serialize, ToSchema)]
@@ -39,6 +53,9 @@ pub struct GenerateResponse {
     pub graph_data: GraphData,
     pub scene: serde_json::Value,
     pub artifacts: serde_json::Value,
+    pub message: Option<String>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub llm_retries: Vec<LlmRetry>,
 }
 
 #[derive(Deserialize, ToSchema)]
@@ -50,7 +67,19 @@ pub struct RenderRequest {
     #[serde(default)]
     pub filename_hint: Option<String>,
     #[serde(default)]
-    pub formats: Option<Vec<String>>, // ["png","svg"]
+    pub formats: Option<Vec<String>>, // ["png","svg","mermaid","dot","drawio"]
+    /// PNG pixel multiplier (default 1.0)
+    #[serde(default)]
+    pub scale: Option<f32>,
+    /// PNG resolution; overrides `scale` (96 DPI = 1.0)
+    #[serde(default)]
+    pub dpi: Option<f32>,
+    /// PNG background color override, e.g. "#FFFFFF" or "transparent"
+    #[serde(default)]
+    pub background: Option<String>,
+    /// Rebuild GraphData from `scene` (when no `graph_data` is given) and re-run auto layout
+    #[serde(default)]
+    pub relayout: Option<bool>,
 }
 
 #[derive(Serialize, ToSchema)]
@@ -58,16 +87,32 @@ pub struct RenderResponse {
     pub suggested: String,
     pub png: Option<String>,
     pub svg: Option<String>,
+    pub mermaid: Option<String>,
+    pub dot: Option<String>,
+    pub drawio: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ImportRequest {
+    /// Excalidraw scene JSON, e.g. edited in the canvas app
+    pub scene: serde_json::Value,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ImportResponse {
+    pub graph_data: GraphData,
 }
 
 #[derive(OpenApi)]
 #[openapi(
-    paths(handle_generate, handle_render),
+    paths(handle_generate, handle_render, handle_import),
     components(schemas(
         GenerateRequest,
         GenerateResponse,
         RenderRequest,
         RenderResponse,
+        ImportRequest,
+        ImportResponse,
         
         GraphData,
         crate::state::NodeData,
@@ -80,7 +125,17 @@ pub struct RenderResponse {
         crate::state::DecorationSize,
         crate::state::DecorationOffset,
         crate::state::Container,
-        crate::state::ContainerStyle
+        crate::state::ContainerStyle,
+        crate::state::SequenceData,
+        crate::state::Participant,
+        crate::state::SequenceMessage,
+        crate::state::SequenceFragment,
+        crate::state::FragmentSection,
+        crate::state::Activation,
+        LlmRetry,
+        Diagnostic,
+        crate::validate::DiagnosticKind,
+        crate::validate::Severity
     )),
     tags(
         (name = "graph", description = "Graph generation and rendering APIs")
@@ -135,6 +190,7 @@ pub async fn run_server(port: u16, default_allow_images: bool, default_assets_di
     let app = Router::new()
         .route("/graph/generate", post(handle_generate))
         .route("/graph/render", post(handle_render))
+        .route("/graph/import", post(handle_import))
         .merge(SwaggerUi::new("/docs").url("/api-doc/openapi.json", openapi.clone()))
         .layer(cors)
         .with_state(Arc::new(cfg));
@@ -166,7 +222,7 @@ async fn handle_generate(State(cfg): State<Arc<AppConfig>>, Json(req): Json<Gene
         // Use placeholder user_id "test" to pass current AuthenticationNode logic
         user_session: UserSession { user_id: "test".into(), is_authenticated: true, tier, credits_remaining: 100, last_activity: String::new() },
         chat_input: ChatInput { input_type: InputType::Text, content: req.content.clone(), timestamp: String::new() },
-        ai_response: AiResponse { status: crate::state::AiStatus::Success, message: None, graph_data: None, credits_cost: 0 },
+        ai_response: AiResponse { status: crate::state::AiStatus::Success, message: None, graph_data: None, credits_cost: 0, diagnostics: Vec::new(), llm_retries: Vec::new() },
         current_graph: None,
         payment_info: None,
     };
@@ -175,20 +231,23 @@ async fn handle_generate(State(cfg): State<Arc<AppConfig>>, Json(req): Json<Gene
     pf_ctx.set("export_excalidraw_path", json!(Option::<String>::None));
     pf_ctx.set("allow_images", json!(allow_images));
     pf_ctx.set("assets_dir", json!(assets_dir.clone()));
+    pf_ctx.set("repair_graph", json!(req.repair.unwrap_or(false)));
+    if let Some(n) = req.max_retries { pf_ctx.set("llm_max_retries", json!(n)); }
+    if let Some(route) = req.llm_route.as_ref() { pf_ctx.set("llm_route", json!(route)); }
 
     let flow = create_graph_flow();
-    let final_ctx = flow.run(pf_ctx).await.map_err(internal_err)?;
+    let final_state = flow.run(pf_ctx).await.map_err(internal_err)?;
 
-    let shared: SharedState = final_ctx.get("shared_state").cloned().and_then(|v| serde_json::from_value(v).ok()).unwrap_or_default();
+    let shared: SharedState = serde_json::from_value(final_state).unwrap_or_default();
     let gd = shared.ai_response.graph_data.clone().ok_or((StatusCode::BAD_REQUEST, "No graph generated".to_string()))?;
 
-    let scene = graphdata_to_excalidraw_scene_with_opts(&gd, allow_images, &assets_dir);
+    let scene = excalidraw_scene_blocking(gd.clone(), allow_images, assets_dir.clone()).await.map_err(internal_err)?;
     let suggested = suggest_filename(&req.content);
     Ok(Json(GenerateResponse { graph_data: gd, scene, artifacts: json!({
         "suggested": suggested,
         "png": format!("docs/screens/{}.png", suggested),
         "svg": format!("docs/screens/{}.svg", suggested)
-    }) }))
+    }), message: shared.ai_response.message, diagnostics: shared.ai_response.diagnostics, llm_retries: shared.ai_response.llm_retries }))
 }
 
 /// Render a scene (or GraphData) to PNG/SVG artifacts.
@@ -208,12 +267,28 @@ async fn handle_render(State(cfg): State<Arc<AppConfig>>, Json(req): Json<Render
     let assets_dir = cfg.assets_dir.clone();
     let project_root = Path::new(env!("CARGO_MANIFEST_DIR"));
 
-    let scene = if let Some(scene) = req.scene.clone() {
-        scene
-    } else if let Some(gd) = req.graph_data.clone() {
-        graphdata_to_excalidraw_scene_with_opts(&gd, allow_images, &assets_dir)
-    } else {
-        return Err((StatusCode::BAD_REQUEST, "Provide scene or graph_data".into()));
+    // A scene without graph_data is imported back into structure for text formats / relayout;
+    // the import error is kept so those requests report why the scene was unusable
+    let graph_data = match (&req.graph_data, &req.scene) {
+        (Some(gd), _) => Ok(gd.clone()),
+        (None, Some(scene)) => excalidraw_scene_to_graphdata(scene),
+        (None, None) => return Err((StatusCode::BAD_REQUEST, "Provide scene or graph_data".into())),
+    };
+    let relayout = req.relayout.unwrap_or(false);
+    // Layout and scene building run on the blocking pool: routing large graphs takes a while
+    let graph_data = match graph_data {
+        Ok(gd) if relayout => {
+            let dir = gd.layout_hints.as_ref().map(|h| h.direction.to_uppercase()).filter(|d| !d.is_empty()).unwrap_or_else(|| "LR".to_string());
+            Ok(auto_layout_blocking(gd, 180.0, 140.0, dir, 4).await.map_err(internal_err)?)
+        }
+        Err(e) if relayout => return Err(structure_err("relayout", &e)),
+        other => other,
+    };
+
+    let scene = match (&req.scene, &graph_data) {
+        (Some(scene), _) if !relayout => scene.clone(),
+        (_, Ok(gd)) => excalidraw_scene_blocking(gd.clone(), allow_images, assets_dir.clone()).await.map_err(internal_err)?,
+        _ => return Err((StatusCode::BAD_REQUEST, "Provide scene or graph_data".into())),
     };
 
     // Write scene to a temp file
@@ -228,19 +303,68 @@ async fn handle_render(State(cfg): State<Arc<AppConfig>>, Json(req): Json<Render
     let formats = req.formats.clone().unwrap_or(vec!["png".into(), "svg".into()]);
     let mut png_path = None;
     let mut svg_path = None;
-    let render_script = project_root.join("tools/render-excalidraw/render.js");
+    let backend = RenderBackend::from_env();
+    let raster = RasterOptions::new(req.scale, req.dpi, req.background.clone());
     if formats.iter().any(|f| f == "png") {
         let out_png = out_dir_abs.join(format!("{}.png", suggested));
-        let s = Command::new("node").arg(&render_script).arg(&scene_path).arg(&out_png).status().map_err(internal_err)?;
-        if s.success() { png_path = Some(out_png.display().to_string()); }
+        match render_scene(&scene, &scene_path, &out_png, backend, &raster) {
+            Ok(()) => png_path = Some(out_png.display().to_string()),
+            Err(e) => eprintln!("PNG render failed: {}", e),
+        }
     }
     if formats.iter().any(|f| f == "svg") {
         let out_svg = out_dir_abs.join(format!("{}.svg", suggested));
-        let s = Command::new("node").arg(&render_script).arg(&scene_path).arg(&out_svg).status().map_err(internal_err)?;
-        if s.success() { svg_path = Some(out_svg.display().to_string()); }
+        match render_scene(&scene, &scene_path, &out_svg, backend, &raster) {
+            Ok(()) => svg_path = Some(out_svg.display().to_string()),
+            Err(e) => eprintln!("SVG render failed: {}", e),
+        }
+    }
+
+    // Text exports need the structured graph
+    let mut mermaid_path = None;
+    if formats.iter().any(|f| f == "mermaid") {
+        let gd = graph_data.as_ref().map_err(|e| structure_err("mermaid export", e))?;
+        let out_mmd = out_dir_abs.join(format!("{}.mmd", suggested));
+        std::fs::write(&out_mmd, graphdata_to_mermaid(gd)).map_err(internal_err)?;
+        mermaid_path = Some(out_mmd.display().to_string());
     }
+    let mut dot_path = None;
+    if formats.iter().any(|f| f == "dot") {
+        let gd = graph_data.as_ref().map_err(|e| structure_err("dot export", e))?;
+        let out_dot = out_dir_abs.join(format!("{}.dot", suggested));
+        std::fs::write(&out_dot, graphdata_to_dot(gd)).map_err(internal_err)?;
+        dot_path = Some(out_dot.display().to_string());
+    }
+    let mut drawio_path = None;
+    if formats.iter().any(|f| f == "drawio") {
+        let gd = graph_data.as_ref().map_err(|e| structure_err("drawio export", e))?;
+        let out_drawio = out_dir_abs.join(format!("{}.drawio", suggested));
+        std::fs::write(&out_drawio, graphdata_to_drawio_with_opts(gd, allow_images, &assets_dir)).map_err(internal_err)?;
+        drawio_path = Some(out_drawio.display().to_string());
+    }
+
+    Ok(Json(RenderResponse { suggested, png: png_path, svg: svg_path, mermaid: mermaid_path, dot: dot_path, drawio: drawio_path }))
+}
+
+/// Rebuild GraphData from an (edited) Excalidraw scene.
+#[utoipa::path(
+    post,
+    path = "/graph/import",
+    request_body = ImportRequest,
+    responses(
+        (status = 200, description = "Scene converted to GraphData", body = ImportResponse),
+        (status = 400, description = "Scene has no usable shapes")
+    ),
+    tag = "graph"
+)]
+async fn handle_import(Json(req): Json<ImportRequest>) -> Result<Json<ImportResponse>, (StatusCode, String)> {
+    let graph_data = excalidraw_scene_to_graphdata(&req.scene).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    Ok(Json(ImportResponse { graph_data }))
+}
 
-    Ok(Json(RenderResponse { suggested, png: png_path, svg: svg_path }))
+// 400 for an operation that needs structure the request did not provide
+fn structure_err(what: &str, import_error: &str) -> (StatusCode, String) {
+    (StatusCode::BAD_REQUEST, format!("{} requires graph_data or a scene with shapes: {}", what, import_error))
 }
 
 fn internal_err<E: std::fmt::Display>(e: E) -> (StatusCode, String) { (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()) }
@@ -0,0 +1,156 @@
+use crate::layout::approx_node_size;
+use crate::state::NodeData;
+use crate::text::node_label;
+
+// Node shapes: canonical names for the NodeStyle.shape vocabulary, shape-aware box sizes, and
+// the point where a ray from the center leaves the outline (so arrows end on the real border).
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shape {
+    Rectangle,
+    Rounded,
+    Ellipse,
+    Circle,
+    Diamond,
+    Cylinder,
+    Parallelogram,
+    Hexagon,
+    Document,
+    Actor,
+}
+
+/// Height of the actor stick figure; its label sits underneath.
+pub const ACTOR_FIGURE_H: f64 = 64.0;
+
+impl Shape {
+    /// Map a `NodeStyle.shape` value (LLM, Mermaid, DOT or Excalidraw vocabulary) to a shape.
+    pub fn parse(name: &str) -> Shape {
+        match name.trim().to_lowercase().replace(['-', ' '], "_").as_str() {
+            "rounded" | "round" | "rounded_rectangle" | "stadium" | "pill" => Shape::Rounded,
+            "ellipse" | "oval" => Shape::Ellipse,
+            "circle" => Shape::Circle,
+            "diamond" | "decision" | "rhombus" => Shape::Diamond,
+            "cylinder" | "database" | "db" => Shape::Cylinder,
+            "parallelogram" | "io" | "input" | "output" | "data" => Shape::Parallelogram,
+            "hexagon" | "preparation" => Shape::Hexagon,
+            "document" | "doc" | "file" => Shape::Document,
+            "actor" | "person" | "user" | "stick_figure" => Shape::Actor,
+            _ => Shape::Rectangle,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Shape::Rectangle => "rectangle",
+            Shape::Rounded => "rounded",
+            Shape::Ellipse => "ellipse",
+            Shape::Circle => "circle",
+            Shape::Diamond => "diamond",
+            Shape::Cylinder => "cylinder",
+            Shape::Parallelogram => "parallelogram",
+            Shape::Hexagon => "hexagon",
+            Shape::Document => "document",
+            Shape::Actor => "actor",
+        }
+    }
+}
+
+/// Rendered box size for a label in the given shape: curved and slanted outlines get extra
+/// room so the label still fits inside, actors are a figure with the label below.
+pub fn shape_size(shape: Shape, label: &str) -> (f64, f64) {
+    let (w, h) = approx_node_size(label);
+    match shape {
+        Shape::Rectangle | Shape::Rounded => (w, h),
+        Shape::Ellipse => (w * 1.25, h + 12.0),
+        Shape::Circle => {
+            let d = w.max(72.0);
+            (d, d)
+        }
+        Shape::Diamond => (w * 1.5, h + 32.0),
+        Shape::Cylinder => (w, h + 24.0),
+        Shape::Parallelogram | Shape::Hexagon => (w + 40.0, h),
+        Shape::Document => (w, h + 12.0),
+        Shape::Actor => {
+            let text = node_label(label);
+            ((text.width + 8.0).max(48.0), ACTOR_FIGURE_H + text.height + 8.0)
+        }
+    }
+}
+
+/// Shape-aware box size of a node.
+pub fn node_box(n: &NodeData) -> (f64, f64) {
+    shape_size(Shape::parse(&n.style.shape), &n.label)
+}
+
+/// Polygon outline relative to the center, for the straight-edged non-rectangular shapes.
+pub fn outline(shape: Shape, w: f64, h: f64) -> Option<Vec<(f64, f64)>> {
+    let (a, b) = (w / 2.0, h / 2.0);
+    match shape {
+        Shape::Diamond => Some(vec![(0.0, -b), (a, 0.0), (0.0, b), (-a, 0.0)]),
+        Shape::Parallelogram => {
+            let s = (w * 0.15).min(20.0);
+            Some(vec![(-a + s, -b), (a, -b), (a - s, b), (-a, b)])
+        }
+        Shape::Hexagon => {
+            let s = (w * 0.2).min(20.0);
+            Some(vec![(-a + s, -b), (a - s, -b), (a, 0.0), (a - s, b), (-a + s, b), (-a, 0.0)])
+        }
+        _ => None,
+    }
+}
+
+// Smallest t > 0 at which (t*dx, t*dy) crosses the polygon
+fn ray_polygon(poly: &[(f64, f64)], dx: f64, dy: f64) -> Option<f64> {
+    let mut best: Option<f64> = None;
+    for (i, &p) in poly.iter().enumerate() {
+        let q = poly[(i + 1) % poly.len()];
+        let (ex, ey) = (q.0 - p.0, q.1 - p.1);
+        let denom = dx * ey - dy * ex;
+        if denom.abs() < 1e-9 { continue; }
+        let t = (p.0 * ey - p.1 * ex) / denom;
+        let s = (p.0 * dy - p.1 * dx) / denom;
+        if t > 0.0 && (-1e-9..=1.0 + 1e-9).contains(&s) {
+            best = Some(best.map_or(t, |b: f64| b.min(t)));
+        }
+    }
+    best
+}
+
+/// Point where the ray from the center (cx, cy) toward (tx, ty) leaves the shape's outline.
+pub fn border_point(shape: Shape, cx: f64, cy: f64, w: f64, h: f64, tx: f64, ty: f64) -> (f64, f64) {
+    let (dx, dy) = (tx - cx, ty - cy);
+    if dx == 0.0 && dy == 0.0 { return (cx, cy); }
+    let (a, b) = (w / 2.0, h / 2.0);
+    let rect = || {
+        let tx = if dx != 0.0 { a / dx.abs() } else { f64::INFINITY };
+        let ty = if dy != 0.0 { b / dy.abs() } else { f64::INFINITY };
+        tx.min(ty)
+    };
+    let t = match shape {
+        Shape::Ellipse | Shape::Circle => 1.0 / ((dx / a).powi(2) + (dy / b).powi(2)).sqrt(),
+        Shape::Diamond | Shape::Parallelogram | Shape::Hexagon => {
+            outline(shape, w, h).and_then(|poly| ray_polygon(&poly, dx, dy)).unwrap_or_else(rect)
+        }
+        _ => rect(),
+    };
+    (cx + dx * t, cy + dy * t)
+}
+
+/// Point on the outline (relative to the center) where an edge attaches when it leaves along
+/// the unit axis `normal` at `offset` along that side (0 = middle of the side).
+pub fn outline_point(shape: Shape, w: f64, h: f64, (nx, ny): (f64, f64), offset: f64) -> (f64, f64) {
+    let vertical = nx == 0.0;
+    let (half_n, half_t) = if vertical { (h / 2.0, w / 2.0) } else { (w / 2.0, h / 2.0) };
+    let u = (offset / half_t).clamp(-0.95, 0.95);
+    let (bx, by) = if vertical { (offset, 0.0) } else { (0.0, offset) };
+    let d = match shape {
+        Shape::Ellipse | Shape::Circle => half_n * (1.0 - u * u).sqrt(),
+        Shape::Diamond => half_n * (1.0 - u.abs()),
+        Shape::Parallelogram | Shape::Hexagon => outline(shape, w, h)
+            .map(|poly| poly.into_iter().map(|(px, py)| (px - bx, py - by)).collect::<Vec<_>>())
+            .and_then(|poly| ray_polygon(&poly, nx, ny))
+            .unwrap_or(half_n),
+        _ => half_n,
+    };
+    (bx + nx * d, by + ny * d)
+}
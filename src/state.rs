@@ -1,6 +1,7 @@
 use serde::{Serialize, Deserialize};
 use utoipa::ToSchema;
 use pocketflow_rs::ProcessState;
+use crate::validate::Diagnostic;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default, ToSchema)]
 pub struct SharedState {
@@ -27,6 +28,59 @@ pub struct Container {
     pub style: Option<ContainerStyle>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, Default, ToSchema)]
+pub struct SequenceData {
+    pub participants: Vec<Participant>, // left to right
+    pub messages: Vec<SequenceMessage>, // top to bottom, in the order they are sent
+    pub fragments: Option<Vec<SequenceFragment>>,
+    pub activations: Option<Vec<Activation>>, // derived from sync/return messages when omitted
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, ToSchema)]
+pub struct Participant {
+    pub id: String,
+    #[serde(default)]
+    pub label: String,
+    #[schema(example = "actor")]
+    pub kind: Option<String>, // participant | actor | database
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, ToSchema)]
+pub struct SequenceMessage {
+    pub from: String,
+    pub to: String,
+    #[serde(default)]
+    pub label: String,
+    #[serde(default)]
+    #[schema(example = "sync")]
+    pub kind: String, // sync | async | return
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, ToSchema)]
+pub struct SequenceFragment {
+    #[schema(example = "alt")]
+    pub kind: String, // alt | loop | opt
+    #[serde(default)]
+    pub label: String,  // guard of the first section, e.g. "valid token"
+    pub start: usize,   // index of the first message inside the frame
+    pub end: usize,     // index of the last message inside the frame (inclusive)
+    pub sections: Option<Vec<FragmentSection>>, // further operands of an alt ("else" branches)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, ToSchema)]
+pub struct FragmentSection {
+    #[serde(default)]
+    pub label: String,
+    pub start: usize, // index of the first message of this section
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, ToSchema)]
+pub struct Activation {
+    pub participant: String,
+    pub start: usize, // message index that starts the activation
+    pub end: usize,   // message index that ends it (inclusive)
+}
+
 impl SharedState {
     pub fn success_state() -> Self {
         let mut state = Self::default();
@@ -89,6 +143,17 @@ pub struct AiResponse {
     pub message: Option<String>,
     pub graph_data: Option<GraphData>,
     pub credits_cost: u32,
+    #[serde(default)]
+    pub diagnostics: Vec<Diagnostic>, // structural issues found in the generated graph
+    #[serde(default)]
+    pub llm_retries: Vec<LlmRetry>,   // correction round-trips after an unusable reply
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, ToSchema)]
+pub struct LlmRetry {
+    pub attempt: u32,          // 1 for the first correction request
+    pub problems: Vec<String>, // what was wrong with the reply being corrected
+    pub error: Option<String>, // provider error; such retries are not charged
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default, ToSchema)]
@@ -132,6 +197,7 @@ pub struct GraphData {
     pub global_style: Option<GlobalStyle>,
     pub decorations: Option<Vec<Decoration>>, // optional visuals/icons/notes
     pub containers: Option<Vec<Container>>,   // optional grouping boxes
+    pub sequence: Option<SequenceData>,       // sequence diagrams: lifelines and ordered messages
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default, ToSchema)]
@@ -155,7 +221,11 @@ pub struct EdgeData {
 #[derive(Debug, Clone, Serialize, Deserialize, Default, ToSchema)]
 pub struct LayoutHints {
     pub direction: String,
-    pub algorithm: String,
+    #[schema(example = "layered")]
+    pub algorithm: String, // longest_path | layered | force | radial | grid | tree | preserve
+    pub rank_sep: Option<f32>,        // distance between consecutive ranks
+    pub node_sep: Option<f32>,        // distance between neighbors within a rank
+    pub max_per_rank: Option<usize>,  // wrap ranks longer than this into extra rows
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default, ToSchema)]
@@ -0,0 +1,383 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use crate::text::{Font, LINE_HEIGHT};
+
+// In-process SVG writer for Excalidraw scenes (as produced by graphdata_to_excalidraw_scene).
+// Covers the element types we emit: rectangle, ellipse, diamond, arrow/line, freedraw, text, image.
+
+const EXPORT_PADDING: f64 = 10.0;
+
+fn num(v: f64) -> String {
+    let s = format!("{:.2}", v);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" || s.is_empty() { "0".to_string() } else { s.to_string() }
+}
+
+fn escape_xml(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for ch in s.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+fn f(el: &Value, key: &str, default: f64) -> f64 {
+    el.get(key).and_then(|v| v.as_f64()).unwrap_or(default)
+}
+
+fn s<'a>(el: &'a Value, key: &str, default: &'a str) -> &'a str {
+    el.get(key).and_then(|v| v.as_str()).unwrap_or(default)
+}
+
+fn font_family_css(id: i64) -> &'static str {
+    match Font::from_family(id) {
+        Font::Helvetica => "Helvetica, Arial, sans-serif",
+        Font::Cascadia => "Cascadia, Consolas, monospace",
+        Font::Virgil => "Virgil, Segoe UI Emoji, sans-serif",
+    }
+}
+
+fn paint(c: &str) -> String {
+    if c.is_empty() || c == "transparent" { "none".to_string() } else { escape_xml(c) }
+}
+
+fn dasharray(el: &Value) -> &'static str {
+    match s(el, "strokeStyle", "solid") {
+        "dashed" => " stroke-dasharray=\"8 8\"",
+        "dotted" => " stroke-dasharray=\"1.5 6\" stroke-linecap=\"round\"",
+        _ => "",
+    }
+}
+
+// Line/arrow points in absolute coordinates
+fn abs_points(el: &Value) -> Vec<(f64, f64)> {
+    let x = f(el, "x", 0.0);
+    let y = f(el, "y", 0.0);
+    el.get("points")
+        .and_then(|v| v.as_array())
+        .map(|pts| {
+            pts.iter()
+                .filter_map(|p| {
+                    let a = p.as_array()?;
+                    Some((x + a.first()?.as_f64()?, y + a.get(1)?.as_f64()?))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn element_bounds(el: &Value) -> Option<(f64, f64, f64, f64)> {
+    let t = s(el, "type", "");
+    if t == "arrow" || t == "line" || t == "freedraw" {
+        let pts = abs_points(el);
+        if pts.is_empty() { return None; }
+        let mut b = (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for (px, py) in pts {
+            b = (b.0.min(px), b.1.min(py), b.2.max(px), b.3.max(py));
+        }
+        // leave room for arrowheads
+        return Some((b.0 - 8.0, b.1 - 8.0, b.2 + 8.0, b.3 + 8.0));
+    }
+    let x = f(el, "x", 0.0);
+    let y = f(el, "y", 0.0);
+    let w = f(el, "width", 0.0);
+    let h = f(el, "height", 0.0);
+    let angle = f(el, "angle", 0.0);
+    if angle == 0.0 {
+        return Some((x, y, x + w, y + h));
+    }
+    let (cx, cy) = (x + w / 2.0, y + h / 2.0);
+    let (sin, cos) = angle.sin_cos();
+    let mut b = (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for (px, py) in [(x, y), (x + w, y), (x, y + h), (x + w, y + h)] {
+        let (dx, dy) = (px - cx, py - cy);
+        let rx = cx + dx * cos - dy * sin;
+        let ry = cy + dx * sin + dy * cos;
+        b = (b.0.min(rx), b.1.min(ry), b.2.max(rx), b.3.max(ry));
+    }
+    Some(b)
+}
+
+// Excalidraw's adaptive radius for rounded rectangles
+fn corner_radius(el: &Value, w: f64, h: f64) -> f64 {
+    match el.get("roundness") {
+        Some(Value::Null) | None => 0.0,
+        Some(_) => (w.min(h) * 0.25).min(32.0),
+    }
+}
+
+// Build an SVG path through the points; smooth (Catmull-Rom) when the element is rounded
+fn path_data(pts: &[(f64, f64)], smooth: bool) -> String {
+    let mut d = String::new();
+    if pts.is_empty() { return d; }
+    let _ = write!(d, "M{} {}", num(pts[0].0), num(pts[0].1));
+    if !smooth || pts.len() < 3 {
+        for p in &pts[1..] {
+            let _ = write!(d, " L{} {}", num(p.0), num(p.1));
+        }
+        return d;
+    }
+    for i in 0..pts.len() - 1 {
+        let p0 = if i == 0 { pts[0] } else { pts[i - 1] };
+        let p1 = pts[i];
+        let p2 = pts[i + 1];
+        let p3 = if i + 2 < pts.len() { pts[i + 2] } else { p2 };
+        let c1 = (p1.0 + (p2.0 - p0.0) / 6.0, p1.1 + (p2.1 - p0.1) / 6.0);
+        let c2 = (p2.0 - (p3.0 - p1.0) / 6.0, p2.1 - (p3.1 - p1.1) / 6.0);
+        let _ = write!(d, " C{} {} {} {} {} {}", num(c1.0), num(c1.1), num(c2.0), num(c2.1), num(p2.0), num(p2.1));
+    }
+    d
+}
+
+// Arrowhead at `tip`, pointing away from `from`
+fn arrowhead_svg(kind: &str, tip: (f64, f64), from: (f64, f64), stroke: &str, sw: f64) -> String {
+    let (dx, dy) = (tip.0 - from.0, tip.1 - from.1);
+    let len = dx.hypot(dy);
+    if len == 0.0 { return String::new(); }
+    let (ux, uy) = (dx / len, dy / len);
+    let size = (15.0_f64).min(len / 2.0).max(6.0);
+    let wing = |a: f64| {
+        let (sin, cos) = a.sin_cos();
+        let bx = -ux * cos + uy * sin;
+        let by = -ux * sin - uy * cos;
+        (tip.0 + bx * size, tip.1 + by * size)
+    };
+    let a = 25f64.to_radians();
+    match kind {
+        "triangle" => {
+            let (l, r) = (wing(a), wing(-a));
+            format!(
+                "<polygon points=\"{},{} {},{} {},{}\" fill=\"{}\" stroke=\"{}\" stroke-width=\"{}\" stroke-linejoin=\"round\"/>",
+                num(tip.0), num(tip.1), num(l.0), num(l.1), num(r.0), num(r.1), stroke, stroke, num(sw)
+            )
+        }
+        "bar" => {
+            let (px, py) = (-uy * size / 2.0, ux * size / 2.0);
+            format!(
+                "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"{}\" stroke-linecap=\"round\"/>",
+                num(tip.0 + px), num(tip.1 + py), num(tip.0 - px), num(tip.1 - py), stroke, num(sw)
+            )
+        }
+        "dot" | "circle" => {
+            let r = size / 3.0;
+            format!(
+                "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\" stroke=\"{}\" stroke-width=\"{}\"/>",
+                num(tip.0 - ux * r), num(tip.1 - uy * r), num(r), stroke, stroke, num(sw)
+            )
+        }
+        _ => {
+            let (l, r) = (wing(a), wing(-a));
+            format!(
+                "<path d=\"M{} {} L{} {} L{} {}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\" stroke-linecap=\"round\" stroke-linejoin=\"round\"/>",
+                num(l.0), num(l.1), num(tip.0), num(tip.1), num(r.0), num(r.1), stroke, num(sw)
+            )
+        }
+    }
+}
+
+struct Patterns {
+    defs: String,
+    ids: HashMap<(String, String), String>,
+}
+
+impl Patterns {
+    // Hachure / cross-hatch fills become SVG patterns keyed by (style, color)
+    fn fill(&mut self, fill_style: &str, color: &str) -> String {
+        if color == "none" || !(fill_style == "hachure" || fill_style == "cross-hatch" || fill_style == "zigzag") {
+            return color.to_string();
+        }
+        let key = (fill_style.to_string(), color.to_string());
+        if let Some(id) = self.ids.get(&key) {
+            return format!("url(#{})", id);
+        }
+        let id = format!("fill-{}", self.ids.len());
+        let cross = if fill_style == "cross-hatch" {
+            format!("<path d=\"M0 0 L0 8\" stroke=\"{}\" stroke-width=\"1\"/>", color)
+        } else {
+            String::new()
+        };
+        let _ = write!(
+            self.defs,
+            "<pattern id=\"{}\" patternUnits=\"userSpaceOnUse\" width=\"8\" height=\"8\" patternTransform=\"rotate(-45)\"><path d=\"M0 0 L8 0\" stroke=\"{}\" stroke-width=\"1\"/>{}</pattern>",
+            id, color, cross
+        );
+        self.ids.insert(key, id.clone());
+        format!("url(#{})", id)
+    }
+}
+
+fn text_svg(el: &Value, stroke: &str, opacity: f64) -> String {
+    let x = f(el, "x", 0.0);
+    let y = f(el, "y", 0.0);
+    let w = f(el, "width", 0.0);
+    let h = f(el, "height", 0.0);
+    let font_size = f(el, "fontSize", 16.0);
+    let family = font_family_css(el.get("fontFamily").and_then(|v| v.as_i64()).unwrap_or(1));
+    let text = s(el, "text", "");
+    let lines: Vec<&str> = text.split('\n').collect();
+    let lh = font_size * LINE_HEIGHT;
+    let block_h = lh * lines.len() as f64;
+    let top = match s(el, "verticalAlign", "top") {
+        "middle" => y + (h - block_h) / 2.0,
+        "bottom" => y + h - block_h,
+        _ => y,
+    };
+    let (anchor, ax) = match s(el, "textAlign", "left") {
+        "center" => ("middle", x + w / 2.0),
+        "right" => ("end", x + w),
+        _ => ("start", x),
+    };
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        "<text font-family=\"{}\" font-size=\"{}\" fill=\"{}\" text-anchor=\"{}\" dominant-baseline=\"alphabetic\" opacity=\"{}\" style=\"white-space: pre;\">",
+        family, num(font_size), stroke, anchor, num(opacity)
+    );
+    for (i, line) in lines.iter().enumerate() {
+        // Baseline sits roughly 80% into the line box
+        let by = top + lh * i as f64 + (lh + font_size * 0.6) / 2.0;
+        let _ = write!(out, "<tspan x=\"{}\" y=\"{}\">{}</tspan>", num(ax), num(by), escape_xml(line));
+    }
+    out.push_str("</text>");
+    out
+}
+
+fn element_svg(el: &Value, files: Option<&serde_json::Map<String, Value>>, patterns: &mut Patterns) -> String {
+    let t = s(el, "type", "");
+    let x = f(el, "x", 0.0);
+    let y = f(el, "y", 0.0);
+    let w = f(el, "width", 0.0);
+    let h = f(el, "height", 0.0);
+    let opacity = f(el, "opacity", 100.0) / 100.0;
+    let stroke = paint(s(el, "strokeColor", "#000000"));
+    let sw = f(el, "strokeWidth", 1.0);
+    let fill = patterns.fill(s(el, "fillStyle", "solid"), &paint(s(el, "backgroundColor", "transparent")));
+    let dash = dasharray(el);
+    let common = format!("fill=\"{}\" stroke=\"{}\" stroke-width=\"{}\"{} opacity=\"{}\"", fill, stroke, num(sw), dash, num(opacity));
+
+    let body = match t {
+        "rectangle" => {
+            let r = corner_radius(el, w, h);
+            format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"{}\" ry=\"{}\" {}/>",
+                num(x), num(y), num(w), num(h), num(r), num(r), common
+            )
+        }
+        "ellipse" => format!(
+            "<ellipse cx=\"{}\" cy=\"{}\" rx=\"{}\" ry=\"{}\" {}/>",
+            num(x + w / 2.0), num(y + h / 2.0), num(w / 2.0), num(h / 2.0), common
+        ),
+        "diamond" => format!(
+            "<polygon points=\"{},{} {},{} {},{} {},{}\" stroke-linejoin=\"round\" {}/>",
+            num(x + w / 2.0), num(y), num(x + w), num(y + h / 2.0), num(x + w / 2.0), num(y + h), num(x), num(y + h / 2.0), common
+        ),
+        "arrow" | "line" | "freedraw" => {
+            let pts = abs_points(el);
+            if pts.len() < 2 { return String::new(); }
+            let smooth = t == "freedraw" || !matches!(el.get("roundness"), None | Some(Value::Null));
+            let closed = t == "line" && pts.len() > 2 && pts.first() == pts.last();
+            let line_fill = if closed { fill.as_str() } else { "none" };
+            let mut out = format!(
+                "<path d=\"{}\" fill=\"{}\" stroke=\"{}\" stroke-width=\"{}\"{} stroke-linecap=\"round\" stroke-linejoin=\"round\" opacity=\"{}\"/>",
+                path_data(&pts, smooth), line_fill, stroke, num(sw), dash, num(opacity)
+            );
+            if t == "arrow" {
+                if let Some(kind) = el.get("endArrowhead").and_then(|v| v.as_str()) {
+                    out.push_str(&arrowhead_svg(kind, pts[pts.len() - 1], pts[pts.len() - 2], &stroke, sw));
+                }
+                if let Some(kind) = el.get("startArrowhead").and_then(|v| v.as_str()) {
+                    out.push_str(&arrowhead_svg(kind, pts[0], pts[1], &stroke, sw));
+                }
+            }
+            out
+        }
+        "text" => text_svg(el, &stroke, opacity),
+        "image" => {
+            let file_id = s(el, "fileId", "");
+            let href = files
+                .and_then(|m| m.get(file_id))
+                .and_then(|file| file.get("dataURL"))
+                .and_then(|v| v.as_str());
+            match href {
+                Some(href) => format!(
+                    "<image x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" href=\"{}\" xlink:href=\"{}\" preserveAspectRatio=\"xMidYMid meet\" opacity=\"{}\"/>",
+                    num(x), num(y), num(w), num(h), escape_xml(href), escape_xml(href), num(opacity)
+                ),
+                None => String::new(),
+            }
+        }
+        _ => String::new(),
+    };
+
+    let angle = f(el, "angle", 0.0);
+    if angle != 0.0 && !body.is_empty() && !matches!(t, "arrow" | "line" | "freedraw") {
+        format!("<g transform=\"rotate({} {} {})\">{}</g>", num(angle.to_degrees()), num(x + w / 2.0), num(y + h / 2.0), body)
+    } else {
+        body
+    }
+}
+
+/// Render an Excalidraw scene JSON into a standalone SVG document.
+pub fn scene_to_svg(scene: &Value) -> String {
+    let empty = Vec::new();
+    let elements: Vec<&Value> = scene
+        .get("elements")
+        .and_then(|v| v.as_array())
+        .unwrap_or(&empty)
+        .iter()
+        .filter(|el| !el.get("isDeleted").and_then(|v| v.as_bool()).unwrap_or(false))
+        .collect();
+    let files = scene.get("files").and_then(|v| v.as_object());
+    let background = scene
+        .get("appState")
+        .and_then(|a| a.get("viewBackgroundColor"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("#FFFFFF");
+
+    let mut b = (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for el in &elements {
+        if let Some((x0, y0, x1, y1)) = element_bounds(el) {
+            b = (b.0.min(x0), b.1.min(y0), b.2.max(x1), b.3.max(y1));
+        }
+    }
+    if !b.0.is_finite() { b = (0.0, 0.0, 0.0, 0.0); }
+    let min_x = b.0 - EXPORT_PADDING;
+    let min_y = b.1 - EXPORT_PADDING;
+    let width = (b.2 - b.0) + 2.0 * EXPORT_PADDING;
+    let height = (b.3 - b.1) + 2.0 * EXPORT_PADDING;
+
+    let mut patterns = Patterns { defs: String::new(), ids: HashMap::new() };
+    let mut body = String::new();
+    for el in &elements {
+        body.push_str(&element_svg(el, files, &mut patterns));
+        body.push('\n');
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\" version=\"1.1\" width=\"{}\" height=\"{}\" viewBox=\"{} {} {} {}\">",
+        num(width), num(height), num(min_x), num(min_y), num(width), num(height)
+    );
+    if !patterns.defs.is_empty() {
+        let _ = writeln!(out, "<defs>{}</defs>", patterns.defs);
+    }
+    if paint(background) != "none" {
+        let _ = writeln!(
+            out,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+            num(min_x), num(min_y), num(width), num(height), escape_xml(background)
+        );
+    }
+    out.push_str(&body);
+    out.push_str("</svg>\n");
+    out
+}
@@ -0,0 +1,204 @@
+// Text metrics for Excalidraw's three fonts: approximate advance widths (in em) per character,
+// counted per glyph rather than per byte, and greedy word wrapping. Node sizes, label boxes and
+// scene text all measure through here so layout and rendering agree.
+
+/// Line height as a multiple of the font size (Excalidraw's default).
+pub const LINE_HEIGHT: f64 = 1.25;
+/// Font of node and edge labels.
+pub const LABEL_FONT: Font = Font::Virgil;
+/// Font size of node labels.
+pub const NODE_FONT_SIZE: f64 = 16.0;
+/// Font size of edge labels.
+pub const EDGE_FONT_SIZE: f64 = 14.0;
+/// Node labels wrap beyond this text width.
+pub const MAX_NODE_TEXT_WIDTH: f64 = 200.0;
+/// Edge labels wrap beyond this text width.
+pub const MAX_EDGE_TEXT_WIDTH: f64 = 160.0;
+
+/// Excalidraw font families (the `fontFamily` numbers 1, 2 and 3).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Font {
+    Virgil,
+    Helvetica,
+    Cascadia,
+}
+
+impl Font {
+    pub fn from_family(family: i64) -> Font {
+        match family {
+            2 => Font::Helvetica,
+            3 => Font::Cascadia,
+            _ => Font::Virgil,
+        }
+    }
+
+    pub fn family(self) -> i64 {
+        match self {
+            Font::Virgil => 1,
+            Font::Helvetica => 2,
+            Font::Cascadia => 3,
+        }
+    }
+}
+
+// Helvetica advance widths for ' '..='~' in 1/1000 em (Adobe AFM)
+const HELVETICA: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, // ' '..'/'
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, // '0'..'?'
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778, // '@'..'O'
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556, // 'P'..'_'
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556, // '`'..'o'
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584, // 'p'..'~'
+];
+
+// Virgil (hand-drawn) is wider and more even than Helvetica. No metrics table is bundled for it,
+// so these are estimates per character class, not the font's real advance widths.
+fn virgil(c: char) -> f64 {
+    match c {
+        ' ' => 0.36,
+        'i' | 'l' | '|' | '!' | '.' | ',' | ':' | ';' | '\'' | '`' => 0.28,
+        'f' | 'j' | 'r' | 't' | 'I' | '(' | ')' | '[' | ']' | '{' | '}' | '"' => 0.42,
+        'm' | 'w' | 'M' | 'W' | '@' | '%' => 0.88,
+        'A'..='Z' => 0.68,
+        '0'..='9' => 0.6,
+        'a'..='z' => 0.58,
+        _ => 0.5,
+    }
+}
+
+// Zero-width: combining marks, joiners, variation selectors, emoji skin tone modifiers
+fn is_zero_width(c: char) -> bool {
+    matches!(c as u32, 0x0300..=0x036F | 0x200B..=0x200D | 0xFE00..=0xFE0F | 0x1F3FB..=0x1F3FF | 0x20E3)
+}
+
+// Full-width CJK, Hangul and fullwidth forms
+fn is_wide(c: char) -> bool {
+    matches!(c as u32, 0x1100..=0x115F | 0x2E80..=0xA4CF | 0xAC00..=0xD7A3 | 0xF900..=0xFAFF | 0xFE30..=0xFE4F | 0xFF00..=0xFF60 | 0xFFE0..=0xFFE6 | 0x20000..=0x3FFFD)
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(c as u32, 0x2600..=0x27BF | 0x1F000..=0x1FAFF)
+}
+
+/// Advance width of one character in em.
+pub fn char_width(c: char, font: Font) -> f64 {
+    if is_zero_width(c) {
+        0.0
+    } else if is_emoji(c) {
+        1.15
+    } else if is_wide(c) {
+        1.0
+    } else {
+        match font {
+            Font::Cascadia => 0.586,
+            Font::Helvetica => match c {
+                ' '..='~' => HELVETICA[c as usize - 32] as f64 / 1000.0,
+                _ => 0.556,
+            },
+            Font::Virgil => virgil(c),
+        }
+    }
+}
+
+/// Width in pixels of the widest line of `text`.
+pub fn text_width(text: &str, font: Font, size: f64) -> f64 {
+    text.split('\n')
+        .map(|line| {
+            let mut em = 0.0;
+            let mut joined = false;
+            for c in line.chars() {
+                // the glyph after a zero-width joiner is drawn as part of the previous one
+                if !joined { em += char_width(c, font); }
+                joined = c == '\u{200D}';
+            }
+            em * size
+        })
+        .fold(0.0, f64::max)
+}
+
+// Pieces a line may break between: words (split at spaces) and single wide characters;
+// the flag says whether a space separates the piece from the one before
+fn pieces(paragraph: &str) -> Vec<(String, bool)> {
+    let mut out: Vec<(String, bool)> = Vec::new();
+    for (wi, word) in paragraph.split(' ').filter(|w| !w.is_empty()).enumerate() {
+        let mut first = true;
+        let mut run = String::new();
+        for c in word.chars() {
+            if is_wide(c) {
+                if !run.is_empty() {
+                    out.push((std::mem::take(&mut run), first && wi > 0));
+                    first = false;
+                }
+                out.push((c.to_string(), first && wi > 0));
+                first = false;
+            } else {
+                run.push(c);
+            }
+        }
+        if !run.is_empty() { out.push((run, first && wi > 0)); }
+    }
+    out
+}
+
+/// Greedy word wrap to `max_width` pixels; explicit newlines are kept, and words longer than
+/// a line are broken between characters.
+pub fn wrap(text: &str, font: Font, size: f64, max_width: f64) -> Vec<String> {
+    let fits = |s: &str| text_width(s, font, size) <= max_width;
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+        for (piece, spaced) in pieces(paragraph) {
+            let joined = if line.is_empty() { piece.clone() } else { format!("{}{}{}", line, if spaced { " " } else { "" }, piece) };
+            if fits(&joined) {
+                line = joined;
+                continue;
+            }
+            if !line.is_empty() { lines.push(std::mem::take(&mut line)); }
+            for c in piece.chars() {
+                let next = format!("{}{}", line, c);
+                if !line.is_empty() && !fits(&next) {
+                    lines.push(std::mem::replace(&mut line, c.to_string()));
+                } else {
+                    line = next;
+                }
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Wrapped text with its measured size.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextBlock {
+    pub lines: Vec<String>,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl TextBlock {
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+}
+
+/// Wrap (when `max_width` is given) and measure `text`.
+pub fn layout_text(text: &str, font: Font, size: f64, max_width: Option<f64>) -> TextBlock {
+    let lines = match max_width {
+        Some(max) => wrap(text, font, size, max),
+        None => text.split('\n').map(str::to_string).collect(),
+    };
+    let width = lines.iter().map(|l| text_width(l, font, size)).fold(0.0, f64::max);
+    let height = lines.len() as f64 * size * LINE_HEIGHT;
+    TextBlock { lines, width, height }
+}
+
+/// A node label as drawn in scenes.
+pub fn node_label(label: &str) -> TextBlock {
+    layout_text(label, LABEL_FONT, NODE_FONT_SIZE, Some(MAX_NODE_TEXT_WIDTH))
+}
+
+/// An edge label as drawn in scenes.
+pub fn edge_label(label: &str) -> TextBlock {
+    layout_text(label, LABEL_FONT, EDGE_FONT_SIZE, Some(MAX_EDGE_TEXT_WIDTH))
+}
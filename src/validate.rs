@@ -0,0 +1,167 @@
+use crate::state::{GraphData, NodeData, NodeStyle};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use utoipa::ToSchema;
+
+// Structural checks for GraphData as produced by the LLM: the rules the prompt asks for
+// (unique IDs, no dangling or duplicate edges, containers over existing nodes, at most three
+// decorations) are verified here, and `repair` fixes what can be fixed without guessing intent.
+
+/// Most decorations a graph may carry (the prompt asks for at most three).
+pub const MAX_DECORATIONS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticKind {
+    DuplicateNodeId,
+    DuplicateEdgeId,
+    DuplicateEdge,
+    DanglingEdge,
+    SelfLoop,
+    UnknownContainerChild,
+    OverlappingContainers,
+    TooManyDecorations,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    pub severity: Severity,
+    pub subject: String, // id of the offending node, edge or container
+    pub message: String,
+    #[serde(default)]
+    pub repaired: bool,
+}
+
+fn diag(kind: DiagnosticKind, subject: &str, message: String) -> Diagnostic {
+    let severity = match kind {
+        DiagnosticKind::DuplicateNodeId | DiagnosticKind::DuplicateEdgeId | DiagnosticKind::DanglingEdge | DiagnosticKind::UnknownContainerChild => Severity::Error,
+        _ => Severity::Warning,
+    };
+    Diagnostic { kind, severity, subject: subject.to_string(), message, repaired: false }
+}
+
+/// Check `g` against the structural rules; an empty list means the graph is clean.
+pub fn validate(g: &GraphData) -> Vec<Diagnostic> {
+    let mut out = Vec::new();
+    let mut node_ids: HashSet<&str> = HashSet::new();
+    for n in &g.nodes {
+        if !node_ids.insert(n.id.as_str()) {
+            out.push(diag(DiagnosticKind::DuplicateNodeId, &n.id, format!("node id `{}` is used more than once", n.id)));
+        }
+    }
+
+    let mut edge_ids: HashSet<&str> = HashSet::new();
+    let mut connections: HashSet<(&str, &str, &str)> = HashSet::new();
+    for e in &g.edges {
+        if !edge_ids.insert(e.id.as_str()) {
+            out.push(diag(DiagnosticKind::DuplicateEdgeId, &e.id, format!("edge id `{}` is used more than once", e.id)));
+        }
+        if !connections.insert((e.source.as_str(), e.target.as_str(), e.label.as_str())) {
+            out.push(diag(DiagnosticKind::DuplicateEdge, &e.id, format!("edge `{}` repeats {} -> {}", e.id, e.source, e.target)));
+        }
+        for end in [&e.source, &e.target] {
+            if !node_ids.contains(end.as_str()) {
+                out.push(diag(DiagnosticKind::DanglingEdge, &e.id, format!("edge `{}` points to unknown node `{}`", e.id, end)));
+            }
+        }
+        if e.source == e.target && !e.source.is_empty() {
+            out.push(diag(DiagnosticKind::SelfLoop, &e.id, format!("edge `{}` loops on `{}`", e.id, e.source)));
+        }
+    }
+
+    let mut owner: HashMap<&str, &str> = HashMap::new();
+    for c in g.containers.iter().flatten() {
+        for child in &c.children {
+            if !node_ids.contains(child.as_str()) {
+                out.push(diag(DiagnosticKind::UnknownContainerChild, &c.id, format!("container `{}` lists unknown node `{}`", c.id, child)));
+            } else if let Some(first) = owner.get(child.as_str()).filter(|&&o| o != c.id) {
+                out.push(diag(DiagnosticKind::OverlappingContainers, &c.id, format!("node `{}` is in both `{}` and `{}`", child, first, c.id)));
+            } else {
+                owner.insert(child, &c.id);
+            }
+        }
+    }
+
+    let decorations = g.decorations.as_ref().map(|d| d.len()).unwrap_or(0);
+    if decorations > MAX_DECORATIONS {
+        out.push(diag(DiagnosticKind::TooManyDecorations, "", format!("{} decorations (at most {})", decorations, MAX_DECORATIONS)));
+    }
+    out
+}
+
+// "payment_service" -> "Payment service"
+fn label_from_id(id: &str) -> String {
+    let words = id.replace(['_', '-'], " ");
+    let mut chars = words.trim().chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => id.to_string(),
+    }
+}
+
+/// Fix what can be fixed in place: later duplicates of a node are dropped, duplicate edges
+/// removed and clashing edge ids renamed, edges to a missing node get that node synthesized
+/// (edges with no existing endpoint at all are dropped), unknown or already-claimed container
+/// children are removed, and decorations are cut to the limit. Self-loops are left alone.
+/// Returns the diagnostics of the graph as it was, marking the ones that were repaired.
+pub fn repair(g: &mut GraphData) -> Vec<Diagnostic> {
+    let mut found = validate(g);
+
+    let mut seen: HashSet<String> = HashSet::new();
+    g.nodes.retain(|n| seen.insert(n.id.clone()));
+
+    let mut synthesized: Vec<NodeData> = Vec::new();
+    g.edges.retain(|e| {
+        let known = |id: &str| seen.contains(id);
+        if !known(&e.source) && !known(&e.target) { return false; }
+        for end in [&e.source, &e.target] {
+            if !end.is_empty() && !known(end) && !synthesized.iter().any(|n| &n.id == end) {
+                synthesized.push(NodeData { id: end.clone(), label: label_from_id(end), x: 0.0, y: 0.0, style: NodeStyle { shape: "rectangle".into(), color: String::new() } });
+            }
+        }
+        !e.source.is_empty() && !e.target.is_empty()
+    });
+    g.nodes.extend(synthesized);
+
+    let mut connections: HashSet<(String, String, String)> = HashSet::new();
+    g.edges.retain(|e| connections.insert((e.source.clone(), e.target.clone(), e.label.clone())));
+    let mut edge_ids: HashSet<String> = HashSet::new();
+    for e in g.edges.iter_mut() {
+        if !edge_ids.insert(e.id.clone()) {
+            let mut k = 2;
+            while edge_ids.contains(&format!("{}_{}", e.id, k)) { k += 1; }
+            e.id = format!("{}_{}", e.id, k);
+            edge_ids.insert(e.id.clone());
+        }
+    }
+
+    let node_ids: HashSet<String> = g.nodes.iter().map(|n| n.id.clone()).collect();
+    let mut claimed: HashSet<String> = HashSet::new();
+    for c in g.containers.iter_mut().flatten() {
+        c.children.retain(|child| node_ids.contains(child) && claimed.insert(child.clone()));
+    }
+    if let Some(d) = g.decorations.as_mut() { d.truncate(MAX_DECORATIONS); }
+
+    let remaining = validate(g);
+    for d in found.iter_mut() {
+        d.repaired = !remaining.iter().any(|r| r.kind == d.kind && r.subject == d.subject);
+    }
+    found
+}
+
+/// One-line summary for `AiResponse.message`, e.g. "2 issues (1 repaired): ...".
+pub fn summarize(diags: &[Diagnostic]) -> Option<String> {
+    if diags.is_empty() { return None; }
+    let repaired = diags.iter().filter(|d| d.repaired).count();
+    let noun = if diags.len() == 1 { "issue" } else { "issues" };
+    let list: Vec<String> = diags.iter().map(|d| if d.repaired { format!("{} (repaired)", d.message) } else { d.message.clone() }).collect();
+    Some(format!("{} {} ({} repaired): {}", diags.len(), noun, repaired, list.join("; ")))
+}
@@ -0,0 +1,31 @@
+//! Shared helpers for the integration tests.
+#![allow(dead_code)] // each test binary uses a subset
+
+use GraphFlow::flow::create_graph_flow;
+use GraphFlow::state::{AiResponse, AiStatus, ChatInput, InputType, SharedState, UserSession, UserTier};
+use pocketflow_rs::Context;
+use serde_json::{json, Value};
+
+/// Run `create_graph_flow` for a Free-tier text request, with extra context keys (e.g.
+/// `llm_max_retries`, `llm_cassette`), and return the final SharedState.
+pub async fn run_flow(content: &str, context_keys: &[(&str, Value)]) -> SharedState {
+    let state = SharedState {
+        user_session: UserSession { user_id: "test".into(), is_authenticated: false, tier: UserTier::Free, credits_remaining: 100, last_activity: String::new() },
+        chat_input: ChatInput { input_type: InputType::Text, content: content.into(), timestamp: String::new() },
+        ai_response: AiResponse { status: AiStatus::Success, message: None, graph_data: None, credits_cost: 0, diagnostics: Vec::new(), llm_retries: Vec::new() },
+        current_graph: None,
+        payment_info: None,
+    };
+    let mut context = Context::new();
+    context.set("shared_state", json!(state));
+    for (key, value) in context_keys {
+        context.set(key, value.clone());
+    }
+    let result = create_graph_flow().run(context).await.expect("flow runs");
+    serde_json::from_value(result).expect("flow result is the final SharedState")
+}
+
+/// The scene element with `id`; panics when it is missing.
+pub fn element<'a>(scene: &'a Value, id: &str) -> &'a Value {
+    scene["elements"].as_array().unwrap().iter().find(|e| e["id"] == id).unwrap_or_else(|| panic!("missing element {}", id))
+}
@@ -0,0 +1,111 @@
+/// Graphviz DOT Import/Export Tests
+///
+/// Verifies that DOT graphs convert deterministically into GraphData (attributes, rankdir,
+/// clusters as containers) and that `graphdata_to_dot` emits DOT that parses back unchanged.
+use GraphFlow::dot::{graphdata_to_dot, is_dot_graph, parse_dot};
+
+const DOC: &str = r##"
+// generated by infra tooling
+strict digraph "deploy" {
+    rankdir=LR;
+    node [shape=box, style=filled, fillcolor="#E5E7EB"];
+    edge [color=gray];
+
+    subgraph cluster_k8s {
+        label = "Kubernetes";
+        api [label="API\nGateway", shape=box, style="rounded,filled", fillcolor="#60A5FA"];
+        worker [shape=cylinder];
+    }
+    lb [label=<<b>Load</b> Balancer>, shape=diamond];
+    "db primary" [shape=cylinder, fillcolor="#F87171"];
+
+    lb -> api [label="https"];
+    api -> worker -> "db primary" [style=dashed];
+    worker -> { cache; queue } [dir=both];
+    /* health checks */
+    lb -> worker [arrowhead=none, style=bold];
+}
+"##;
+
+#[test]
+fn test_detects_dot_input() {
+    assert!(is_dot_graph(DOC));
+    assert!(is_dot_graph("```dot\ngraph { a -- b }\n```"));
+    assert!(!is_dot_graph("A -> B, B -> C"));
+    assert!(!is_dot_graph("flowchart LR\n  a --> b"));
+}
+
+#[test]
+fn test_parses_attributes_clusters_and_edges() {
+    let g = parse_dot(DOC).expect("dot graph");
+    assert_eq!(g.layout_hints.as_ref().unwrap().direction, "LR");
+
+    let node = |id: &str| g.nodes.iter().find(|n| n.id == id).unwrap_or_else(|| panic!("node {}", id));
+    assert_eq!(node("api").label, "API\nGateway");
+    assert_eq!(node("api").style.shape, "rounded");
+    assert_eq!(node("api").style.color, "#60A5FA");
+    assert_eq!(node("worker").style.shape, "cylinder");
+    assert_eq!(node("worker").style.color, "#E5E7EB", "node defaults apply");
+    assert_eq!(node("lb").label, "Load Balancer");
+    assert_eq!(node("lb").style.shape, "diamond");
+    assert_eq!(node("db primary").style.color, "#F87171");
+    assert_eq!(g.nodes.len(), 6);
+
+    let edge = |s: &str, t: &str| g.edges.iter().find(|e| e.source == s && e.target == t).unwrap_or_else(|| panic!("edge {}->{}", s, t));
+    assert_eq!(edge("lb", "api").label, "https");
+    assert_eq!(edge("lb", "api").style.arrow, "end");
+    assert_eq!(edge("api", "worker").style.line, "dashed");
+    assert_eq!(edge("worker", "db primary").style.line, "dashed");
+    assert_eq!(edge("worker", "cache").style.arrow, "both");
+    assert_eq!(edge("worker", "queue").style.arrow, "both");
+    assert_eq!(edge("lb", "worker").style.arrow, "none");
+    assert_eq!(edge("lb", "worker").style.line, "thick");
+
+    let containers = g.containers.as_ref().expect("cluster");
+    assert_eq!(containers.len(), 1);
+    assert_eq!(containers[0].id, "k8s");
+    assert_eq!(containers[0].label, "Kubernetes");
+    assert_eq!(containers[0].children, vec!["api", "worker"]);
+}
+
+#[test]
+fn test_unclosed_angle_bracket_is_not_a_label() {
+    // Prose reaches `is_dot_graph` on every chat input
+    assert!(!is_dot_graph("is x <"));
+    assert!(!is_dot_graph("price < 5€"));
+    let g = parse_dot("digraph { a [label=<Café>]; a -> b [label=\"< 5€\"]; b -> c < }").expect("graph");
+    assert_eq!(g.nodes[0].label, "Café");
+    assert_eq!(g.edges.len(), 2);
+}
+
+#[test]
+fn test_undirected_graph_has_no_arrowheads() {
+    let g = parse_dot("graph { rankdir=BT; a -- b -- c }").expect("graph");
+    assert_eq!(g.layout_hints.unwrap().direction, "BT");
+    assert_eq!(g.edges.len(), 2);
+    assert!(g.edges.iter().all(|e| e.style.arrow == "none"));
+}
+
+#[test]
+fn test_export_round_trips_through_parser() {
+    let g = parse_dot(DOC).expect("dot graph");
+    let text = graphdata_to_dot(&g);
+
+    assert!(text.starts_with("digraph G {\n    rankdir=LR;\n"));
+    assert!(text.contains("subgraph cluster_k8s {"));
+    assert!(text.contains("\"db primary\" [label=\"db primary\", shape=cylinder"));
+
+    let back = parse_dot(&text).expect("re-parse");
+    assert_eq!(back.layout_hints.unwrap().direction, "LR");
+    assert_eq!(back.nodes.len(), g.nodes.len());
+    assert_eq!(back.edges.len(), g.edges.len());
+    for n in &g.nodes {
+        let m = back.nodes.iter().find(|m| m.id == n.id).expect("node survives");
+        assert_eq!((&m.label, &m.style.shape, &m.style.color), (&n.label, &n.style.shape, &n.style.color));
+    }
+    for e in &g.edges {
+        assert!(back.edges.iter().any(|f| f.source == e.source && f.target == e.target && f.label == e.label && f.style.line == e.style.line && f.style.arrow == e.style.arrow));
+    }
+    let (c, d) = (&g.containers.unwrap()[0], &back.containers.unwrap()[0]);
+    assert_eq!((&c.id, &c.label, &c.children), (&d.id, &d.label, &d.children));
+}
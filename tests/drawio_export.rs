@@ -0,0 +1,68 @@
+/// draw.io Export Tests
+///
+/// Checks that `graphdata_to_drawio_with_opts` produces an uncompressed mxGraphModel with
+/// vertex/edge cells, container-relative child geometry, and decoration cells.
+use GraphFlow::drawio::graphdata_to_drawio_with_opts;
+use GraphFlow::state::{Container, Decoration, EdgeData, EdgeStyle, GraphData, NodeData, NodeStyle};
+
+fn sample_graph() -> GraphData {
+    let node = |id: &str, label: &str, x: f32, y: f32, shape: &str| NodeData {
+        id: id.into(),
+        label: label.into(),
+        x,
+        y,
+        style: NodeStyle { shape: shape.into(), color: "#60A5FA".into() },
+    };
+    GraphData {
+        nodes: vec![
+            node("api", "API & Gateway", 0.0, 0.0, "rounded"),
+            node("db", "DB", 0.0, 180.0, "cylinder"),
+            node("user", "User", -300.0, 0.0, "circle"),
+        ],
+        edges: vec![
+            EdgeData { id: "api_db".into(), source: "api".into(), target: "db".into(), label: "SQL".into(), style: EdgeStyle { line: "dashed".into(), arrow: "both".into() } },
+            EdgeData { id: "user_api".into(), source: "user".into(), target: "api".into(), label: String::new(), style: EdgeStyle { line: "orthogonal".into(), arrow: "end".into() } },
+            EdgeData { id: "dangling".into(), source: "user".into(), target: "nowhere".into(), ..Default::default() },
+        ],
+        containers: Some(vec![Container { id: "backend".into(), label: "Backend".into(), children: vec!["api".into(), "db".into()], style: None }]),
+        decorations: Some(vec![Decoration { r#type: "note".into(), target: Some("db".into()), text: Some("primary".into()), ..Default::default() }]),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_drawio_contains_cells_for_graph() {
+    let xml = graphdata_to_drawio_with_opts(&sample_graph(), false, "");
+
+    assert!(xml.starts_with("<mxfile"));
+    assert!(xml.contains("<mxGraphModel"));
+    assert!(xml.contains("<mxCell id=\"0\"/>"));
+    assert!(xml.contains("<mxCell id=\"1\" parent=\"0\"/>"));
+    // 1 container + 3 nodes + 1 decoration are vertices, the dangling edge is dropped
+    assert_eq!(xml.matches("vertex=\"1\"").count(), 5);
+    assert_eq!(xml.matches("edge=\"1\"").count(), 2);
+    assert!(xml.contains("value=\"API &amp; Gateway\""));
+    assert!(xml.contains("source=\"node-api\" target=\"node-db\""));
+    assert!(xml.contains("dashed=1;startArrow=classic;endArrow=classic;"));
+    assert!(xml.contains("shape=cylinder3;"));
+    assert!(xml.contains("value=\"primary\" style=\"text;"));
+}
+
+#[test]
+fn test_container_children_use_relative_geometry() {
+    let xml = graphdata_to_drawio_with_opts(&sample_graph(), false, "");
+
+    assert!(xml.contains("<mxCell id=\"container-backend\" value=\"Backend\""));
+    let cell = |id: &str| {
+        let start = xml.find(&format!("<mxCell id=\"{}\"", id)).expect("cell present");
+        xml[start..start + xml[start..].find("</mxCell>").unwrap()].to_string()
+    };
+    let api = cell("node-api");
+    assert!(api.contains("parent=\"container-backend\""));
+    // Container padding (40) puts the first child at (40, 40) inside the group
+    assert!(api.contains("x=\"40.0\" y=\"40.0\""));
+    let user = cell("node-user");
+    assert!(user.contains("parent=\"1\""));
+    // Whole drawing is shifted so the left-most cell starts at the margin
+    assert!(user.contains("x=\"40.0\""));
+}
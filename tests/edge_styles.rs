@@ -0,0 +1,78 @@
+/// Edge Style Tests
+///
+/// Verifies that `EdgeStyle.line`/`arrow` words select routing, stroke, and arrowheads in the
+/// Excalidraw emitter, and that the importer reads the same styles back.
+use GraphFlow::edge_style::{EdgeLook, Routing, Stroke};
+use GraphFlow::excalidraw::graphdata_to_excalidraw_scene;
+use GraphFlow::excalidraw_import::excalidraw_scene_to_graphdata;
+use GraphFlow::state::{EdgeData, EdgeStyle, GraphData, NodeData, NodeStyle};
+
+mod common;
+use common::element;
+
+fn style(line: &str, arrow: &str) -> EdgeStyle {
+    EdgeStyle { line: line.into(), arrow: arrow.into() }
+}
+
+fn graph(edges: &[(&str, &str)]) -> GraphData {
+    let node = |id: &str, x: f32, y: f32| NodeData { id: id.into(), label: id.to_uppercase(), x, y, style: NodeStyle { shape: "rectangle".into(), color: String::new() } };
+    GraphData {
+        nodes: vec![node("a", 0.0, 0.0), node("b", 320.0, 200.0)],
+        edges: edges
+            .iter()
+            .enumerate()
+            .map(|(i, (line, arrow))| EdgeData { id: format!("e{}", i), source: "a".into(), target: "b".into(), label: String::new(), style: style(line, arrow) })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_style_words_are_parsed() {
+    let look = EdgeLook::of(&style("smooth", "end"));
+    assert_eq!((look.routing, look.stroke, look.start_head, look.end_head), (Routing::Curved, Stroke::Solid, None, Some("arrow")));
+    let look = EdgeLook::of(&style("curved-dashed", "both triangle"));
+    assert_eq!((look.routing, look.stroke, look.start_head, look.end_head), (Routing::Curved, Stroke::Dashed, Some("triangle"), Some("triangle")));
+    let look = EdgeLook::of(&style("dotted straight thick", "none"));
+    assert_eq!((look.routing, look.stroke, look.start_head, look.end_head), (Routing::Straight, Stroke::Dotted, None, None));
+    assert!(look.width > 2.0);
+    // Defaults: orthogonal, solid, open arrowhead at the target; a bare head type implies "end"
+    let look = EdgeLook::of(&style("", ""));
+    assert_eq!((look.routing, look.stroke, look.end_head), (Routing::Orthogonal, Stroke::Solid, Some("arrow")));
+    assert_eq!(EdgeLook::of(&style("solid", "triangle")).end_head, Some("triangle"));
+    assert_eq!(EdgeLook::of(&style("solid", "start dot")).start_head, Some("dot"));
+}
+
+#[test]
+fn test_emitter_applies_routing_stroke_and_heads() {
+    let edges = [("orthogonal", "end"), ("straight dashed", "start bar"), ("curved dotted", "both triangle"), ("solid", "none")];
+    let scene = graphdata_to_excalidraw_scene(&graph(&edges));
+
+    let ortho = element(&scene, "edge-e0");
+    let pts: Vec<(f64, f64)> = ortho["points"].as_array().unwrap().iter().map(|p| (p[0].as_f64().unwrap(), p[1].as_f64().unwrap())).collect();
+    assert!(pts.len() >= 3 && pts.windows(2).all(|w| w[0].0 == w[1].0 || w[0].1 == w[1].1), "orthogonal path: {:?}", pts);
+    assert!(ortho["roundness"].is_null());
+    assert_eq!((ortho["strokeStyle"].as_str(), ortho["startArrowhead"].as_str(), ortho["endArrowhead"].as_str()), (Some("solid"), None, Some("arrow")));
+
+    let straight = element(&scene, "edge-e1");
+    assert_eq!(straight["points"].as_array().unwrap().len(), 2);
+    assert_eq!((straight["strokeStyle"].as_str(), straight["startArrowhead"].as_str(), straight["endArrowhead"].as_str()), (Some("dashed"), Some("bar"), None));
+
+    let curved = element(&scene, "edge-e2");
+    assert_eq!(curved["roundness"]["type"], 2);
+    assert_eq!((curved["strokeStyle"].as_str(), curved["startArrowhead"].as_str(), curved["endArrowhead"].as_str()), (Some("dotted"), Some("triangle"), Some("triangle")));
+
+    let plain = element(&scene, "edge-e3");
+    assert!(plain["startArrowhead"].is_null() && plain["endArrowhead"].is_null());
+}
+
+#[test]
+fn test_styles_round_trip_through_import() {
+    let edges = [("curved dashed", "both triangle"), ("straight", "start dot"), ("orthogonal", "end"), ("dotted", "none")];
+    let g = graph(&edges);
+    let back = excalidraw_scene_to_graphdata(&graphdata_to_excalidraw_scene(&g)).expect("import");
+    for e in &g.edges {
+        let m = back.edges.iter().find(|m| m.id == e.id).expect("edge id survives");
+        assert_eq!(EdgeLook::of(&m.style), EdgeLook::of(&e.style), "{} keeps its look", e.id);
+    }
+}
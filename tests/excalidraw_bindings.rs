@@ -0,0 +1,88 @@
+/// Excalidraw Binding Tests
+///
+/// Verifies that exported scenes are editable diagrams: arrows are bound to their nodes, nodes
+/// list their bound arrows and label, labels are container text, and the importer follows
+/// those bindings even after elements were moved in the editor.
+use GraphFlow::excalidraw::graphdata_to_excalidraw_scene;
+use GraphFlow::excalidraw_import::excalidraw_scene_to_graphdata;
+use GraphFlow::state::{EdgeData, EdgeStyle, GraphData, NodeData, NodeStyle};
+use serde_json::{json, Value};
+
+mod common;
+use common::element;
+
+fn graph() -> GraphData {
+    let node = |id: &str, shape: &str, x: f32, y: f32| NodeData { id: id.into(), label: id.to_uppercase(), x, y, style: NodeStyle { shape: shape.into(), color: String::new() } };
+    let edge = |s: &str, t: &str, label: &str| EdgeData { id: format!("{}_{}", s, t), source: s.into(), target: t.into(), label: label.into(), style: EdgeStyle { line: "orthogonal".into(), arrow: "end".into() } };
+    GraphData {
+        nodes: vec![node("web", "rectangle", 0.0, 0.0), node("api", "hexagon", 260.0, 0.0), node("db", "cylinder", 260.0, 200.0)],
+        edges: vec![edge("web", "api", "REST"), edge("api", "db", "")],
+        ..Default::default()
+    }
+}
+
+fn bound_ids(el: &Value) -> Vec<&str> {
+    el["boundElements"].as_array().unwrap().iter().filter_map(|b| b["id"].as_str()).collect()
+}
+
+#[test]
+fn test_arrows_and_labels_are_bound() {
+    let scene = graphdata_to_excalidraw_scene(&graph());
+
+    let arrow = element(&scene, "edge-web_api");
+    assert_eq!(arrow["startBinding"]["elementId"], "node-web");
+    assert_eq!(arrow["endBinding"]["elementId"], "node-api");
+    assert!(arrow["endBinding"]["gap"].as_f64().unwrap() > 0.0);
+    assert_eq!(bound_ids(arrow), vec!["edge-label-web_api"]);
+    assert_eq!(element(&scene, "edge-label-web_api")["containerId"], "edge-web_api");
+
+    // Composite shapes bind through their frame, which every arrow and the label point at
+    let api = element(&scene, "node-api");
+    assert_eq!(api["type"], "rectangle");
+    let ids = bound_ids(api);
+    for id in ["node-label-api", "edge-web_api", "edge-api_db"] {
+        assert!(ids.contains(&id), "node-api lists {}", id);
+    }
+    let label = element(&scene, "node-label-api");
+    assert_eq!((label["containerId"].as_str(), label["originalText"].as_str()), (Some("node-api"), Some("API")));
+
+    // Every binding points at an existing element of the right kind
+    for el in scene["elements"].as_array().unwrap() {
+        for key in ["startBinding", "endBinding"] {
+            if let Some(target) = el[key]["elementId"].as_str() {
+                assert!(matches!(element(&scene, target)["type"].as_str(), Some("rectangle" | "ellipse" | "diamond")));
+            }
+        }
+        if let Some(container) = el["containerId"].as_str() {
+            let id = el["id"].as_str().unwrap();
+            assert!(bound_ids(element(&scene, container)).contains(&id), "{} listed by {}", id, container);
+        }
+    }
+}
+
+#[test]
+fn test_import_follows_bindings_after_edits() {
+    let mut scene = graphdata_to_excalidraw_scene(&graph());
+    for el in scene["elements"].as_array_mut().unwrap() {
+        match el["id"].as_str() {
+            // arrow dragged far away from both nodes: the bindings still name them
+            Some("edge-api_db") => {
+                el["x"] = json!(900.0);
+                el["y"] = json!(900.0);
+            }
+            // edge label dropped on top of a node: it stays the arrow's label
+            Some("edge-label-web_api") => {
+                el["x"] = json!(-20.0);
+                el["y"] = json!(-10.0);
+            }
+            _ => {}
+        }
+    }
+    let back = excalidraw_scene_to_graphdata(&scene).expect("import");
+    let web = back.nodes.iter().find(|n| n.id == "web").unwrap();
+    assert_eq!(web.label, "WEB");
+    let e = back.edges.iter().find(|e| e.id == "web_api").unwrap();
+    assert_eq!(e.label, "REST");
+    let e = back.edges.iter().find(|e| e.id == "api_db").unwrap();
+    assert_eq!((e.source.as_str(), e.target.as_str()), ("api", "db"));
+}
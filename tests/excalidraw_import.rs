@@ -0,0 +1,89 @@
+/// Excalidraw Scene Import Tests
+///
+/// Verifies that scenes emitted by `graphdata_to_excalidraw_scene` convert back into the same
+/// GraphData structure, and that hand-drawn scenes (bound labels, loose arrows, background
+/// rectangles) are recognized as nodes, edges, and containers.
+use GraphFlow::excalidraw::graphdata_to_excalidraw_scene;
+use GraphFlow::excalidraw_import::excalidraw_scene_to_graphdata;
+use GraphFlow::state::{Container, EdgeData, EdgeStyle, GraphData, NodeData, NodeStyle};
+use serde_json::json;
+
+#[test]
+fn test_emitted_scene_round_trips() {
+    let node = |id: &str, label: &str, x: f32, y: f32| NodeData {
+        id: id.into(),
+        label: label.into(),
+        x,
+        y,
+        style: NodeStyle { shape: "rectangle".into(), color: "#FDE68A".into() },
+    };
+    let g = GraphData {
+        nodes: vec![node("ingest", "Ingest", 0.0, 0.0), node("index", "Index", 200.0, 0.0), node("query", "Query", 400.0, 140.0)],
+        edges: vec![
+            EdgeData { id: "ingest_index".into(), source: "ingest".into(), target: "index".into(), label: "chunks".into(), style: EdgeStyle { line: "orthogonal".into(), arrow: "end".into() } },
+            EdgeData { id: "index_query".into(), source: "index".into(), target: "query".into(), label: String::new(), style: EdgeStyle { line: "orthogonal".into(), arrow: "end".into() } },
+        ],
+        containers: Some(vec![Container { id: "pipeline".into(), label: "Pipeline".into(), children: vec!["ingest".into(), "index".into()], style: None }]),
+        ..Default::default()
+    };
+
+    let back = excalidraw_scene_to_graphdata(&graphdata_to_excalidraw_scene(&g)).expect("import");
+
+    assert_eq!(back.nodes.len(), 3);
+    for n in &g.nodes {
+        let m = back.nodes.iter().find(|m| m.id == n.id).expect("node id survives");
+        assert_eq!(m.label, n.label);
+        assert_eq!(m.style.color, n.style.color);
+        assert!((m.x - n.x).abs() < 0.5 && (m.y - n.y).abs() < 0.5, "center preserved");
+    }
+    assert_eq!(back.edges.len(), 2);
+    let e = back.edges.iter().find(|e| e.id == "ingest_index").expect("edge id survives");
+    assert_eq!((e.source.as_str(), e.target.as_str(), e.label.as_str()), ("ingest", "index", "chunks"));
+    assert_eq!(e.style.arrow, "end");
+    let c = &back.containers.as_ref().expect("container")[0];
+    assert_eq!((c.id.as_str(), c.label.as_str()), ("pipeline", "Pipeline"));
+    assert_eq!(c.children, vec!["ingest", "index"]);
+    assert_eq!(back.layout_hints.unwrap().direction, "LR");
+}
+
+#[test]
+fn test_hand_drawn_scene_uses_bindings_and_proximity() {
+    let scene = json!({
+        "type": "excalidraw",
+        "elements": [
+            {"type": "rectangle", "id": "bg", "x": -50, "y": -50, "width": 400, "height": 300, "backgroundColor": "#EEF2FF"},
+            {"type": "text", "id": "title", "x": -40, "y": -45, "width": 80, "height": 20, "text": "Cluster"},
+            {"type": "ellipse", "id": "e1", "x": 0, "y": 0, "width": 100, "height": 50, "backgroundColor": "transparent"},
+            {"type": "text", "id": "t1", "x": 10, "y": 10, "width": 80, "height": 20, "text": "Client", "containerId": "e1"},
+            {"type": "diamond", "id": "d1", "x": 0, "y": 150, "width": 100, "height": 60},
+            {"type": "text", "id": "t2", "x": 20, "y": 170, "width": 60, "height": 20, "text": "Auth?"},
+            {"type": "rectangle", "id": "r-far", "x": 600, "y": 0, "width": 100, "height": 50, "isDeleted": true},
+            // bound arrow with a bound label
+            {"type": "arrow", "id": "a1", "x": 50, "y": 50, "points": [[0, 0], [0, 100]],
+             "startBinding": {"elementId": "e1"}, "endBinding": {"elementId": "d1"},
+             "startArrowhead": null, "endArrowhead": "arrow", "strokeStyle": "dashed"},
+            {"type": "text", "id": "t3", "x": 55, "y": 90, "width": 40, "height": 20, "text": "login", "containerId": "a1"},
+            // loose line whose ends land near the shapes
+            {"type": "line", "id": "l1", "x": 110, "y": 180, "points": [[0, 0], [0, -150], [-5, -155]]}
+        ],
+        "appState": {"viewBackgroundColor": "#0B1021"}
+    });
+
+    let g = excalidraw_scene_to_graphdata(&scene).expect("import");
+
+    assert_eq!(g.nodes.len(), 2, "background rect is a container, deleted shapes are ignored");
+    let client = g.nodes.iter().find(|n| n.id == "e1").unwrap();
+    assert_eq!((client.label.as_str(), client.style.shape.as_str(), client.style.color.as_str()), ("Client", "ellipse", ""));
+    assert_eq!(g.nodes.iter().find(|n| n.id == "d1").unwrap().label, "Auth?");
+
+    let a1 = g.edges.iter().find(|e| e.label == "login").expect("bound arrow");
+    assert_eq!((a1.source.as_str(), a1.target.as_str()), ("e1", "d1"));
+    assert_eq!((a1.style.line.as_str(), a1.style.arrow.as_str()), ("dashed", "end"));
+    let l1 = g.edges.iter().find(|e| e.source == "d1").expect("snapped line");
+    assert_eq!((l1.target.as_str(), l1.style.arrow.as_str()), ("e1", "none"));
+
+    let c = &g.containers.as_ref().expect("container")[0];
+    assert_eq!((c.id.as_str(), c.label.as_str()), ("bg", "Cluster"));
+    assert_eq!(c.children, vec!["e1", "d1"]);
+    assert_eq!(g.global_style.unwrap().background, "#0B1021");
+}
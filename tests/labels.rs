@@ -0,0 +1,66 @@
+/// Edge Label Placement Tests
+///
+/// Verifies that `place_labels` moves labels off nodes, arrows, and each other, and that a
+/// label with no free spot is boxed on its own arrow (drawn over a background-filled rectangle
+/// by the Excalidraw emitter and ignored by the importer).
+use GraphFlow::excalidraw::graphdata_to_excalidraw_scene;
+use GraphFlow::excalidraw_import::excalidraw_scene_to_graphdata;
+use GraphFlow::labels::{place_labels, LabelRequest, Placement};
+use GraphFlow::state::{EdgeData, EdgeStyle, GraphData, NodeData, NodeStyle};
+
+fn rect(p: &Placement, req: &LabelRequest) -> (f64, f64, f64, f64) {
+    (p.x - req.w / 2.0, p.y - req.h / 2.0, p.x + req.w / 2.0, p.y + req.h / 2.0)
+}
+
+fn overlaps(a: (f64, f64, f64, f64), b: (f64, f64, f64, f64)) -> bool {
+    a.0 < b.2 && b.0 < a.2 && a.1 < b.3 && b.1 < a.3
+}
+
+#[test]
+fn test_labels_avoid_nodes_and_each_other() {
+    // Two horizontal arrows 30px apart with a node just below the first one's middle
+    let node = (60.0, 8.0, 140.0, 60.0);
+    let paths = vec![vec![(0.0, 0.0), (200.0, 0.0)], vec![(0.0, -30.0), (200.0, -30.0)]];
+    let reqs = [LabelRequest { path: 0, w: 60.0, h: 20.0 }, LabelRequest { path: 1, w: 60.0, h: 20.0 }];
+    let spots = place_labels(&[node], &paths, &reqs);
+
+    assert!(spots.iter().all(|s| !s.boxed));
+    let (a, b) = (rect(&spots[0], &reqs[0]), rect(&spots[1], &reqs[1]));
+    assert!(!overlaps(a, node) && !overlaps(b, node));
+    assert!(!overlaps(a, b), "labels do not stack: {:?} {:?}", a, b);
+    for r in [a, b] {
+        for p in &paths {
+            let y = p[0].1;
+            assert!(!(r.1 < y && y < r.3), "label {:?} is clear of the arrow at y={}", r, y);
+        }
+    }
+    // Unobstructed labels stay centered on their arrow, just below it by default
+    let lone = place_labels(&[], &paths[..1], &reqs[..1]);
+    assert_eq!((lone[0].x, lone[0].boxed), (100.0, false));
+    assert!(lone[0].y > 0.0);
+}
+
+#[test]
+fn test_no_free_spot_falls_back_to_boxed_label() {
+    // A short arrow squeezed between two wide nodes leaves no room beside it
+    let nodes = [(-100.0, -60.0, 100.0, -5.0), (-100.0, 5.0, 100.0, 60.0)];
+    let paths = vec![vec![(0.0, -5.0), (0.0, 5.0)]];
+    let spots = place_labels(&nodes, &paths, &[LabelRequest { path: 0, w: 100.0, h: 20.0 }]);
+    assert!(spots[0].boxed);
+    assert_eq!((spots[0].x, spots[0].y), (0.0, 0.0));
+
+    // The emitter draws the box and the importer still sees one labeled edge
+    let node = |id: &str, y: f32| NodeData { id: id.into(), label: id.into(), x: 0.0, y, style: NodeStyle { shape: "rectangle".into(), color: String::new() } };
+    let g = GraphData {
+        nodes: vec![node("a", 0.0), node("b", 60.0)],
+        edges: vec![EdgeData { id: "ab".into(), source: "a".into(), target: "b".into(), label: "very long label".into(), style: EdgeStyle { line: "orthogonal".into(), arrow: "end".into() } }],
+        ..Default::default()
+    };
+    let scene = graphdata_to_excalidraw_scene(&g);
+    let bg = scene["elements"].as_array().unwrap().iter().find(|e| e["id"] == "edge-label-bg-ab").expect("label background");
+    assert_eq!(bg["backgroundColor"], "#FFFFFF");
+    let back = excalidraw_scene_to_graphdata(&scene).expect("import");
+    assert_eq!(back.nodes.len(), 2);
+    assert_eq!(back.edges.len(), 1);
+    assert_eq!(back.edges[0].label, "very long label");
+}
@@ -0,0 +1,88 @@
+/// Layered Layout Tests
+///
+/// Checks the Sugiyama pipeline behind `LayoutHints.algorithm = "layered"`: ranks follow edges
+/// (also through cycles), crossings are removed where possible, and boxes never overlap.
+use GraphFlow::layout::{approx_node_size, layered_layout};
+use GraphFlow::nodes::apply_auto_layout;
+use GraphFlow::state::{EdgeData, GraphData, LayoutHints, NodeData};
+
+fn graph(nodes: &[&str], edges: &[(&str, &str)]) -> GraphData {
+    GraphData {
+        nodes: nodes.iter().map(|id| NodeData { id: id.to_string(), label: id.to_string(), ..Default::default() }).collect(),
+        edges: edges
+            .iter()
+            .map(|(s, t)| EdgeData { id: format!("{}_{}", s, t), source: s.to_string(), target: t.to_string(), ..Default::default() })
+            .collect(),
+        layout_hints: Some(LayoutHints { direction: "TB".into(), algorithm: "layered".into(), ..Default::default() }),
+        ..Default::default()
+    }
+}
+
+fn pos<'a>(g: &'a GraphData, id: &str) -> &'a NodeData {
+    g.nodes.iter().find(|n| n.id == id).unwrap()
+}
+
+fn crossings(g: &GraphData) -> usize {
+    // Straight segments between centers; count proper intersections
+    let seg = |e: &EdgeData| {
+        let (s, t) = (pos(g, &e.source), pos(g, &e.target));
+        ((s.x as f64, s.y as f64), (t.x as f64, t.y as f64))
+    };
+    let orient = |a: (f64, f64), b: (f64, f64), c: (f64, f64)| ((b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)).signum();
+    let mut count = 0;
+    for (i, e) in g.edges.iter().enumerate() {
+        for f in &g.edges[i + 1..] {
+            if e.source == f.source || e.source == f.target || e.target == f.source || e.target == f.target { continue; }
+            let ((p1, p2), (q1, q2)) = (seg(e), seg(f));
+            if orient(p1, p2, q1) * orient(p1, p2, q2) < 0.0 && orient(q1, q2, p1) * orient(q1, q2, p2) < 0.0 { count += 1; }
+        }
+    }
+    count
+}
+
+#[test]
+fn test_layered_removes_avoidable_crossings() {
+    // Input order forces a crossing for naive placement: a->d, b->c
+    let mut g = graph(&["a", "b", "c", "d"], &[("a", "d"), ("b", "c")]);
+    apply_auto_layout(&mut g, 180.0, 140.0, "TB", 4);
+    assert_eq!(crossings(&g), 0);
+
+    // Two-layer K(3,3)-free bipartite graph that is crossing-free only after reordering
+    let mut g = graph(
+        &["s1", "s2", "s3", "t1", "t2", "t3"],
+        &[("s1", "t3"), ("s2", "t2"), ("s3", "t1"), ("s1", "t2")],
+    );
+    layered_layout(&mut g, 180.0, 140.0, "TB");
+    assert_eq!(crossings(&g), 0);
+}
+
+#[test]
+fn test_layered_ranks_follow_edges_and_cycles() {
+    let mut g = graph(&["a", "b", "c", "d"], &[("a", "b"), ("b", "c"), ("c", "a"), ("a", "d"), ("d", "c")]);
+    layered_layout(&mut g, 180.0, 140.0, "TB");
+    assert!(pos(&g, "a").y < pos(&g, "b").y);
+    assert!(pos(&g, "b").y < pos(&g, "c").y);
+    assert!(pos(&g, "d").y < pos(&g, "c").y);
+
+    let mut lr = graph(&["a", "b"], &[("a", "b")]);
+    layered_layout(&mut lr, 180.0, 140.0, "RL");
+    assert!(pos(&lr, "a").x > pos(&lr, "b").x, "RL mirrors the rank axis");
+}
+
+#[test]
+fn test_layered_boxes_do_not_overlap() {
+    let long = "A very long service name that is wide";
+    let mut g = graph(&["root", long, "short", "x", "y", "z"], &[("root", long), ("root", "short"), ("root", "x"), (long, "y"), ("short", "z"), ("root", "z")]);
+    layered_layout(&mut g, 180.0, 140.0, "TB");
+    for (i, a) in g.nodes.iter().enumerate() {
+        for b in &g.nodes[i + 1..] {
+            let (aw, ah) = approx_node_size(&a.label);
+            let (bw, bh) = approx_node_size(&b.label);
+            let overlap_x = ((a.x - b.x).abs() as f64) < (aw + bw) / 2.0;
+            let overlap_y = ((a.y - b.y).abs() as f64) < (ah + bh) / 2.0;
+            assert!(!(overlap_x && overlap_y), "{} overlaps {}", a.id, b.id);
+        }
+    }
+    // The long edge root -> z spans two ranks and gets routed through a dummy slot
+    assert!(pos(&g, "z").y > pos(&g, "short").y);
+}
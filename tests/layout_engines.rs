@@ -0,0 +1,107 @@
+/// Layout Engine Registry Tests
+///
+/// Verifies that `LayoutHints.algorithm` selects an engine by name or alias, that hinted gaps
+/// override the caller's defaults, and that the size-aware engines produce non-overlapping boxes.
+use GraphFlow::layout::{approx_node_size, engine_for, engines};
+use GraphFlow::nodes::apply_auto_layout;
+use GraphFlow::state::{EdgeData, GraphData, LayoutHints, NodeData};
+
+fn graph(algorithm: &str, nodes: &[&str], edges: &[(&str, &str)]) -> GraphData {
+    GraphData {
+        nodes: nodes.iter().map(|id| NodeData { id: id.to_string(), label: id.to_string(), ..Default::default() }).collect(),
+        edges: edges
+            .iter()
+            .map(|(s, t)| EdgeData { id: format!("{}_{}", s, t), source: s.to_string(), target: t.to_string(), ..Default::default() })
+            .collect(),
+        layout_hints: Some(LayoutHints { direction: "TB".into(), algorithm: algorithm.into(), ..Default::default() }),
+        ..Default::default()
+    }
+}
+
+fn pos(g: &GraphData, id: &str) -> (f32, f32) {
+    let n = g.nodes.iter().find(|n| n.id == id).unwrap();
+    (n.x, n.y)
+}
+
+fn assert_no_overlaps(g: &GraphData) {
+    for (i, a) in g.nodes.iter().enumerate() {
+        for b in &g.nodes[i + 1..] {
+            let (aw, ah) = approx_node_size(&a.label);
+            let (bw, bh) = approx_node_size(&b.label);
+            let overlap_x = ((a.x - b.x).abs() as f64) < (aw + bw) / 2.0;
+            let overlap_y = ((a.y - b.y).abs() as f64) < (ah + bh) / 2.0;
+            assert!(!(overlap_x && overlap_y), "{} overlaps {}", a.id, b.id);
+        }
+    }
+}
+
+const NODES: &[&str] = &["root", "Authentication Service", "b", "c", "d", "e", "f", "island"];
+const EDGES: &[(&str, &str)] = &[("root", "Authentication Service"), ("root", "b"), ("root", "c"), ("Authentication Service", "d"), ("b", "e"), ("b", "f"), ("c", "f")];
+
+#[test]
+fn test_registry_resolves_names_and_aliases() {
+    let names: Vec<&str> = engines().iter().map(|e| e.name()).collect();
+    assert_eq!(names, vec!["longest_path", "layered", "force", "radial", "grid", "tree", "preserve"]);
+    assert_eq!(engine_for("dagre").unwrap().name(), "layered");
+    assert_eq!(engine_for("Force-Directed").unwrap().name(), "force");
+    assert_eq!(engine_for("manual").unwrap().name(), "preserve");
+    assert!(engine_for("spiral").is_none());
+}
+
+#[test]
+fn test_size_aware_engines_avoid_overlaps() {
+    // longest_path keeps its historical fixed pitch (and preserve falls back to it for 0,0 input)
+    for engine in engines().iter().filter(|e| !matches!(e.name(), "longest_path" | "preserve")) {
+        let mut g = graph(engine.name(), NODES, EDGES);
+        apply_auto_layout(&mut g, 180.0, 140.0, "TB", 4);
+        assert_no_overlaps(&g);
+    }
+    // Unknown names fall back to longest_path
+    let mut unknown = graph("spiral", NODES, EDGES);
+    let mut default = graph("longest_path", NODES, EDGES);
+    apply_auto_layout(&mut unknown, 180.0, 140.0, "TB", 4);
+    apply_auto_layout(&mut default, 180.0, 140.0, "TB", 4);
+    assert_eq!(unknown.nodes.iter().map(|n| (n.x, n.y)).collect::<Vec<_>>(), default.nodes.iter().map(|n| (n.x, n.y)).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_hinted_gaps_override_defaults() {
+    let mut g = graph("longest_path", &["a", "b"], &[("a", "b")]);
+    apply_auto_layout(&mut g, 180.0, 140.0, "TB", 4);
+    assert_eq!(pos(&g, "b").1 - pos(&g, "a").1, 180.0);
+
+    let hints = g.layout_hints.as_mut().unwrap();
+    hints.rank_sep = Some(300.0);
+    apply_auto_layout(&mut g, 180.0, 140.0, "TB", 4);
+    assert_eq!(pos(&g, "b").1 - pos(&g, "a").1, 300.0);
+
+    // max_per_rank wraps a wide rank into rows
+    let mut grid = graph("grid", &["a", "b", "c", "d", "e", "f"], &[]);
+    grid.layout_hints.as_mut().unwrap().max_per_rank = Some(3);
+    apply_auto_layout(&mut grid, 180.0, 140.0, "TB", 4);
+    let rows: std::collections::BTreeSet<i64> = grid.nodes.iter().map(|n| n.y as i64).collect();
+    assert_eq!(rows.len(), 2);
+}
+
+#[test]
+fn test_tree_radial_and_preserve_shapes() {
+    let mut tree = graph("tree", NODES, EDGES);
+    apply_auto_layout(&mut tree, 180.0, 140.0, "LR", 4);
+    assert!(pos(&tree, "root").0 < pos(&tree, "b").0 && pos(&tree, "b").0 < pos(&tree, "e").0);
+    let (e, f) = (pos(&tree, "e").1, pos(&tree, "f").1);
+    assert!((pos(&tree, "b").1 - (e + f) / 2.0).abs() < 0.5, "parent centered over its children");
+
+    let mut radial = graph("radial", NODES, EDGES);
+    apply_auto_layout(&mut radial, 180.0, 140.0, "TB", 4);
+    assert_eq!(pos(&radial, "root"), (0.0, 0.0));
+    let dist = |p: (f32, f32)| (p.0 * p.0 + p.1 * p.1).sqrt();
+    let ring1 = dist(pos(&radial, "b"));
+    assert!((dist(pos(&radial, "c")) - ring1).abs() < 0.5);
+    assert!(dist(pos(&radial, "e")) > ring1);
+
+    let mut kept = graph("preserve", &["a", "b"], &[("a", "b")]);
+    kept.nodes[0].x = 10.0;
+    kept.nodes[1].y = 400.0;
+    apply_auto_layout(&mut kept, 180.0, 140.0, "TB", 4);
+    assert_eq!((pos(&kept, "a"), pos(&kept, "b")), ((10.0, 0.0), (0.0, 400.0)));
+}
@@ -0,0 +1,104 @@
+/// LLM Cassette Tests
+///
+/// Verifies record / replay / strict cassette modes, and replays the golden cassettes in
+/// `tests/golden` through the full pipeline. When the prompt template changes, strict replay
+/// fails; re-record with `GRAPHFLOW_GOLDEN=record cargo test --test llm_cassette` (real API keys,
+/// or `GRAPHFLOW_LLM_PROVIDER=mock`) and review the cassette and `.graph.json` diffs.
+use GraphFlow::cassette::{Cassette, CassetteConfig, CassetteMode, CassetteProvider};
+use GraphFlow::llm::{LlmProvider, LlmRequest, CONFIG_ENV};
+use GraphFlow::mock_llm::MockProvider;
+use GraphFlow::state::SharedState;
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::Arc;
+
+mod common;
+
+fn request(model: &str, prompt: &str) -> LlmRequest {
+    LlmRequest { system: String::new(), prompt: prompt.into(), model: model.into(), max_tokens: 1024, temperature: None, schema: None }
+}
+
+fn cassette(path: &Path, mode: CassetteMode, inner: Result<Arc<dyn LlmProvider>, String>) -> CassetteProvider {
+    CassetteProvider { config: CassetteConfig { path: path.to_path_buf(), mode }, provider: "mock".into(), inner }
+}
+
+#[tokio::test]
+async fn test_record_replay_and_strict() {
+    let path = std::env::temp_dir().join(format!("graphflow-cassette-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let mock = || -> Result<Arc<dyn LlmProvider>, String> { Ok(Arc::new(MockProvider)) };
+    let offline = || -> Result<Arc<dyn LlmProvider>, String> { Err("no API key".into()) };
+
+    let recorder = cassette(&path, CassetteMode::Record, mock());
+    let reply = recorder.complete(&request("m1", "USER_INPUT\nA -> B")).await.unwrap();
+    recorder.complete(&request("m1", "USER_INPUT\nA -> B")).await.unwrap();
+    let recorded = Cassette::load(&path).unwrap();
+    assert_eq!(recorded.interactions.len(), 1, "re-recording replaces the entry");
+    assert_eq!(recorded.find("m1", "USER_INPUT\nA -> B").unwrap().reply, reply);
+
+    // Strict replay matches model + prompt exactly and never reaches the provider
+    let strict = cassette(&path, CassetteMode::Strict, offline());
+    assert_eq!(strict.complete(&request("m1", "USER_INPUT\nA -> B")).await.unwrap(), reply);
+    let mut streamed = String::new();
+    strict.stream(&request("m1", "USER_INPUT\nA -> B"), &mut |d: &str| streamed.push_str(d)).await.unwrap();
+    assert_eq!(streamed, reply);
+    for miss in [request("m2", "USER_INPUT\nA -> B"), request("m1", "USER_INPUT\nA -> C")] {
+        let err = strict.complete(&miss).await.unwrap_err();
+        assert!(err.contains("Unrecorded LLM call") && err.contains(&format!("`{}`", miss.model)), "{}", err);
+    }
+
+    // Replay passes new calls through and records them
+    let replay = cassette(&path, CassetteMode::Replay, mock());
+    replay.complete(&request("m1", "USER_INPUT\nA -> C")).await.unwrap();
+    assert_eq!(Cassette::load(&path).unwrap().interactions.len(), 2);
+    let err = cassette(&path, CassetteMode::Replay, offline()).complete(&request("m1", "USER_INPUT\nX")).await.unwrap_err();
+    assert_eq!(err, "no API key");
+
+    assert!(CassetteMode::parse("rewind").is_err());
+    let _ = std::fs::remove_file(&path);
+}
+
+async fn run(content: &str, cassette: &Path, mode: &str) -> SharedState {
+    common::run_flow(content, &[("llm_cassette", json!(cassette.to_string_lossy())), ("llm_cassette_mode", json!(mode))]).await
+}
+
+#[tokio::test]
+async fn test_golden_cassettes() {
+    let golden = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    std::env::set_var(CONFIG_ENV, golden.join("routes.json"));
+    let recording = std::env::var("GRAPHFLOW_GOLDEN").as_deref() == Ok("record");
+    let cases = [
+        ("checkout", "Customer -> Checkout page -> Payment approved?\nPayment approved? -> Orders database\nPayment approved? -> Checkout page"),
+        ("login_sequence", ":sequence\nBrowser -> API: POST /login\nAPI -> User store: find user\nUser store -> API: user\nAPI -> Browser: session cookie"),
+    ];
+    for (name, content) in cases {
+        let state = run(content, &golden.join(format!("{}.cassette.json", name)), if recording { "record" } else { "strict" }).await;
+        let ai = &state.ai_response;
+        let graph = serde_json::to_value(ai.graph_data.as_ref().unwrap_or_else(|| panic!("{}: {:?}", name, ai.message))).unwrap();
+        let expected_path = golden.join(format!("{}.graph.json", name));
+        if recording {
+            std::fs::write(&expected_path, serde_json::to_string_pretty(&graph).unwrap() + "\n").unwrap();
+            continue;
+        }
+        let expected: Value = serde_json::from_str(&std::fs::read_to_string(&expected_path).unwrap()).unwrap();
+        assert_eq!(graph, expected, "{} differs from its golden output; re-record with GRAPHFLOW_GOLDEN=record", name);
+    }
+
+    // An unrecorded prompt fails the run instead of falling back to the heuristic parser
+    let state = run("Something new -> Never recorded", &golden.join("checkout.cassette.json"), "strict").await;
+    assert!(state.ai_response.graph_data.is_none());
+    assert!(state.ai_response.message.unwrap().contains("Unrecorded LLM call"));
+    std::env::remove_var(CONFIG_ENV);
+}
+
+#[tokio::test]
+async fn test_imports_ignore_the_cassette() {
+    // Mermaid/DOT sources never reach the LLM, so a bad cassette mode cannot fail them
+    let missing = std::env::temp_dir().join("graphflow-no-such.cassette.json");
+    let state = run("flowchart LR\n  a --> b", &missing, "rewind").await;
+    assert_eq!(state.ai_response.graph_data.expect("imported").edges.len(), 1);
+    assert_eq!(state.ai_response.credits_cost, 0);
+
+    let state = run("Order -> Ship", &missing, "rewind").await;
+    assert!(state.ai_response.graph_data.is_none());
+}
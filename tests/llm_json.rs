@@ -0,0 +1,78 @@
+/// LLM JSON Extraction Tests
+///
+/// Verifies that GraphData survives the usual ways model replies deviate from strict JSON:
+/// markdown fences, surrounding prose, trailing commas, single quotes and bare keys,
+/// truncation, and loosely-typed values.
+use GraphFlow::llm_json::{outermost_object, parse_graph_data, repair_json};
+
+const STRICT: &str = r##"{"nodes":[{"id":"a","label":"A","x":0,"y":0,"style":{"shape":"rectangle","color":"#fff"}},{"id":"b","label":"B","x":0,"y":0,"style":{"shape":"diamond","color":"#fff"}}],"edges":[{"id":"a_b","source":"a","target":"b","label":"","style":{"line":"orthogonal","arrow":"end"}}]}"##;
+
+#[test]
+fn test_fences_and_prose() {
+    let fenced = format!("Here is the diagram you asked for:\n\n```json\n{}\n```\n\nLet me know if you want changes {{like colors}}.", STRICT);
+    let g = parse_graph_data(&fenced).expect("fenced reply");
+    assert_eq!((g.nodes.len(), g.edges.len()), (2, 1));
+
+    let prose = format!("Sure! {} Hope this helps.", STRICT);
+    assert_eq!(parse_graph_data(&prose).expect("inline reply").nodes[1].style.shape, "diamond");
+
+    assert_eq!(outermost_object("x {\"a\": \"}\"} y"), Some("{\"a\": \"}\"}"));
+    assert!(parse_graph_data("I cannot draw that.").is_err());
+}
+
+#[test]
+fn test_objects_without_nodes_are_rejected() {
+    for reply in ["{}", r#"{"error": "rate limited"}"#, r#"Sure! {"nodes": []}"#, r#"{"nodes": [], "edges": []}"#, r#"{"graph_data": {"edges": []}}"#] {
+        let err = parse_graph_data(reply).expect_err(reply);
+        assert!(err.contains("no `nodes`"), "{}: {}", reply, err);
+    }
+}
+
+#[test]
+fn test_lexical_repairs() {
+    let sloppy = r##"{
+        // nodes first
+        nodes: [
+            {id: 'start', label: 'Don\'t "panic"', x: 0, y: 0, style: {shape: 'circle', color: '#FFF'},},
+            {'id': 'end', 'label': 'End', 'x': 1e2, 'y': -5, 'style': {'shape': 'circle', 'color': ''}},
+        ],
+        edges: [{id: 'e', source: 'start', target: 'end', label: None, style: {line: 'straight', arrow: 'end'}},],
+        containers: null,
+    }"##;
+    let g = parse_graph_data(sloppy).expect("repaired reply");
+    assert_eq!(g.nodes[0].label, "Don't \"panic\"");
+    assert_eq!(g.nodes[1].x, 100.0);
+    assert_eq!(g.nodes[1].y, -5.0);
+    assert_eq!(g.edges[0].label, "");
+    assert!(g.containers.is_none());
+
+    // A reply cut off mid-object keeps everything before the cut
+    let truncated = r#"{"nodes": [{"id": "a", "label": "A", "x": 0, "y": 0}, {"id": "b", "lab"#;
+    let g = parse_graph_data(truncated).expect("truncated reply");
+    assert_eq!(g.nodes.iter().map(|n| n.id.as_str()).collect::<Vec<_>>(), vec!["a", "b"]);
+    assert_eq!(g.nodes[1].label, "b");
+    assert_eq!(repair_json("{\"a\": [1, 2,"), "{\"a\": [1, 2]}");
+}
+
+#[test]
+fn test_loose_values_map_onto_defaults() {
+    let loose = r#"{"graph_data": {
+        "nodes": [{"id": 1, "label": "One", "x": "120", "y": "40px"}, {"id": "two"}],
+        "edges": [{"from": 1, "to": "two"}],
+        "layout_hints": {"direction": "LR", "max_per_rank": "4"},
+        "decorations": {"type": "note", "text": "hi", "size": {"w": "20", "h": 20}},
+        "sequence": "n/a"
+    }}"#;
+    let g = parse_graph_data(loose).expect("loose reply");
+    assert_eq!((g.nodes[0].id.as_str(), g.nodes[0].x, g.nodes[0].y), ("1", 120.0, 40.0));
+    assert_eq!(g.nodes[1].label, "two", "missing label falls back to the id");
+    assert_eq!(g.nodes[1].style.shape, "", "missing style gets defaults");
+    let e = &g.edges[0];
+    assert_eq!((e.id.as_str(), e.source.as_str(), e.target.as_str()), ("1_two", "1", "two"));
+    let hints = g.layout_hints.as_ref().unwrap();
+    assert_eq!((hints.direction.as_str(), hints.algorithm.as_str(), hints.max_per_rank), ("LR", "", Some(4)));
+    let decorations = g.decorations.as_ref().unwrap();
+    assert_eq!(decorations.len(), 1);
+    assert_eq!(decorations[0].size.as_ref().unwrap().w, 20.0);
+    assert!(g.sequence.is_none(), "malformed optional sections are dropped");
+}
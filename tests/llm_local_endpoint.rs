@@ -0,0 +1,131 @@
+/// OpenAI-Compatible Endpoint Tests
+///
+/// Runs the `local` provider against a stub `/v1/chat/completions` server: JSON mode is requested
+/// and dropped only when the endpoint rejects it (not on rate limits), routes with their own
+/// `base_url` get separate clients, and `GRAPHFLOW_LLM_BASE_URL` points the built-in Free and Pro
+/// routes at the self-hosted model.
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use GraphFlow::llm::{default_config, provider, LlmProvider, LlmRequest, OpenAiCompatibleProvider, Route, RouteKey, Routed, BASE_URL_ENV};
+use GraphFlow::state::UserTier;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+mod common;
+
+const REPLY: &str = r#"{"nodes":[{"id":"notes","label":"Internal notes"},{"id":"diagram","label":"Diagram"}],"edges":[{"source":"notes","target":"diagram"}]}"#;
+
+#[derive(Clone)]
+struct Stub {
+    json_mode: bool,
+    rate_limited: Arc<AtomicUsize>, // requests left to answer with 429
+    seen: Arc<Mutex<Vec<Value>>>,
+}
+
+async fn completions(State(stub): State<Stub>, Json(body): Json<Value>) -> Response {
+    stub.seen.lock().unwrap().push(body.clone());
+    if stub.rate_limited.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1)).is_ok() {
+        // `insufficient_quota` is not retried by the client, so the error reaches the provider
+        let error = json!({"error": {"message": "You exceeded your current quota", "type": "insufficient_quota", "param": null, "code": "insufficient_quota"}});
+        return (StatusCode::TOO_MANY_REQUESTS, Json(error)).into_response();
+    }
+    if body.get("response_format").is_some() && !stub.json_mode {
+        let error = json!({"error": {"message": "response_format is not supported", "type": "invalid_request_error", "param": null, "code": null}});
+        return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+    }
+    Json(json!({
+        "id": "chatcmpl-stub", "object": "chat.completion", "created": 0, "model": body["model"],
+        "choices": [{"index": 0, "message": {"role": "assistant", "content": REPLY}, "finish_reason": "stop"}]
+    }))
+    .into_response()
+}
+
+// Base URL of a stub server, and the request bodies it receives
+async fn serve(json_mode: bool, rate_limited: usize) -> (String, Arc<Mutex<Vec<Value>>>) {
+    let stub = Stub { json_mode, rate_limited: Arc::new(AtomicUsize::new(rate_limited)), seen: Arc::new(Mutex::new(Vec::new())) };
+    let seen = stub.seen.clone();
+    let app = Router::new().route("/v1/chat/completions", post(completions)).with_state(stub);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (format!("http://{}/v1/", addr), seen)
+}
+
+#[tokio::test]
+async fn test_json_mode_falls_back_when_rejected() {
+    let (url, seen) = serve(false, 0).await;
+    let provider = OpenAiCompatibleProvider::new(&url, "", true);
+    let req = LlmRequest { system: "sys".into(), prompt: "draw".into(), model: "llama3.1".into(), max_tokens: 512, temperature: None, schema: None };
+    assert_eq!(provider.complete(&req).await.unwrap(), REPLY);
+    assert_eq!(provider.complete(&req).await.unwrap(), REPLY);
+
+    let seen = seen.lock().unwrap();
+    let formats: Vec<Option<&Value>> = seen.iter().map(|b| b.get("response_format")).collect();
+    assert_eq!(formats, vec![Some(&json!({"type": "json_object"})), None, None], "rejected once, then remembered");
+    assert_eq!(seen[0]["max_tokens"], 512);
+    assert!(seen[0].get("max_completion_tokens").is_none());
+    assert_eq!(seen[0]["messages"][0], json!({"role": "system", "content": "sys"}));
+}
+
+#[tokio::test]
+async fn test_rate_limit_keeps_json_mode() {
+    let (url, seen) = serve(true, 2).await;
+    let provider = OpenAiCompatibleProvider::new(&url, "", true);
+    let req = LlmRequest { system: "sys".into(), prompt: "draw".into(), model: "llama3.1".into(), max_tokens: 512, temperature: None, schema: None };
+    assert!(provider.complete(&req).await.unwrap_err().contains("quota"));
+    assert!(provider.stream(&req, &mut |_: &str| {}).await.unwrap_err().contains("quota"));
+    assert_eq!(provider.complete(&req).await.unwrap(), REPLY);
+    assert!(provider.capabilities().structured_output, "JSON mode stays on");
+
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.len(), 3, "no retry without response_format");
+    assert!(seen.iter().all(|b| b["response_format"]["type"] == "json_object"));
+}
+
+#[tokio::test]
+async fn test_route_base_urls_get_their_own_clients() {
+    let (first_url, first) = serve(true, 0).await;
+    let (second_url, second) = serve(true, 0).await;
+    let route: Route = serde_json::from_value(json!({"provider": "local", "model": "m", "base_url": first_url})).unwrap();
+    assert_eq!(route.base_url.as_deref(), Some(first_url.as_str()));
+
+    let a = provider("local", route.base_url.as_deref()).unwrap();
+    let b = provider("local", Some(&second_url)).unwrap();
+    assert!(Arc::ptr_eq(&a, &provider("local", Some(first_url.trim_end_matches('/'))).unwrap()), "one client per endpoint");
+    assert!(!Arc::ptr_eq(&a, &b));
+    let req = LlmRequest { system: "sys".into(), prompt: "draw".into(), model: "m".into(), max_tokens: 512, temperature: None, schema: None };
+    a.complete(&req).await.unwrap();
+    b.complete(&req).await.unwrap();
+    b.complete(&req).await.unwrap();
+    assert_eq!((first.lock().unwrap().len(), second.lock().unwrap().len()), (1, 2));
+}
+
+#[tokio::test]
+async fn test_tiers_route_to_base_url() {
+    let (url, seen) = serve(true, 0).await;
+    std::env::set_var(BASE_URL_ENV, &url);
+    std::env::set_var("GRAPHFLOW_LLM_MODEL", "qwen2.5");
+    assert!(default_config().routes.iter().all(|r| r.provider == "local" && r.model == "qwen2.5"));
+
+    // The pipeline (authentication currently assigns every session the Free tier)
+    let result = common::run_flow("Summarize our internal notes", &[]).await;
+    let ids: Vec<String> = result.ai_response.graph_data.expect("graph").nodes.into_iter().map(|n| n.id).collect();
+    assert_eq!(ids, vec!["notes", "diagram"]);
+
+    // The Pro route reaches the same endpoint with its own limits
+    let pro = Routed::resolve(&RouteKey { tier: &UserTier::Pro, kind: "flow", route: None }).unwrap();
+    assert_eq!(pro.complete("draw", None, None).await.unwrap(), REPLY);
+
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.len(), 2);
+    assert!(seen.iter().all(|b| b["model"] == "qwen2.5"));
+    let formats: Vec<&Value> = seen.iter().map(|b| &b["response_format"]["type"]).collect();
+    assert_eq!(formats, vec!["json_schema", "json_object"], "generation sends the GraphData schema");
+    assert_eq!((seen[0]["max_tokens"].as_u64(), seen[1]["max_tokens"].as_u64()), (Some(1024), Some(4096)), "route limits per tier");
+    std::env::remove_var(BASE_URL_ENV);
+    std::env::remove_var("GRAPHFLOW_LLM_MODEL");
+}
@@ -0,0 +1,68 @@
+/// LLM Routing Tests
+///
+/// Verifies the routing table: the built-in Free/Pro defaults, config files selecting by tier,
+/// diagram kind, or explicit route name, and provider resolution errors.
+use GraphFlow::llm::{default_config, load_config, LlmConfig, RouteError, RouteKey, Routed, CONFIG_ENV};
+use GraphFlow::state::UserTier;
+
+mod common;
+
+const CONFIG: &str = r#"{
+  "routes": [
+    {"tier": "pro", "kind": "sequence", "provider": "anthropic", "model": "claude-sonnet", "temperature": 0.1},
+    {"tier": "pro", "provider": "openai", "model": "gpt-4o", "max_tokens": 4096},
+    {"provider": "anthropic", "model": "claude-haiku"},
+    {"name": "fast", "provider": "openai", "model": "gpt-4o-mini", "max_tokens": 512}
+  ]
+}"#;
+
+#[test]
+fn test_route_selection() {
+    let defaults = default_config();
+    let route = |c: &LlmConfig, tier: &UserTier, kind: &str, name: Option<&str>| c.route_for(&RouteKey { tier, kind, route: name }).map(|r| (r.provider.clone(), r.model.clone()));
+    assert_eq!(route(&defaults, &UserTier::Free, "flow", None).unwrap().0, "anthropic");
+    assert_eq!(route(&defaults, &UserTier::Pro, "flow", None).unwrap().0, "openai");
+
+    let config: LlmConfig = serde_json::from_str(CONFIG).unwrap();
+    assert_eq!(route(&config, &UserTier::Pro, "sequence", None).unwrap(), ("anthropic".into(), "claude-sonnet".into()));
+    assert_eq!(route(&config, &UserTier::Pro, "flow", None).unwrap(), ("openai".into(), "gpt-4o".into()));
+    assert_eq!(route(&config, &UserTier::Free, "sequence", None).unwrap(), ("anthropic".into(), "claude-haiku".into()));
+    // Routes after a catch-all are reachable by name only
+    assert_eq!(route(&config, &UserTier::Free, "flow", Some("fast")).unwrap(), ("openai".into(), "gpt-4o-mini".into()));
+    assert_eq!(route(&config, &UserTier::Free, "flow", Some("missing")).unwrap().1, "claude-haiku", "unknown names fall back to tier/kind");
+    assert_eq!(config.routes[2].max_tokens, 1024, "max_tokens defaults");
+    assert_eq!(config.routes[0].temperature, Some(0.1));
+}
+
+#[tokio::test]
+async fn test_config_file_and_provider_resolution() {
+    let path = std::env::temp_dir().join(format!("graphflow-llm-{}.json", std::process::id()));
+    std::fs::write(&path, r#"{"routes": [{"provider": "nonesuch", "model": "m"}]}"#).unwrap();
+    std::env::set_var(CONFIG_ENV, &path);
+    let config = load_config().expect("config file");
+    assert_eq!(config.routes[0].provider, "nonesuch");
+    let err = Routed::resolve(&RouteKey { tier: &UserTier::Free, kind: "flow", route: None }).err().expect("unknown provider");
+    assert!(matches!(&err, RouteError::Invalid(e) if e.contains("nonesuch")), "{}", err);
+
+    // Config and route errors fail the request with the reason, free of charge
+    let state = common::run_flow("Order -> Ship", &[]).await;
+    assert!(state.ai_response.graph_data.is_none());
+    assert!(state.ai_response.message.as_deref().unwrap_or_default().contains("Unknown LLM provider `nonesuch`"), "{:?}", state.ai_response.message);
+    assert_eq!((state.ai_response.credits_cost, state.user_session.credits_remaining), (0, 100));
+
+    std::fs::write(&path, "{not json").unwrap();
+    assert!(load_config().unwrap_err().contains("Invalid LLM config"));
+    let state = common::run_flow("Order -> Ship", &[]).await;
+    assert!(state.ai_response.message.as_deref().unwrap_or_default().contains("Invalid LLM config"), "{:?}", state.ai_response.message);
+
+    // Only a provider without an endpoint or key falls back to the heuristic parser, also free
+    std::fs::write(&path, r#"{"routes": [{"provider": "local", "model": "m"}]}"#).unwrap();
+    let err = Routed::resolve(&RouteKey { tier: &UserTier::Free, kind: "flow", route: None }).err().expect("no endpoint");
+    assert!(matches!(err, RouteError::Unconfigured(_)), "{}", err);
+    let state = common::run_flow("Order -> Ship", &[]).await;
+    assert_eq!(state.ai_response.graph_data.expect("heuristic graph").edges.len(), 1);
+    assert_eq!((state.ai_response.credits_cost, state.user_session.credits_remaining), (0, 100));
+    std::env::remove_var(CONFIG_ENV);
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(load_config().unwrap().routes.len(), default_config().routes.len());
+}
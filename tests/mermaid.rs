@@ -0,0 +1,116 @@
+/// Mermaid Flowchart Import/Export Tests
+///
+/// Verifies that Mermaid flowchart text converts deterministically into GraphData:
+/// shapes, edge labels and link styles, subgraphs as containers, and classDef colors;
+/// and that `graphdata_to_mermaid` emits text that parses back to the same graph.
+use GraphFlow::mermaid::{graphdata_to_mermaid, is_mermaid_flowchart, parse_mermaid_flowchart};
+
+const DOC: &str = r#"
+Some prose before the diagram.
+
+```mermaid
+flowchart LR
+    %% comment
+    start([Start]) --> check{Valid?}
+    check -->|yes| store[(Database)]
+    check -- no --> fix(Fix input) -.-> check
+    a & b ==> c
+    subgraph backend [Backend Services]
+        store
+        api((API)):::hot
+    end
+    classDef hot fill:#F87171,stroke:#333
+    style fix fill:#FDE68A
+```
+"#;
+
+#[test]
+fn test_detects_fenced_and_bare_flowcharts() {
+    assert!(is_mermaid_flowchart(DOC));
+    assert!(is_mermaid_flowchart("graph TD\nA-->B"));
+    assert!(!is_mermaid_flowchart("graph of our sales funnel: Leads -> Sales"));
+    assert!(!is_mermaid_flowchart("A -> B, B -> C"));
+}
+
+#[test]
+fn test_parses_one_line_flowcharts() {
+    assert!(is_mermaid_flowchart("graph TD; A-->B"));
+    let g = parse_mermaid_flowchart("flowchart LR; A-->B; B-->C").expect("one-line flowchart");
+    assert_eq!(g.layout_hints.as_ref().unwrap().direction, "LR");
+    let edges: Vec<(&str, &str)> = g.edges.iter().map(|e| (e.source.as_str(), e.target.as_str())).collect();
+    assert_eq!(edges, vec![("A", "B"), ("B", "C")]);
+}
+
+#[test]
+fn test_parses_shapes_edges_subgraphs_and_classes() {
+    let g = parse_mermaid_flowchart(DOC).expect("flowchart");
+    let node = |id: &str| g.nodes.iter().find(|n| n.id == id).unwrap_or_else(|| panic!("node {}", id));
+
+    assert_eq!(g.layout_hints.as_ref().unwrap().direction, "LR");
+    assert_eq!(node("start").label, "Start");
+    assert_eq!(node("start").style.shape, "rounded");
+    assert_eq!(node("check").style.shape, "diamond");
+    assert_eq!(node("store").style.shape, "cylinder");
+    assert_eq!(node("api").style.shape, "circle");
+    assert_eq!(node("api").style.color, "#F87171");
+    assert_eq!(node("fix").style.color, "#FDE68A");
+
+    let edge = |s: &str, t: &str| g.edges.iter().find(|e| e.source == s && e.target == t).unwrap_or_else(|| panic!("edge {}->{}", s, t));
+    assert_eq!(edge("check", "store").label, "yes");
+    assert_eq!(edge("check", "fix").label, "no");
+    assert_eq!(edge("check", "fix").style.arrow, "end");
+    assert_eq!(edge("fix", "check").style.line, "dashed");
+    assert_eq!(edge("a", "c").style.line, "thick");
+    assert_eq!(edge("b", "c").style.line, "thick");
+    assert_eq!(g.edges.len(), 6);
+
+    let containers = g.containers.as_ref().expect("containers");
+    assert_eq!(containers.len(), 1);
+    assert_eq!(containers[0].id, "backend");
+    assert_eq!(containers[0].label, "Backend Services");
+    assert_eq!(containers[0].children, vec!["store".to_string(), "api".to_string()]);
+}
+
+#[test]
+fn test_export_round_trips_through_parser() {
+    let g = parse_mermaid_flowchart(DOC).expect("flowchart");
+    let text = graphdata_to_mermaid(&g);
+
+    assert!(text.starts_with("flowchart LR\n"));
+    assert!(text.contains("subgraph backend [\"Backend Services\"]"));
+    assert!(text.contains("check -->|\"yes\"| store"));
+    assert!(text.contains("fix -.-> check"));
+    assert!(text.contains("a ==> c"));
+    assert!(text.contains("style api fill:#F87171"));
+
+    let back = parse_mermaid_flowchart(&text).expect("re-parse");
+    assert_eq!(back.nodes.len(), g.nodes.len());
+    assert_eq!(back.edges.len(), g.edges.len());
+    for n in &g.nodes {
+        let m = back.nodes.iter().find(|m| m.id == n.id).expect("node survives");
+        assert_eq!((&m.label, &m.style.shape, &m.style.color), (&n.label, &n.style.shape, &n.style.color));
+    }
+    for e in &g.edges {
+        assert!(back.edges.iter().any(|f| f.source == e.source && f.target == e.target && f.label == e.label && f.style.line == e.style.line && f.style.arrow == e.style.arrow));
+    }
+    assert_eq!(back.containers.unwrap()[0].children, g.containers.unwrap()[0].children);
+}
+
+#[test]
+fn test_export_sanitizes_ids_and_escapes_labels() {
+    use GraphFlow::state::{EdgeData, GraphData, NodeData};
+
+    let g = GraphData {
+        nodes: vec![
+            NodeData { id: "end".into(), label: "Say \"bye\"".into(), ..Default::default() },
+            NodeData { id: "load-balancer".into(), label: "LB".into(), ..Default::default() },
+        ],
+        edges: vec![EdgeData { id: "e".into(), source: "load-balancer".into(), target: "end".into(), ..Default::default() }],
+        ..Default::default()
+    };
+    let text = graphdata_to_mermaid(&g);
+    assert!(text.contains("end_[\"Say #quot;bye#quot;\"]"));
+    assert!(text.contains("load_balancer --> end_"));
+    let back = parse_mermaid_flowchart(&text).expect("re-parse");
+    assert_eq!(back.nodes[0].label, "Say \"bye\"");
+}
@@ -0,0 +1,121 @@
+/// Mindmap Layout Tests
+///
+/// Verifies the radial mindmap layout (central topic at the origin, angular space proportional
+/// to subtree size, deeper levels on outer rings), branch color inheritance, and curved edges
+/// in the emitted Excalidraw scene.
+use GraphFlow::excalidraw::graphdata_to_excalidraw_scene;
+use GraphFlow::layout::approx_node_size;
+use GraphFlow::mindmap::style_mindmap;
+use GraphFlow::nodes::apply_auto_layout;
+use GraphFlow::state::{EdgeData, GraphData, NodeData};
+
+fn mindmap() -> GraphData {
+    let edges = [
+        ("topic", "big"), ("topic", "small"), ("topic", "mid"),
+        ("big", "b1"), ("big", "b2"), ("big", "b3"), ("big", "b4"),
+        ("mid", "m1"), ("mid", "m2"),
+        ("b2", "b2_detail"),
+    ];
+    let mut ids: Vec<&str> = vec!["topic"];
+    for (_, t) in &edges { ids.push(t); }
+    GraphData {
+        nodes: ids.iter().map(|id| NodeData { id: id.to_string(), label: id.to_string(), ..Default::default() }).collect(),
+        edges: edges
+            .iter()
+            .map(|(s, t)| EdgeData { id: format!("{}_{}", s, t), source: s.to_string(), target: t.to_string(), ..Default::default() })
+            .collect(),
+        ..Default::default()
+    }
+}
+
+fn polar(g: &GraphData, id: &str) -> (f64, f64) {
+    let n = g.nodes.iter().find(|n| n.id == id).unwrap();
+    let (x, y) = (n.x as f64, n.y as f64);
+    ((x * x + y * y).sqrt(), y.atan2(x))
+}
+
+fn angle_between(a: f64, b: f64) -> f64 {
+    let d = (a - b).abs() % (2.0 * std::f64::consts::PI);
+    d.min(2.0 * std::f64::consts::PI - d)
+}
+
+#[test]
+fn test_style_mindmap_selects_radial_and_inherits_branch_colors() {
+    let mut g = mindmap();
+    g.nodes[0].style.color = "#111111".into();
+    g.edges[0].style.line = "orthogonal dashed thick".into();
+    style_mindmap(&mut g);
+    assert_eq!(g.layout_hints.as_ref().unwrap().algorithm, "radial");
+    assert_eq!(g.edges[0].style.line, "curved dashed thick", "stroke and width survive");
+    assert!(g.edges[1..].iter().all(|e| e.style.line == "curved"));
+
+    let color = |id: &str| g.nodes.iter().find(|n| n.id == id).unwrap().style.color.clone();
+    assert_eq!(color("topic"), "#111111", "central topic keeps its color");
+    assert_eq!(color("b1"), color("big"));
+    assert_eq!(color("b2_detail"), color("big"));
+    assert_eq!(color("m2"), color("mid"));
+    assert_ne!(color("big"), color("mid"));
+    assert_ne!(color("big"), color("small"));
+
+    // A branch color chosen by the model is kept and inherited
+    let mut own = mindmap();
+    own.nodes.iter_mut().find(|n| n.id == "mid").unwrap().style.color = "#123456".into();
+    style_mindmap(&mut own);
+    let colors: Vec<&str> = own.nodes.iter().filter(|n| n.id.starts_with('m')).map(|n| n.style.color.as_str()).collect();
+    assert_eq!(colors, vec!["#123456"; 3]);
+
+    // An explicit tree layout is respected
+    let mut t = mindmap();
+    t.layout_hints = Some(GraphFlow::state::LayoutHints { algorithm: "tree".into(), ..Default::default() });
+    style_mindmap(&mut t);
+    assert_eq!(t.layout_hints.unwrap().algorithm, "tree");
+}
+
+#[test]
+fn test_radial_layout_rings_and_proportional_wedges() {
+    let mut g = mindmap();
+    style_mindmap(&mut g);
+    apply_auto_layout(&mut g, 180.0, 140.0, "TB", 4);
+
+    assert_eq!(polar(&g, "topic").0, 0.0, "central topic at the origin");
+    let ring1 = polar(&g, "big").0;
+    for id in ["small", "mid"] { assert!((polar(&g, id).0 - ring1).abs() < 0.5); }
+    assert!(polar(&g, "b1").0 > ring1);
+    assert!(polar(&g, "b2_detail").0 > polar(&g, "b2").0);
+
+    // "big" (4 leaves) owns far more angular space than "small" (1 leaf)
+    let spread = |a: &str, b: &str| angle_between(polar(&g, a).1, polar(&g, b).1);
+    assert!(spread("b1", "b4") > 2.0 * spread("m1", "m2"));
+    // Children stay inside their parent's wedge, next to it
+    assert!(spread("big", "b1") < spread("big", "m1"));
+
+    for (i, a) in g.nodes.iter().enumerate() {
+        for b in &g.nodes[i + 1..] {
+            let (aw, ah) = approx_node_size(&a.label);
+            let (bw, bh) = approx_node_size(&b.label);
+            let overlap = ((a.x - b.x).abs() as f64) < (aw + bw) / 2.0 && ((a.y - b.y).abs() as f64) < (ah + bh) / 2.0;
+            assert!(!overlap, "{} overlaps {}", a.id, b.id);
+        }
+    }
+}
+
+#[test]
+fn test_scene_draws_curved_edges() {
+    let mut g = mindmap();
+    style_mindmap(&mut g);
+    apply_auto_layout(&mut g, 180.0, 140.0, "TB", 4);
+    let scene = graphdata_to_excalidraw_scene(&g);
+    let arrow = scene["elements"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|el| el["id"] == "edge-topic_big")
+        .expect("edge element");
+    assert_eq!(arrow["roundness"]["type"], 2);
+    let pts = arrow["points"].as_array().unwrap();
+    assert_eq!(pts.len(), 3);
+    // The bend is off the straight line between the endpoints
+    let (x1, y1) = (pts[1][0].as_f64().unwrap(), pts[1][1].as_f64().unwrap());
+    let (x2, y2) = (pts[2][0].as_f64().unwrap(), pts[2][1].as_f64().unwrap());
+    assert!((x1 * y2 - y1 * x2).abs() > 1.0);
+}
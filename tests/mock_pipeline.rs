@@ -0,0 +1,82 @@
+/// Mock LLM Pipeline Tests
+///
+/// Runs the full `create_graph_flow` pipeline offline against the mock provider: rule-generated
+/// replies for arbitrary input, and fixture replies (keyed by prompt hash) that trigger the
+/// correction loop.
+use GraphFlow::llm::PROVIDER_ENV;
+use GraphFlow::mock_llm::{generate, prompt_hash, FIXTURES_ENV};
+use GraphFlow::nodes::generation_prompt;
+use GraphFlow::state::SharedState;
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+mod common;
+
+// One fixtures directory per test binary; env vars are process-wide
+fn fixtures_dir() -> &'static PathBuf {
+    static DIR: OnceLock<PathBuf> = OnceLock::new();
+    DIR.get_or_init(|| {
+        let dir = std::env::temp_dir().join(format!("graphflow-mock-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::env::set_var(PROVIDER_ENV, "mock");
+        std::env::set_var(FIXTURES_ENV, &dir);
+        dir
+    })
+}
+
+async fn run(content: &str, max_retries: Option<u32>) -> SharedState {
+    fixtures_dir();
+    let retries: Vec<(&str, serde_json::Value)> = max_retries.map(|n| ("llm_max_retries", json!(n))).into_iter().collect();
+    common::run_flow(content, &retries).await
+}
+
+#[tokio::test]
+async fn test_generated_graph_end_to_end() {
+    let content = "Customer -> Checkout page -> Payment approved?\nPayment approved? -> Orders database\nPayment approved? -> Checkout page";
+    let state = run(content, None).await;
+    let ai = &state.ai_response;
+    assert_eq!(ai.message.as_deref(), Some("ok"));
+    assert!(ai.diagnostics.is_empty() && ai.llm_retries.is_empty());
+    assert_eq!(ai.credits_cost, 5);
+
+    let g = ai.graph_data.as_ref().expect("graph");
+    let shapes: Vec<(&str, &str)> = g.nodes.iter().map(|n| (n.id.as_str(), n.style.shape.as_str())).collect();
+    assert_eq!(shapes, vec![("customer", "actor"), ("checkout_page", "rectangle"), ("payment_approved", "diamond"), ("orders_database", "cylinder")]);
+    let branches: Vec<&str> = g.edges.iter().filter(|e| e.source == "payment_approved").map(|e| e.label.as_str()).collect();
+    assert_eq!(branches, vec!["yes", "no"]);
+    assert!(g.nodes.iter().any(|n| n.x != 0.0 || n.y != 0.0), "the pipeline lays the graph out");
+
+    let containers: Vec<(&str, usize)> = g.containers.as_ref().unwrap().iter().map(|c| (c.id.as_str(), c.children.len())).collect();
+    assert_eq!(containers, vec![("people", 1), ("steps", 2), ("data", 1)]);
+    let icons: Vec<(&str, &str)> = g.decorations.as_ref().unwrap().iter().map(|d| (d.builtin.as_deref().unwrap(), d.target.as_deref().unwrap())).collect();
+    assert_eq!(icons, vec![("database", "orders_database"), ("salesperson", "customer")]);
+
+    // Same prompt, same reply
+    let prompt = generation_prompt(content);
+    assert_eq!(serde_json::to_string(&generate(&prompt)).unwrap(), serde_json::to_string(&generate(&prompt)).unwrap());
+}
+
+#[tokio::test]
+async fn test_fixture_reply_triggers_correction() {
+    // The fixture answers the first prompt with a dangling edge; the correction prompt has no
+    // fixture, so the generator's valid reply is accepted on the first retry.
+    let content = "Draft post -> Review post";
+    let fixture = fixtures_dir().join(format!("{}.txt", prompt_hash(&generation_prompt(content))));
+    std::fs::write(&fixture, r#"Here you go: {"nodes": [{"id": "draft_post", "label": "Draft post"}], "edges": [{"source": "draft_post", "target": "publish"}]}"#).unwrap();
+
+    let state = run(content, None).await;
+    let ai = &state.ai_response;
+    assert_eq!(ai.llm_retries.len(), 1);
+    assert!(ai.llm_retries[0].error.is_none());
+    assert!(ai.llm_retries[0].problems.iter().any(|p| p.contains("`publish`")), "{:?}", ai.llm_retries[0].problems);
+    assert_eq!(ai.credits_cost, 6, "one call plus one retry");
+    let ids: Vec<&str> = ai.graph_data.as_ref().unwrap().nodes.iter().map(|n| n.id.as_str()).collect();
+    assert_eq!(ids, vec!["draft_post", "review_post"]);
+
+    // Without retries the fixture's graph is kept and reported
+    let state = run(content, Some(0)).await;
+    assert!(state.ai_response.llm_retries.is_empty());
+    assert!(state.ai_response.message.unwrap().contains("unknown node `publish`"));
+    let _ = std::fs::remove_file(&fixture);
+}
@@ -12,6 +12,7 @@ use std::fs::read_to_string;
 /// broken specs from being deployed.
 
 use GraphFlow::server::ApiDoc;
+use utoipa::openapi::Server;
 use utoipa::OpenApi;
 use serde_json::Value;
 use openapiv3;
@@ -119,8 +120,12 @@ fn test_all_refs_are_resolvable() {
     let json = serde_json::to_string(&spec).expect("Failed to serialize OpenAPI spec");
     assert_all_component_refs_resolve("in-memory ApiDoc::openapi()", &json);
 
-    // Overwrite persisted openapi.json with the freshly generated spec so UI validates the same doc
-    std::fs::write("./openapi.json", &json).expect("Failed to write ./openapi.json");
+    // Overwrite persisted openapi.json with the freshly generated spec so UI validates the same doc.
+    // Written the way `run_server` does on the default port, so the committed file stays stable.
+    let mut persisted = spec.clone();
+    persisted.servers = Some(vec![Server::new("http://localhost:8080")]);
+    let pretty = serde_json::to_string_pretty(&persisted).expect("Failed to serialize OpenAPI spec");
+    std::fs::write("./openapi.json", &pretty).expect("Failed to write ./openapi.json");
 
     // Validate the just-persisted file as well
     if let Ok(file_json) = read_to_string("./openapi.json") {
@@ -0,0 +1,124 @@
+/// Orthogonal Routing Tests
+///
+/// Verifies that `route_edges` produces axis-aligned paths that go around node and container
+/// obstacles, attaches ports on the sides given by the layout direction, and spreads edges
+/// that share a side.
+use GraphFlow::routing::{port_sides, route_edges, Side};
+use GraphFlow::shapes::node_box;
+use GraphFlow::state::{Container, EdgeData, EdgeStyle, GraphData, LayoutHints, NodeData, NodeStyle};
+
+fn node(id: &str, x: f32, y: f32) -> NodeData {
+    NodeData { id: id.into(), label: id.to_uppercase(), x, y, style: NodeStyle { shape: "rectangle".into(), color: String::new() } }
+}
+
+fn edge(id: &str, s: &str, t: &str) -> EdgeData {
+    EdgeData { id: id.into(), source: s.into(), target: t.into(), label: String::new(), style: EdgeStyle { line: "orthogonal".into(), arrow: "end".into() } }
+}
+
+fn hints(direction: &str) -> Option<LayoutHints> {
+    Some(LayoutHints { direction: direction.into(), ..Default::default() })
+}
+
+// Does the axis-aligned segment a-b pass through the interior of the box (cx, cy, w, h)?
+fn crosses(a: (f64, f64), b: (f64, f64), (cx, cy, w, h): (f64, f64, f64, f64)) -> bool {
+    let (x0, x1) = (a.0.min(b.0), a.0.max(b.0));
+    let (y0, y1) = (a.1.min(b.1), a.1.max(b.1));
+    x1 > cx - w / 2.0 + 1.0 && x0 < cx + w / 2.0 - 1.0 && y1 > cy - h / 2.0 + 1.0 && y0 < cy + h / 2.0 - 1.0
+}
+
+fn assert_orthogonal(route: &[(f64, f64)]) {
+    assert!(route.len() >= 2);
+    for w in route.windows(2) {
+        assert!((w[0].0 - w[1].0).abs() < 0.01 || (w[0].1 - w[1].1).abs() < 0.01, "diagonal segment in {:?}", route);
+    }
+}
+
+#[test]
+fn test_routes_around_nodes_and_containers() {
+    // top -> bottom with a node and a foreign container in the way
+    let mut g = GraphData {
+        nodes: vec![node("top", 0.0, 0.0), node("blocker", 0.0, 160.0), node("bottom", 0.0, 320.0), node("side", 60.0, 480.0), node("end", 0.0, 640.0)],
+        edges: vec![edge("e1", "top", "bottom"), edge("e2", "bottom", "end")],
+        containers: Some(vec![Container { id: "zone".into(), label: "Zone".into(), children: vec!["side".into()], style: None }]),
+        layout_hints: hints("TB"),
+        ..Default::default()
+    };
+    let routes = route_edges(&g);
+    let e1 = routes[0].as_ref().expect("e1 routed");
+    assert_orthogonal(e1);
+    let blocker = &g.nodes[1];
+    let (w, h) = node_box(blocker);
+    assert!(!e1.windows(2).any(|s| crosses(s[0], s[1], (blocker.x as f64, blocker.y as f64, w, h))), "e1 avoids the blocker: {:?}", e1);
+    // leaves the bottom of its source and enters the top of its target
+    assert!((e1[0].1 - 24.0).abs() < 0.5 && (e1[e1.len() - 1].1 - (320.0 - 24.0)).abs() < 0.5);
+
+    let e2 = routes[1].as_ref().expect("e2 routed");
+    assert_orthogonal(e2);
+    let side = &g.nodes[3];
+    let (w, h) = node_box(side);
+    let zone = (side.x as f64, side.y as f64, w + 80.0, h + 80.0);
+    assert!(!e2.windows(2).any(|s| crosses(s[0], s[1], zone)), "e2 avoids the container: {:?}", e2);
+
+    // Straight/curved edges are not routed
+    g.edges[0].style.line = "curved".into();
+    assert!(route_edges(&g)[0].is_none());
+}
+
+#[test]
+fn test_port_sides_follow_direction() {
+    let size = (100.0, 48.0);
+    assert_eq!(port_sides("TB", (0.0, 0.0), size, (150.0, 200.0), size), (Side::Bottom, Side::Top));
+    assert_eq!(port_sides("LR", (0.0, 0.0), size, (150.0, 200.0), size), (Side::Right, Side::Left));
+    assert_eq!(port_sides("TB", (0.0, 0.0), size, (-300.0, 10.0), size), (Side::Left, Side::Right), "same rank connects side by side");
+    assert_eq!(port_sides("LR", (0.0, 0.0), size, (10.0, -200.0), size), (Side::Top, Side::Bottom));
+
+    let g = GraphData { nodes: vec![node("a", 0.0, 0.0), node("b", 300.0, 40.0)], edges: vec![edge("ab", "a", "b")], layout_hints: hints("LR"), ..Default::default() };
+    let r = route_edges(&g)[0].clone().expect("routed");
+    assert!((r[0].0 - 50.0).abs() < 0.5, "leaves the right side: {:?}", r);
+    assert!((r[r.len() - 1].0 - 250.0).abs() < 0.5, "enters the left side: {:?}", r);
+}
+
+#[test]
+fn test_parallel_edges_are_spread() {
+    let g = GraphData {
+        nodes: vec![node("hub", 0.0, 0.0), node("a", -200.0, 200.0), node("b", 0.0, 200.0), node("c", 200.0, 200.0)],
+        edges: vec![edge("ha", "hub", "a"), edge("hb", "hub", "b"), edge("hc", "hub", "c"), edge("hb2", "hub", "b")],
+        layout_hints: hints("TB"),
+        ..Default::default()
+    };
+    let routes: Vec<Vec<(f64, f64)>> = route_edges(&g).into_iter().map(|r| r.expect("routed")).collect();
+    let starts: Vec<f64> = routes.iter().map(|r| r[0].0).collect();
+    for i in 0..starts.len() {
+        for j in i + 1..starts.len() {
+            assert!((starts[i] - starts[j]).abs() > 5.0, "ports {} and {} share a spot: {:?}", i, j, starts);
+        }
+    }
+    // ordered by where the other end lies, so fan-out edges do not cross at the hub
+    assert!(starts[0] < starts[1] && starts[1] < starts[2]);
+    // the two hub -> b edges do not run on top of each other
+    let overlap = routes[1].windows(2).any(|s| {
+        routes[3].windows(2).any(|t| {
+            let same_x = (s[0].0 - s[1].0).abs() < 0.01 && (t[0].0 - t[1].0).abs() < 0.01 && (s[0].0 - t[0].0).abs() < 0.01;
+            let (a0, a1) = (s[0].1.min(s[1].1), s[0].1.max(s[1].1));
+            let (b0, b1) = (t[0].1.min(t[1].1), t[0].1.max(t[1].1));
+            same_x && a1.min(b1) - a0.max(b0) > 1.0
+        })
+    });
+    assert!(!overlap, "{:?} vs {:?}", routes[1], routes[3]);
+}
+
+#[test]
+fn test_large_graphs_fall_back_to_l_shapes() {
+    // A long diagonal chain: every node adds its own grid lines on both axes
+    let chain = |n: usize| GraphData {
+        nodes: (0..n).map(|i| node(&format!("n{}", i), i as f32 * 150.0, i as f32 * 100.0)).collect(),
+        edges: (1..n).map(|i| edge(&format!("e{}", i), &format!("n{}", i - 1), &format!("n{}", i))).collect(),
+        layout_hints: hints("TB"),
+        ..Default::default()
+    };
+    // Each search stays near its endpoints, so a long chain still routes every edge
+    let routes = route_edges(&chain(300));
+    assert!(routes.iter().all(|r| r.is_some()));
+    // Past the grid size cap nothing is routed and the emitter draws L-shapes instead
+    assert!(route_edges(&chain(700)).iter().all(|r| r.is_none()));
+}
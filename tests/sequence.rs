@@ -0,0 +1,117 @@
+/// Sequence Diagram Tests
+///
+/// Verifies the sequence extension of GraphData: conversion from plain edges, derived
+/// activation bars, and the dedicated Excalidraw emitter (lifelines, messages stacked in
+/// send order, fragment frames with alt sections).
+use GraphFlow::excalidraw::graphdata_to_excalidraw_scene;
+use GraphFlow::sequence::{derive_activations, ensure_sequence};
+use GraphFlow::state::{EdgeData, EdgeStyle, FragmentSection, GraphData, NodeData, Participant, SequenceData, SequenceFragment, SequenceMessage};
+use serde_json::Value;
+
+mod common;
+use common::element;
+
+fn msg(from: &str, to: &str, label: &str, kind: &str) -> SequenceMessage {
+    SequenceMessage { from: from.into(), to: to.into(), label: label.into(), kind: kind.into() }
+}
+
+fn login() -> SequenceData {
+    SequenceData {
+        participants: vec![
+            Participant { id: "user".into(), label: "User".into(), kind: Some("actor".into()) },
+            Participant { id: "api".into(), label: "API".into(), kind: None },
+            Participant { id: "auth".into(), label: "Auth Service".into(), kind: None },
+        ],
+        messages: vec![
+            msg("user", "api", "POST /login", "sync"),
+            msg("api", "auth", "verify(credentials)", "sync"),
+            msg("auth", "auth", "hash password", "sync"),
+            msg("auth", "api", "token", "return"),
+            msg("api", "user", "200 OK", "return"),
+            msg("auth", "api", "audit event", "async"),
+            msg("api", "user", "401", "return"),
+            msg("user", "api", "retry", "sync"),
+        ],
+        fragments: Some(vec![SequenceFragment {
+            kind: "alt".into(),
+            label: "valid credentials".into(),
+            start: 1,
+            end: 6,
+            sections: Some(vec![FragmentSection { label: "else".into(), start: 5 }]),
+        }]),
+        activations: None,
+    }
+}
+
+fn num(el: &Value, key: &str) -> f64 {
+    el[key].as_f64().unwrap()
+}
+
+#[test]
+fn test_ensure_sequence_from_edges_and_back() {
+    let mut g = GraphData {
+        nodes: vec![
+            NodeData { id: "a".into(), label: "Client".into(), ..Default::default() },
+            NodeData { id: "b".into(), label: "Server".into(), ..Default::default() },
+        ],
+        edges: vec![
+            EdgeData { id: "e0".into(), source: "a".into(), target: "b".into(), label: "GET".into(), ..Default::default() },
+            EdgeData { id: "e1".into(), source: "b".into(), target: "a".into(), label: "200".into(), style: EdgeStyle { line: "orthogonal dashed".into(), arrow: "end".into() } },
+        ],
+        ..Default::default()
+    };
+    ensure_sequence(&mut g);
+    let seq = g.sequence.as_ref().expect("sequence");
+    assert_eq!(seq.participants.iter().map(|p| p.label.as_str()).collect::<Vec<_>>(), vec!["Client", "Server"]);
+    assert_eq!((seq.messages[0].kind.as_str(), seq.messages[1].kind.as_str()), ("sync", "return"));
+
+    let mut only_seq = GraphData { sequence: Some(login()), ..Default::default() };
+    ensure_sequence(&mut only_seq);
+    assert_eq!(only_seq.nodes.len(), 3);
+    assert_eq!(only_seq.edges.len(), 8);
+    assert_eq!(only_seq.edges[3].style.line, "dashed");
+}
+
+#[test]
+fn test_activations_follow_calls_and_returns() {
+    let acts = derive_activations(&login());
+    let spans: Vec<(&str, usize, usize)> = acts.iter().map(|a| (a.participant.as_str(), a.start, a.end)).collect();
+    assert!(spans.contains(&("api", 0, 4)), "{:?}", spans);
+    assert!(spans.contains(&("auth", 1, 3)), "{:?}", spans);
+    // The nested self call ends with auth's reply to the API
+    assert!(spans.contains(&("auth", 2, 3)), "{:?}", spans);
+    // Never answered: ends at the receiver's last message
+    assert!(spans.contains(&("api", 7, 7)), "{:?}", spans);
+    assert_eq!(spans.len(), 4);
+}
+
+#[test]
+fn test_scene_draws_lifelines_ordered_messages_and_frames() {
+    let scene = graphdata_to_excalidraw_scene(&GraphData { sequence: Some(login()), ..Default::default() });
+
+    assert_eq!(element(&scene, "participant-user")["type"], "ellipse");
+    let lifeline = element(&scene, "lifeline-api");
+    assert_eq!(lifeline["strokeStyle"], "dashed");
+
+    // Messages are horizontal and stacked top to bottom in send order
+    let ys: Vec<f64> = (0..7).map(|i| num(element(&scene, &format!("message-{}", i)), "y")).collect();
+    assert!(ys.windows(2).all(|w| w[0] < w[1]), "{:?}", ys);
+    let call = element(&scene, "message-0");
+    assert_eq!(call["points"][1][1], 0.0);
+    assert_eq!(call["endArrowhead"], "triangle");
+    assert_eq!(element(&scene, "message-3")["strokeStyle"], "dashed");
+    assert_eq!(element(&scene, "message-5")["endArrowhead"], "arrow");
+    assert_eq!(element(&scene, "message-2")["points"].as_array().unwrap().len(), 4, "self call loops back");
+
+    // The alt frame encloses its messages; the else divider sits between the two sections
+    let frame = element(&scene, "fragment-0");
+    let (top, bottom) = (num(frame, "y"), num(frame, "y") + num(frame, "height"));
+    assert!(top < ys[1] && ys[6] < bottom && ys[0] < top);
+    let divider = num(element(&scene, "fragment-divider-0-0"), "y");
+    assert!(ys[4] < divider && divider < ys[5]);
+    assert_eq!(element(&scene, "fragment-tag-label-0")["text"], "alt");
+
+    // Activation bars cover the message range on the lifeline
+    let bar = element(&scene, "activation-0");
+    assert!(num(bar, "y") < ys[0] && ys[4] < num(bar, "y") + num(bar, "height"));
+}
@@ -0,0 +1,97 @@
+/// Node Shape Tests
+///
+/// Verifies that `NodeStyle.shape` picks the Excalidraw primitives for each shape, that scenes
+/// with composite shapes (cylinder, document, actor, ...) import back with the same shapes, and
+/// that arrows end on the actual outline instead of the bounding box.
+use GraphFlow::excalidraw::graphdata_to_excalidraw_scene;
+use GraphFlow::excalidraw_import::excalidraw_scene_to_graphdata;
+use GraphFlow::shapes::{shape_size, Shape};
+use GraphFlow::state::{EdgeData, EdgeStyle, GraphData, NodeData, NodeStyle};
+
+mod common;
+use common::element;
+
+const SHAPES: [&str; 10] = ["rectangle", "rounded", "ellipse", "circle", "diamond", "cylinder", "parallelogram", "hexagon", "document", "actor"];
+
+fn node(id: &str, shape: &str, x: f32, y: f32) -> NodeData {
+    NodeData { id: id.into(), label: id.to_uppercase(), x, y, style: NodeStyle { shape: shape.into(), color: "#BFDBFE".into() } }
+}
+
+fn edge(source: &str, target: &str) -> EdgeData {
+    EdgeData { id: format!("{}_{}", source, target), source: source.into(), target: target.into(), label: String::new(), style: EdgeStyle { line: "straight".into(), arrow: "end".into() } }
+}
+
+#[test]
+fn test_shape_names_and_sizes() {
+    assert_eq!(Shape::parse("database"), Shape::Cylinder);
+    assert_eq!(Shape::parse("Decision"), Shape::Diamond);
+    assert_eq!(Shape::parse("stick-figure"), Shape::Actor);
+    assert_eq!(Shape::parse("rect"), Shape::Rectangle);
+    assert_eq!(Shape::parse("unknown"), Shape::Rectangle);
+    for name in SHAPES {
+        assert_eq!(Shape::parse(name).name(), name);
+    }
+    let rect = shape_size(Shape::Rectangle, "Validate order");
+    let diamond = shape_size(Shape::Diamond, "Validate order");
+    assert!(diamond.0 > rect.0 && diamond.1 > rect.1, "diamonds leave room for the label");
+    let (w, h) = shape_size(Shape::Circle, "Start");
+    assert_eq!(w, h);
+}
+
+#[test]
+fn test_every_shape_is_drawn_and_round_trips() {
+    let nodes: Vec<NodeData> = SHAPES.iter().enumerate().map(|(i, s)| node(s, s, (i % 5) as f32 * 260.0, (i / 5) as f32 * 200.0)).collect();
+    let edges = vec![edge("rectangle", "diamond"), edge("diamond", "cylinder"), edge("actor", "document")];
+    let g = GraphData { nodes, edges, ..Default::default() };
+    let scene = graphdata_to_excalidraw_scene(&g);
+
+    let kind = |id: &str| element(&scene, &format!("node-{}", id))["type"].as_str().unwrap().to_string();
+    assert_eq!(kind("rectangle"), "rectangle");
+    assert!(element(&scene, "node-rectangle")["roundness"].is_null());
+    assert_eq!(element(&scene, "node-rounded")["roundness"]["type"], 3);
+    assert_eq!(kind("ellipse"), "ellipse");
+    assert_eq!(kind("circle"), "ellipse");
+    assert_eq!(kind("diamond"), "diamond");
+    for closed in ["cylinder", "parallelogram", "hexagon", "document"] {
+        // Bindable invisible frame carries the node; the outline is a closed line part
+        let frame = element(&scene, &format!("node-{}", closed));
+        assert_eq!((frame["type"].as_str(), frame["customData"]["shape"].as_str()), (Some("rectangle"), Some(closed)));
+        let el = element(&scene, &format!("node-outline-{}", closed));
+        assert_eq!(el["type"], "line");
+        let pts = el["points"].as_array().unwrap();
+        assert_eq!(pts.first(), pts.last(), "{} outline is closed", closed);
+        assert_eq!(el["groupIds"], frame["groupIds"]);
+    }
+    assert_eq!(element(&scene, "node-cap-cylinder")["type"], "ellipse");
+    assert_eq!(element(&scene, "node-head-actor")["type"], "ellipse");
+
+    let back = excalidraw_scene_to_graphdata(&scene).expect("import");
+    assert_eq!(back.nodes.len(), SHAPES.len(), "shape parts do not become nodes");
+    assert_eq!(back.edges.len(), 3, "outline parts are not edges");
+    for n in &g.nodes {
+        let m = back.nodes.iter().find(|m| m.id == n.id).expect("node id survives");
+        assert_eq!(m.style.shape, n.style.shape);
+        assert_eq!(m.label, n.label);
+        assert!((m.x - n.x).abs() < 0.5 && (m.y - n.y).abs() < 0.5, "{} center preserved", n.id);
+    }
+}
+
+#[test]
+fn test_arrows_end_on_the_outline() {
+    // Diagonal edge: a bounding-box intersection would stop well outside the diamond/ellipse
+    let g = GraphData { nodes: vec![node("a", "diamond", 0.0, 0.0), node("b", "ellipse", 300.0, 200.0)], edges: vec![edge("a", "b")], ..Default::default() };
+    let scene = graphdata_to_excalidraw_scene(&g);
+    let arrow = element(&scene, "edge-a_b");
+    let (ox, oy) = (arrow["x"].as_f64().unwrap(), arrow["y"].as_f64().unwrap());
+    let pts = arrow["points"].as_array().unwrap();
+    let end = pts.last().unwrap();
+    let (ex, ey) = (ox + end[0].as_f64().unwrap(), oy + end[1].as_f64().unwrap());
+
+    let (dw, dh) = shape_size(Shape::Diamond, "A");
+    let diamond = ox.abs() / (dw / 2.0) + oy.abs() / (dh / 2.0);
+    assert!((diamond - 1.0).abs() < 0.05, "arrow starts on the diamond border ({})", diamond);
+
+    let (ew, eh) = shape_size(Shape::Ellipse, "B");
+    let ellipse = (((ex - 300.0) / (ew / 2.0)).powi(2) + ((ey - 200.0) / (eh / 2.0)).powi(2)).sqrt();
+    assert!((ellipse - 1.0).abs() < 0.05, "arrow ends on the ellipse border ({})", ellipse);
+}
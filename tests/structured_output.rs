@@ -0,0 +1,89 @@
+/// Structured Output Tests
+///
+/// Checks the GraphData JSON Schema derived from the `ToSchema` types, and that generation
+/// requests carry it as `response_format: json_schema` on a structured-output route, while a route
+/// with `"structured_output": false` keeps plain JSON mode.
+use axum::extract::State;
+use axum::routing::post;
+use axum::{Json, Router};
+use GraphFlow::llm::{RouteKey, Routed, BASE_URL_ENV, CONFIG_ENV};
+use GraphFlow::llm_schema::graph_data_schema;
+use GraphFlow::state::UserTier;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+
+mod common;
+
+const REPLY: &str = r#"{"nodes":[{"id":"order","label":"Order"},{"id":"ship","label":"Ship"}],"edges":[{"source":"order","target":"ship"}]}"#;
+
+async fn completions(State(seen): State<Arc<Mutex<Vec<Value>>>>, Json(body): Json<Value>) -> Json<Value> {
+    seen.lock().unwrap().push(body.clone());
+    Json(json!({
+        "id": "chatcmpl-stub", "object": "chat.completion", "created": 0, "model": body["model"],
+        "choices": [{"index": 0, "message": {"role": "assistant", "content": REPLY}, "finish_reason": "stop"}]
+    }))
+}
+
+// Every schema node, depth first
+fn walk<'a>(value: &'a Value, out: &mut Vec<&'a Value>) {
+    out.push(value);
+    match value {
+        Value::Object(map) => map.values().for_each(|v| walk(v, out)),
+        Value::Array(items) => items.iter().for_each(|v| walk(v, out)),
+        _ => {}
+    }
+}
+
+#[test]
+fn test_graph_data_schema_is_plain_json_schema() {
+    let schema = graph_data_schema();
+    assert_eq!(schema["type"], "object");
+    let required: Vec<&str> = schema["required"].as_array().unwrap().iter().filter_map(|r| r.as_str()).collect();
+    assert!(required.contains(&"nodes") && required.contains(&"edges"), "{:?}", required);
+    assert_eq!(schema["properties"]["nodes"]["items"]["type"], "object", "node refs are inlined");
+    assert!(schema["properties"]["nodes"]["items"]["properties"]["id"].is_object());
+
+    let mut all = Vec::new();
+    walk(schema, &mut all);
+    for node in all.iter().filter_map(|v| v.as_object()) {
+        assert!(!node.contains_key("$ref") && !node.contains_key("nullable") && !node.contains_key("example"), "{:?}", node);
+        assert!(!matches!(node.get("format").and_then(|f| f.as_str()), Some("float" | "double" | "int32" | "int64")));
+    }
+    let hints = &schema["properties"]["layout_hints"]["anyOf"];
+    assert_eq!(hints[1], json!({"type": "null"}), "optional objects may be null");
+}
+
+#[tokio::test]
+async fn test_requests_carry_schema_per_route() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let app = Router::new().route("/v1/chat/completions", post(completions)).with_state(seen.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let routes = std::env::temp_dir().join(format!("graphflow-structured-{}.json", std::process::id()));
+    let config = json!({"routes": [
+        {"provider": "local", "model": "m"},
+        {"name": "plain", "provider": "local", "model": "m", "structured_output": false}
+    ]});
+    std::fs::write(&routes, config.to_string()).unwrap();
+    std::env::set_var(CONFIG_ENV, &routes);
+    std::env::set_var(BASE_URL_ENV, format!("http://{}/v1/", addr));
+
+    let result = common::run_flow("Order -> Ship", &[]).await;
+    assert_eq!(result.ai_response.graph_data.expect("graph").nodes.len(), 2);
+
+    // The named route opts out: same schema offered, plain JSON mode sent
+    let plain = Routed::resolve(&RouteKey { tier: &UserTier::Free, kind: "flow", route: Some("plain") }).unwrap();
+    assert!(!plain.structured());
+    plain.complete("draw", Some(graph_data_schema()), None).await.unwrap();
+
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.len(), 2);
+    assert_eq!(seen[0]["response_format"]["type"], "json_schema");
+    assert_eq!(&seen[0]["response_format"]["json_schema"]["schema"], graph_data_schema());
+    assert_eq!(seen[1]["response_format"], json!({"type": "json_object"}));
+    std::env::remove_var(CONFIG_ENV);
+    std::env::remove_var(BASE_URL_ENV);
+    let _ = std::fs::remove_file(&routes);
+}
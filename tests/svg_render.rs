@@ -0,0 +1,83 @@
+/// Native SVG/PNG Rendering Tests
+///
+/// Checks that the in-process renderer turns scenes produced by
+/// `graphdata_to_excalidraw_scene` into standalone SVG and PNG without Node/Puppeteer.
+use GraphFlow::excalidraw::graphdata_to_excalidraw_scene;
+use GraphFlow::state::{Container, EdgeData, EdgeStyle, GraphData, NodeData, NodeStyle};
+use GraphFlow::svg::scene_to_svg;
+use serde_json::json;
+
+fn sample_graph() -> GraphData {
+    let node = |id: &str, label: &str, x: f32, y: f32| NodeData {
+        id: id.into(),
+        label: label.into(),
+        x,
+        y,
+        style: NodeStyle { shape: "rect".into(), color: "#F3F4F6".into() },
+    };
+    GraphData {
+        nodes: vec![node("a", "Start", 0.0, 0.0), node("b", "Fish & Chips", 0.0, 180.0)],
+        edges: vec![EdgeData {
+            id: "a_b".into(),
+            source: "a".into(),
+            target: "b".into(),
+            label: "next".into(),
+            style: EdgeStyle { line: "orthogonal".into(), arrow: "end".into() },
+        }],
+        containers: Some(vec![Container { id: "grp".into(), label: "Group".into(), children: vec!["a".into(), "b".into()], style: None }]),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_scene_renders_to_standalone_svg() {
+    let scene = graphdata_to_excalidraw_scene(&sample_graph());
+    let svg = scene_to_svg(&scene);
+
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(svg.trim_end().ends_with("</svg>"));
+    // container + two nodes
+    assert_eq!(svg.matches("<rect x=").count(), 1 + 3, "background + container + 2 nodes");
+    // arrow body and its arrowhead
+    assert!(svg.contains("<path d=\"M"));
+    // labels are escaped
+    assert!(svg.contains(">Fish &amp; Chips</tspan>"));
+    assert!(svg.contains(">next</tspan>"));
+    assert!(svg.contains(">Group</tspan>"));
+}
+
+#[test]
+fn test_svg_embeds_image_files_and_skips_deleted() {
+    let scene = json!({
+        "type": "excalidraw",
+        "elements": [
+            {"type": "image", "id": "img", "x": 0, "y": 0, "width": 24, "height": 24, "fileId": "f1"},
+            {"type": "ellipse", "id": "gone", "x": 0, "y": 0, "width": 10, "height": 10, "isDeleted": true},
+            {"type": "diamond", "id": "d", "x": 40, "y": 0, "width": 40, "height": 40, "strokeStyle": "dashed"}
+        ],
+        "appState": {"viewBackgroundColor": "#0B1021"},
+        "files": {"f1": {"id": "f1", "dataURL": "data:image/png;base64,AAAA", "mimeType": "image/png"}}
+    });
+    let svg = scene_to_svg(&scene);
+
+    assert!(svg.contains("href=\"data:image/png;base64,AAAA\""));
+    assert!(!svg.contains("<ellipse"));
+    assert!(svg.contains("<polygon"));
+    assert!(svg.contains("stroke-dasharray"));
+    assert!(svg.contains("fill=\"#0B1021\""));
+}
+
+#[test]
+fn test_png_rasterization_honors_scale() {
+    use GraphFlow::render::{scene_to_png, RasterOptions};
+
+    let scene = graphdata_to_excalidraw_scene(&sample_graph());
+    let png_1x = scene_to_png(&scene, &RasterOptions::default()).expect("1x render");
+    let png_2x = scene_to_png(&scene, &RasterOptions::new(None, Some(192.0), Some("transparent".into()))).expect("2x render");
+
+    assert_eq!(&png_1x[..8], b"\x89PNG\r\n\x1a\n");
+    // IHDR width lives at bytes 16..20
+    let width = |png: &[u8]| u32::from_be_bytes([png[16], png[17], png[18], png[19]]);
+    assert!(width(&png_1x) > 0);
+    assert_eq!(width(&png_2x), width(&png_1x) * 2);
+}
@@ -0,0 +1,65 @@
+/// Text Metrics Tests
+///
+/// Verifies per-font widths (counted per glyph, not per byte), word wrapping of long labels,
+/// and that scene labels and node boxes are sized from the same wrapped text.
+use GraphFlow::excalidraw::graphdata_to_excalidraw_scene;
+use GraphFlow::layout::approx_node_size;
+use GraphFlow::text::{node_label, text_width, wrap, Font, MAX_NODE_TEXT_WIDTH};
+use GraphFlow::state::{GraphData, NodeData, NodeStyle};
+
+#[test]
+fn test_widths_per_font_and_script() {
+    // Proportional fonts: "i" is narrower than "m"; the monospace font is even
+    assert!(text_width("iiii", Font::Helvetica, 16.0) < text_width("mmmm", Font::Helvetica, 16.0));
+    assert_eq!(text_width("iiii", Font::Cascadia, 16.0), text_width("mmmm", Font::Cascadia, 16.0));
+    assert_eq!(Font::from_family(Font::Helvetica.family()), Font::Helvetica);
+    // Multi-byte text is measured per glyph: accents add nothing, CJK is full width
+    let plain = text_width("cafe", Font::Virgil, 16.0);
+    assert!((text_width("cafe\u{301}", Font::Virgil, 16.0) - plain).abs() < 1e-9);
+    assert!((text_width("数据库", Font::Virgil, 16.0) - 48.0).abs() < 1e-9);
+    // A ZWJ emoji sequence is one glyph
+    let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+    assert!((text_width(family, Font::Virgil, 16.0) - text_width("\u{1F468}", Font::Virgil, 16.0)).abs() < 1e-9);
+    // Width of multi-line text is its widest line
+    assert_eq!(text_width("ab\nabcd", Font::Virgil, 16.0), text_width("abcd", Font::Virgil, 16.0));
+}
+
+#[test]
+fn test_wrapping() {
+    let lines = wrap("Validate the incoming order against inventory and pricing rules", Font::Virgil, 16.0, 200.0);
+    assert!(lines.len() > 1);
+    assert!(lines.iter().all(|l| text_width(l, Font::Virgil, 16.0) <= 200.0));
+    assert_eq!(lines.join(" "), "Validate the incoming order against inventory and pricing rules");
+    // Explicit newlines are kept; an overlong word is broken between characters
+    assert_eq!(wrap("API\nGateway", Font::Virgil, 16.0, 200.0), vec!["API", "Gateway"]);
+    let long = wrap(&"x".repeat(60), Font::Virgil, 16.0, 100.0);
+    assert!(long.len() > 1 && long.concat() == "x".repeat(60));
+    // CJK breaks between characters without spaces
+    let cjk = wrap("用户提交订单后系统校验库存", Font::Virgil, 16.0, 80.0);
+    assert_eq!(cjk.concat(), "用户提交订单后系统校验库存");
+    assert!(cjk.iter().all(|l| l.chars().count() <= 5));
+}
+
+#[test]
+fn test_node_boxes_and_scene_labels_agree() {
+    let short = approx_node_size("Start");
+    assert_eq!(short.1, 48.0, "single-line labels keep the standard height");
+    let label = "Reconcile ledger entries with the bank statement export";
+    let (w, h) = approx_node_size(label);
+    assert!(w <= MAX_NODE_TEXT_WIDTH + 30.0, "long labels wrap instead of widening the box");
+    assert!(h > 48.0, "wrapped labels make the box taller");
+
+    let g = GraphData {
+        nodes: vec![NodeData { id: "n".into(), label: label.into(), x: 0.0, y: 0.0, style: NodeStyle { shape: "rectangle".into(), color: String::new() } }],
+        ..Default::default()
+    };
+    let scene = graphdata_to_excalidraw_scene(&g);
+    let els = scene["elements"].as_array().unwrap();
+    let text = els.iter().find(|e| e["id"] == "node-label-n").unwrap();
+    let node = els.iter().find(|e| e["id"] == "node-n").unwrap();
+    assert_eq!(text["originalText"], label);
+    assert_eq!(text["text"].as_str().unwrap(), node_label(label).text());
+    assert!(text["text"].as_str().unwrap().contains('\n'));
+    assert!(text["width"].as_f64().unwrap() <= node["width"].as_f64().unwrap());
+    assert!(text["height"].as_f64().unwrap() <= node["height"].as_f64().unwrap());
+}
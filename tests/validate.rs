@@ -0,0 +1,81 @@
+/// GraphData Validation Tests
+///
+/// Verifies that `validate` reports each structural rule the prompt asks the LLM to follow,
+/// and that `repair` fixes what it can while leaving intentional self-loops alone.
+use GraphFlow::state::{Container, Decoration, EdgeData, EdgeStyle, GraphData, NodeData, NodeStyle};
+use GraphFlow::validate::{repair, summarize, validate, DiagnosticKind, Severity};
+
+fn node(id: &str) -> NodeData {
+    NodeData { id: id.into(), label: id.to_uppercase(), x: 0.0, y: 0.0, style: NodeStyle { shape: "rectangle".into(), color: String::new() } }
+}
+
+fn edge(id: &str, source: &str, target: &str) -> EdgeData {
+    EdgeData { id: id.into(), source: source.into(), target: target.into(), label: String::new(), style: EdgeStyle { line: "orthogonal".into(), arrow: "end".into() } }
+}
+
+fn container(id: &str, children: &[&str]) -> Container {
+    Container { id: id.into(), label: id.into(), children: children.iter().map(|c| c.to_string()).collect(), style: None }
+}
+
+fn messy() -> GraphData {
+    GraphData {
+        nodes: vec![node("a"), node("b"), node("a"), node("c")],
+        edges: vec![
+            edge("e1", "a", "b"),
+            edge("e1", "b", "c"),
+            edge("e3", "a", "b"),
+            edge("e4", "c", "payment_service"),
+            edge("e5", "ghost", "phantom"),
+            edge("e6", "c", "c"),
+        ],
+        containers: Some(vec![container("left", &["a", "b", "missing"]), container("right", &["b", "c"])]),
+        decorations: Some(vec![Decoration::default(); 5]),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_validate_reports_every_rule() {
+    let clean = GraphData { nodes: vec![node("a"), node("b")], edges: vec![edge("ab", "a", "b")], ..Default::default() };
+    assert!(validate(&clean).is_empty());
+    assert_eq!(summarize(&validate(&clean)), None);
+
+    let diags = validate(&messy());
+    let count = |k: DiagnosticKind| diags.iter().filter(|d| d.kind == k).count();
+    assert_eq!(count(DiagnosticKind::DuplicateNodeId), 1);
+    assert_eq!(count(DiagnosticKind::DuplicateEdgeId), 1);
+    assert_eq!(count(DiagnosticKind::DuplicateEdge), 1);
+    assert_eq!(count(DiagnosticKind::DanglingEdge), 3, "one per missing endpoint");
+    assert_eq!(count(DiagnosticKind::SelfLoop), 1);
+    assert_eq!(count(DiagnosticKind::UnknownContainerChild), 1);
+    assert_eq!(count(DiagnosticKind::OverlappingContainers), 1);
+    assert_eq!(count(DiagnosticKind::TooManyDecorations), 1);
+    let dangling = diags.iter().find(|d| d.kind == DiagnosticKind::DanglingEdge).unwrap();
+    assert_eq!((dangling.severity, dangling.subject.as_str()), (Severity::Error, "e4"));
+    assert!(diags.iter().all(|d| !d.repaired));
+}
+
+#[test]
+fn test_repair_fixes_what_it_can() {
+    let mut g = messy();
+    let diags = repair(&mut g);
+
+    // Everything but the self-loop is fixed, and the report says so
+    let remaining = validate(&g);
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].kind, DiagnosticKind::SelfLoop);
+    assert!(diags.iter().all(|d| d.repaired == (d.kind != DiagnosticKind::SelfLoop)));
+
+    let ids: Vec<&str> = g.nodes.iter().map(|n| n.id.as_str()).collect();
+    assert_eq!(ids, vec!["a", "b", "c", "payment_service"], "duplicates dropped, missing endpoint synthesized");
+    assert_eq!(g.nodes[3].label, "Payment service");
+    let edges: Vec<(&str, &str, &str)> = g.edges.iter().map(|e| (e.id.as_str(), e.source.as_str(), e.target.as_str())).collect();
+    assert_eq!(edges, vec![("e1", "a", "b"), ("e1_2", "b", "c"), ("e4", "c", "payment_service"), ("e6", "c", "c")]);
+    let containers = g.containers.as_ref().unwrap();
+    assert_eq!(containers[0].children, vec!["a", "b"]);
+    assert_eq!(containers[1].children, vec!["c"]);
+    assert_eq!(g.decorations.as_ref().unwrap().len(), 3);
+
+    let summary = summarize(&diags).unwrap();
+    assert!(summary.starts_with(&format!("{} issues ({} repaired)", diags.len(), diags.len() - 1)), "{}", summary);
+}